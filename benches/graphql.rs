@@ -6,11 +6,18 @@ extern crate graphql_parser;
 use std::fs::File;
 use std::io::Read;
 
-use graphql_parser::parse_query;
+use graphql_parser::{parse_query, parse_schema};
 
 fn load_file(name: &str) -> String {
+    load(&format!("tests/queries/{}.graphql", name))
+}
+
+fn load_schema(name: &str) -> String {
+    load(&format!("tests/schemas/{}.graphql", name))
+}
+
+fn load(path: &str) -> String {
     let mut buf = String::with_capacity(1024);
-    let path = format!("tests/queries/{}.graphql", name);
     let mut f = File::open(path).unwrap();
     f.read_to_string(&mut buf).unwrap();
     buf
@@ -45,3 +52,63 @@ fn bench_kitchen_sink(b: &mut test::Bencher) {
     let f = load_file("kitchen-sink");
     b.iter(|| parse_query::<String>(&f).unwrap());
 }
+
+#[bench]
+fn bench_kitchen_sink_str(b: &mut test::Bencher) {
+    let f = load_file("kitchen-sink");
+    b.iter(|| parse_query::<&str>(&f).unwrap());
+}
+
+#[bench]
+fn bench_schema_minimal(b: &mut test::Bencher) {
+    let f = load_schema("minimal");
+    b.iter(|| parse_schema::<String>(&f).unwrap());
+}
+
+#[bench]
+fn bench_schema_directive_descriptions(b: &mut test::Bencher) {
+    let f = load_schema("directive_descriptions");
+    b.iter(|| parse_schema::<String>(&f).unwrap());
+}
+
+#[bench]
+fn bench_schema_kitchen_sink(b: &mut test::Bencher) {
+    let f = load_schema("kitchen-sink");
+    b.iter(|| parse_schema::<String>(&f).unwrap());
+}
+
+#[bench]
+fn bench_schema_kitchen_sink_str(b: &mut test::Bencher) {
+    let f = load_schema("kitchen-sink");
+    b.iter(|| parse_schema::<&str>(&f).unwrap());
+}
+
+#[cfg(feature = "arena")]
+fn bench_arena(name: &str, b: &mut test::Bencher) {
+    use graphql_parser::query::arena;
+
+    let f = load_file(name);
+    let mut bump = arena::Bump::new();
+    b.iter(|| {
+        arena::parse_query(&f, &bump).unwrap();
+        bump.reset();
+    });
+}
+
+#[cfg(feature = "arena")]
+#[bench]
+fn bench_minimal_arena(b: &mut test::Bencher) {
+    bench_arena("minimal", b);
+}
+
+#[cfg(feature = "arena")]
+#[bench]
+fn bench_directive_args_arena(b: &mut test::Bencher) {
+    bench_arena("directive_args", b);
+}
+
+#[cfg(feature = "arena")]
+#[bench]
+fn bench_kitchen_sink_arena(b: &mut test::Bencher) {
+    bench_arena("kitchen-sink", b);
+}
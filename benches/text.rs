@@ -0,0 +1,105 @@
+//! Parsing speed and memory footprint of a large schema for different
+//! `Text` implementations
+//!
+//! Memory retained by the parsed document is printed once per benchmark,
+//! run with `cargo bench --features intern --bench text -- --nocapture`
+//! to see it.
+#![feature(test)]
+extern crate test;
+
+extern crate graphql_parser;
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::borrow::Cow;
+use std::fmt::Write;
+use std::rc::Rc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::Arc;
+
+use graphql_parser::parse_schema;
+use graphql_parser::schema::{Document, Text};
+
+struct Counting;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for Counting {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        ALLOCATED.fetch_sub(layout.size(), Ordering::Relaxed);
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static GLOBAL: Counting = Counting;
+
+fn large_schema() -> String {
+    let mut buf = String::with_capacity(1 << 20);
+    for i in 0..500 {
+        write!(
+            buf,
+            "type User{i} implements Node & Entity @key(fields: \"id\") {{
+                id: ID!
+                name: String
+                email: String @deprecated(reason: \"Use contacts\")
+                friends(first: Int = 10, after: String): [User{next}!]!
+                node(id: ID!): Node
+            }}
+            input UserFilter{i} {{ name: String, email: String, limit: Int }}
+            ",
+            i = i,
+            next = (i + 1) % 500,
+        )
+        .unwrap();
+    }
+    buf
+}
+
+fn bench_text<'a, T: Text<'a>>(name: &str, schema: &'a str, b: &mut test::Bencher) {
+    let before = ALLOCATED.load(Ordering::SeqCst);
+    let doc: Document<'a, T> = parse_schema(schema).unwrap();
+    let retained = ALLOCATED.load(Ordering::SeqCst) - before;
+    eprintln!(
+        "{}: {} KiB retained for {} KiB of source",
+        name,
+        retained / 1024,
+        schema.len() / 1024,
+    );
+    drop(doc);
+    b.iter(|| parse_schema::<T>(schema).unwrap());
+}
+
+#[bench]
+fn bench_large_str(b: &mut test::Bencher) {
+    bench_text::<&str>("&str", &large_schema(), b);
+}
+
+#[bench]
+fn bench_large_string(b: &mut test::Bencher) {
+    bench_text::<String>("String", &large_schema(), b);
+}
+
+#[bench]
+fn bench_large_cow(b: &mut test::Bencher) {
+    bench_text::<Cow<str>>("Cow<str>", &large_schema(), b);
+}
+
+#[bench]
+fn bench_large_arc(b: &mut test::Bencher) {
+    bench_text::<Arc<str>>("Arc<str>", &large_schema(), b);
+}
+
+#[bench]
+fn bench_large_rc(b: &mut test::Bencher) {
+    bench_text::<Rc<str>>("Rc<str>", &large_schema(), b);
+}
+
+#[cfg(feature = "intern")]
+#[bench]
+fn bench_large_interned(b: &mut test::Bencher) {
+    bench_text::<graphql_parser::Interned>("Interned", &large_schema(), b);
+}
@@ -0,0 +1,166 @@
+//! Procedural macros checking graphql at compile time
+//!
+//! Queries and schemas embedded in the code are parsed when the crate is
+//! compiled, a syntax error becomes a compile error:
+//!
+//! ```rust
+//! use graphql_parser_macros::{graphql_query, graphql_schema};
+//!
+//! const USER: &str = graphql_query!("query User($id: ID!) { user(id: $id) { name } }");
+//! const SCHEMA: &str = graphql_schema!("type Query { user(id: ID!): User }");
+//! ```
+//!
+//! ```compile_fail
+//! use graphql_parser_macros::graphql_query;
+//!
+//! const USER: &str = graphql_query!("query { user(id: ) }");
+//! ```
+//!
+//! Files are checked with `include_query!` and `include_schema!`. Paths are
+//! relative to the directory containing the `Cargo.toml` of the crate.
+//! Macros expand to the original string, so the parsed document isn't kept
+//! in the binary.
+use std::env;
+use std::fs;
+use std::path::Path;
+
+use proc_macro2::{Span, TokenStream};
+use quote::quote;
+use syn::{parse_macro_input, LitStr};
+
+use graphql_parser::{parse_query, parse_schema, Pos};
+
+#[derive(Clone, Copy)]
+enum Kind {
+    Query,
+    Schema,
+}
+
+/// Checks that the string literal is a valid query and returns it
+#[proc_macro]
+pub fn graphql_query(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    expand(inline(&literal, Kind::Query))
+}
+
+/// Checks that the string literal is a valid schema and returns it
+#[proc_macro]
+pub fn graphql_schema(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let literal = parse_macro_input!(input as LitStr);
+    expand(inline(&literal, Kind::Schema))
+}
+
+/// Checks that the file is a valid query and includes it as a string
+#[proc_macro]
+pub fn include_query(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    expand(include(&path, Kind::Query))
+}
+
+/// Checks that the file is a valid schema and includes it as a string
+#[proc_macro]
+pub fn include_schema(input: proc_macro::TokenStream) -> proc_macro::TokenStream {
+    let path = parse_macro_input!(input as LitStr);
+    expand(include(&path, Kind::Schema))
+}
+
+fn expand(result: syn::Result<TokenStream>) -> proc_macro::TokenStream {
+    result.unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+/// Returns error message and position of the error, if any
+fn check(source: &str, kind: Kind) -> Result<(), (String, Pos)> {
+    let error = match kind {
+        Kind::Query => parse_query::<&str>(source)
+            .err()
+            .map(|e| (e.to_string(), e.position())),
+        Kind::Schema => parse_schema::<&str>(source)
+            .err()
+            .map(|e| (e.to_string(), e.position())),
+    };
+    match error {
+        Some((message, position)) => Err((message.trim_end().to_string(), position)),
+        None => Ok(()),
+    }
+}
+
+fn inline(literal: &LitStr, kind: Kind) -> syn::Result<TokenStream> {
+    let source = literal.value();
+    match check(&source, kind) {
+        Ok(()) => Ok(quote!(#literal)),
+        Err((message, position)) => {
+            let span = error_span(literal, &source, position);
+            Err(syn::Error::new(span, message))
+        }
+    }
+}
+
+/// Returns span of the error inside of the literal, if the compiler can
+/// point there, otherwise span of the whole literal
+fn error_span(literal: &LitStr, source: &str, position: Pos) -> Span {
+    let token = literal.token();
+    let text = token.to_string();
+    // Skip `"` or `r#"`, offsets only match if there are no escapes
+    let start = match text.find('"') {
+        Some(idx) => idx + 1,
+        None => return literal.span(),
+    };
+    if text.get(start..start + source.len()) != Some(source) {
+        return literal.span();
+    }
+    let offset = start + position.byte_offset(source);
+    let end = text[offset..]
+        .chars()
+        .next()
+        .map_or(offset, |c| offset + c.len_utf8());
+    token.subspan(offset..end).unwrap_or_else(|| literal.span())
+}
+
+fn include(path: &LitStr, kind: Kind) -> syn::Result<TokenStream> {
+    let dir = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".into());
+    let full_path = Path::new(&dir).join(path.value());
+    let source = fs::read_to_string(&full_path).map_err(|e| {
+        syn::Error::new(
+            path.span(),
+            format!("couldn't read {}: {}", full_path.display(), e),
+        )
+    })?;
+    check(&source, kind).map_err(|(message, _)| {
+        syn::Error::new(path.span(), format!("{}: {}", full_path.display(), message))
+    })?;
+    // `include_str` makes cargo rebuild the crate when the file changes
+    let full_path = full_path.to_string_lossy();
+    Ok(quote!(include_str!(#full_path)))
+}
+
+#[cfg(test)]
+mod test {
+    use proc_macro2::Span;
+    use syn::LitStr;
+
+    use super::{include, inline, Kind};
+
+    fn error(result: syn::Result<proc_macro2::TokenStream>) -> String {
+        result.unwrap_err().to_string()
+    }
+
+    #[test]
+    fn inline_error() {
+        let literal = LitStr::new("{\n  user(id: ) }", Span::call_site());
+        assert_eq!(
+            error(inline(&literal, Kind::Query)),
+            "query parse error: Parse error at 2:12\nUnexpected `)[Punctuator]`\n\
+             Expected IntValue, FloatValue, StringValue, BlockString, true, false, null or Name"
+        );
+        let literal = LitStr::new("type Query {", Span::call_site());
+        assert!(error(inline(&literal, Kind::Schema)).starts_with("schema parse error"));
+    }
+
+    #[test]
+    fn include_error() {
+        let literal = LitStr::new("tests/missing.graphql", Span::call_site());
+        assert!(error(include(&literal, Kind::Query)).starts_with("couldn't read "));
+        let literal = LitStr::new("../tests/queries/kitchen-sink.graphql", Span::call_site());
+        assert!(error(include(&literal, Kind::Schema)).contains("schema parse error"));
+    }
+}
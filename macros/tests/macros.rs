@@ -0,0 +1,28 @@
+use graphql_parser::{parse_query, parse_schema};
+use graphql_parser_macros::{graphql_query, graphql_schema, include_query, include_schema};
+
+const QUERY: &str = graphql_query!("query User($id: ID!) { user(id: $id) { name } }");
+const RAW_QUERY: &str = graphql_query!(r#"{ user(name: "x") { id } }"#);
+const SCHEMA: &str = graphql_schema!("type Query { user(id: ID!): User }");
+
+#[test]
+fn inline() {
+    assert_eq!(QUERY, "query User($id: ID!) { user(id: $id) { name } }");
+    assert_eq!(RAW_QUERY, r#"{ user(name: "x") { id } }"#);
+    assert!(parse_schema::<&str>(SCHEMA).is_ok());
+}
+
+#[test]
+fn include() {
+    let query = include_query!("../tests/queries/kitchen-sink.graphql");
+    assert_eq!(
+        query,
+        include_str!("../../tests/queries/kitchen-sink.graphql")
+    );
+    assert!(parse_query::<&str>(query).is_ok());
+    let schema = include_schema!("../tests/schemas/kitchen-sink.graphql");
+    assert_eq!(
+        schema,
+        include_str!("../../tests/schemas/kitchen-sink.graphql")
+    );
+}
@@ -0,0 +1,354 @@
+//! Command-line tool to format, check and minify graphql files
+use std::env;
+use std::fmt;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::process::exit;
+
+use graphql_parser::schema::{self, Criticality};
+use graphql_parser::{format_query_range, format_schema_range, minify_query};
+use graphql_parser::{parse_query, parse_schema, Pos, Style, TextEdit};
+use graphql_parser::{skipped_query_parts, skipped_schema_definitions};
+
+const USAGE: &str = "\
+Usage: graphql <command> [options] <file>...
+       graphql diff <old-schema> <new-schema>
+
+Commands:
+    fmt       Format files in place
+    check     Check that files parse
+    minify    Write minified files to standard output
+    diff      List changes between two schemas, fail if any of them
+              is breaking
+
+Use `-` as a file name to read standard input. Formatted standard input
+is written to standard output.
+
+Files are detected to be queries or schemas by their first definition,
+unless `--query` or `--schema` is given.
+
+Options:
+    --query                     Files are queries
+    --schema                    Files are schemas (SDL)
+    --check                     Don't write files, fail if any of them
+                                isn't formatted (fmt only)
+    --indent <n>                Number of spaces to indent with
+    --max-width <n>             Wrap lines longer than <n> characters
+    --multiline-arguments       Write every argument on its own line
+    --sort-definitions          Sort definitions by kind and name
+    --group-extensions          Write type extensions after their types
+    --sort-fields               Sort fields and enum values
+    --sort-arguments            Sort arguments
+    --preserve-blank-lines      Keep blank lines between fields
+    -h, --help                  Print this help
+
+Definitions that contain comments are never reformatted, as the comments
+would be lost. They are listed as warnings and don't fail `--check`.
+";
+
+#[derive(Clone, Copy, PartialEq)]
+enum Command {
+    Fmt,
+    Check,
+    Minify,
+    Diff,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Kind {
+    Query,
+    Schema,
+}
+
+struct Options {
+    command: Command,
+    kind: Option<Kind>,
+    check: bool,
+    style: Style,
+    files: Vec<String>,
+}
+
+/// Error reported for a single file, the rest of the files are processed
+enum Error {
+    Io(io::Error),
+    Parse { position: Pos, message: String },
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Error {
+        Error::Io(e)
+    }
+}
+
+fn parse_error<E: fmt::Display>(position: Pos, error: E) -> Error {
+    // Skip "... parse error: Parse error at line:column"
+    let error = error.to_string();
+    let message = error.lines().skip(1).collect::<Vec<_>>().join(", ");
+    Error::Parse { position, message }
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("graphql: {}", message);
+    eprint!("{}", USAGE);
+    exit(2);
+}
+
+fn number(value: Option<String>, option: &str) -> usize {
+    value
+        .and_then(|v| v.parse().ok())
+        .unwrap_or_else(|| usage_error(&format!("{} requires a number", option)))
+}
+
+fn parse_args() -> Options {
+    let mut args = env::args().skip(1);
+    let command = match args.next().as_deref() {
+        Some("fmt") => Command::Fmt,
+        Some("check") => Command::Check,
+        Some("minify") => Command::Minify,
+        Some("diff") => Command::Diff,
+        Some("-h") | Some("--help") => {
+            print!("{}", USAGE);
+            exit(0);
+        }
+        Some(cmd) => usage_error(&format!("unknown command `{}`", cmd)),
+        None => usage_error("command is required"),
+    };
+    let mut options = Options {
+        command,
+        kind: None,
+        check: false,
+        style: Style::default(),
+        files: Vec::new(),
+    };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--query" => options.kind = Some(Kind::Query),
+            "--schema" => options.kind = Some(Kind::Schema),
+            "--check" if command == Command::Fmt => options.check = true,
+            "--indent" => {
+                let indent = number(args.next(), &arg);
+                options.style.indent(indent as u32);
+            }
+            "--max-width" => {
+                options.style.max_width(number(args.next(), &arg));
+            }
+            "--multiline-arguments" => {
+                options.style.multiline_arguments(true);
+            }
+            "--sort-definitions" => {
+                options.style.sort_definitions(true);
+            }
+            "--group-extensions" => {
+                options.style.group_extensions(true);
+            }
+            "--sort-fields" => {
+                options.style.sort_fields(true);
+            }
+            "--sort-arguments" => {
+                options.style.sort_arguments(true);
+            }
+            "--preserve-blank-lines" => {
+                options.style.preserve_blank_lines(true);
+            }
+            "-h" | "--help" => {
+                print!("{}", USAGE);
+                exit(0);
+            }
+            "-" => options.files.push(arg),
+            _ if arg.starts_with('-') => usage_error(&format!("unknown option `{}`", arg)),
+            _ => options.files.push(arg),
+        }
+    }
+    if options.files.is_empty() {
+        usage_error("no files given");
+    }
+    if command == Command::Diff && options.files.len() != 2 {
+        usage_error("diff requires two files");
+    }
+    options
+}
+
+/// Guesses the kind of the document by its first definition
+fn detect(source: &str) -> Kind {
+    let mut rest = source;
+    loop {
+        rest = rest.trim_start_matches(|c: char| c.is_whitespace() || c == ',' || c == '\u{feff}');
+        if rest.starts_with('#') {
+            rest = rest.find('\n').map_or("", |idx| &rest[idx..]);
+        } else {
+            break;
+        }
+    }
+    let word = rest
+        .split(|c: char| !(c.is_alphanumeric() || c == '_'))
+        .next()
+        .unwrap_or("");
+    match word {
+        "query" | "mutation" | "subscription" | "fragment" => Kind::Query,
+        _ if rest.starts_with('{') => Kind::Query,
+        _ => Kind::Schema,
+    }
+}
+
+fn read(file: &str) -> io::Result<String> {
+    if file == "-" {
+        let mut buf = String::new();
+        io::stdin().read_to_string(&mut buf)?;
+        Ok(buf)
+    } else {
+        fs::read_to_string(file)
+    }
+}
+
+fn format(source: &str, kind: Kind, style: &Style) -> Result<Vec<TextEdit>, Error> {
+    let whole = 0..source.len();
+    match kind {
+        Kind::Query => {
+            format_query_range(source, whole, style).map_err(|e| parse_error(e.position(), e))
+        }
+        Kind::Schema => {
+            format_schema_range(source, whole, style).map_err(|e| parse_error(e.position(), e))
+        }
+    }
+}
+
+/// Returns positions of definitions that aren't formatted because they
+/// contain comments
+fn skipped(source: &str, kind: Kind) -> Result<Vec<Pos>, Error> {
+    let whole = 0..source.len();
+    match kind {
+        Kind::Query => skipped_query_parts(source, whole).map_err(|e| parse_error(e.position(), e)),
+        Kind::Schema => {
+            skipped_schema_definitions(source, whole).map_err(|e| parse_error(e.position(), e))
+        }
+    }
+}
+
+fn check(source: &str, kind: Kind) -> Result<(), Error> {
+    match kind {
+        Kind::Query => parse_query::<&str>(source)
+            .map(|_| ())
+            .map_err(|e| parse_error(e.position(), e)),
+        Kind::Schema => parse_schema::<&str>(source)
+            .map(|_| ())
+            .map_err(|e| parse_error(e.position(), e)),
+    }
+}
+
+/// Processes a single file, returns `false` if it isn't formatted in the
+/// check mode
+fn process(file: &str, options: &Options) -> Result<bool, Error> {
+    let source = read(file)?;
+    let kind = options.kind.unwrap_or_else(|| detect(&source));
+    match options.command {
+        Command::Check => check(&source, kind).map(|()| true),
+        Command::Diff => unreachable!(),
+        Command::Minify => {
+            check(&source, kind)?;
+            let minified = minify_query(source)
+                .map_err(|e| Error::Io(io::Error::new(io::ErrorKind::Other, e)))?;
+            let mut stdout = io::stdout();
+            writeln!(stdout, "{}", minified)?;
+            Ok(true)
+        }
+        Command::Fmt => {
+            let edits = format(&source, kind, &options.style)?;
+            for position in skipped(&source, kind)? {
+                eprintln!(
+                    "{}:{}: warning: contains comments, not formatted",
+                    name(file),
+                    position
+                );
+            }
+            if options.check {
+                return Ok(edits.is_empty());
+            }
+            let mut formatted = source.clone();
+            for edit in edits.iter().rev() {
+                formatted.replace_range(edit.range.clone(), &edit.new_text);
+            }
+            if file == "-" {
+                io::stdout().write_all(formatted.as_bytes())?;
+            } else if !edits.is_empty() {
+                fs::write(file, formatted)?;
+            }
+            Ok(true)
+        }
+    }
+}
+
+/// Prints changes between two schemas, returns `false` if any of them is
+/// breaking
+fn diff(old_file: &str, new_file: &str) -> Result<bool, (String, Error)> {
+    let read_schema = |file: &str| read(file).map_err(|e| (name(file).to_string(), e.into()));
+    let old_source = read_schema(old_file)?;
+    let new_source = read_schema(new_file)?;
+    let old = parse_schema::<&str>(&old_source)
+        .map_err(|e| (name(old_file).to_string(), parse_error(e.position(), e)))?;
+    let new = parse_schema::<&str>(&new_source)
+        .map_err(|e| (name(new_file).to_string(), parse_error(e.position(), e)))?;
+    let mut compatible = true;
+    for change in schema::diff(&old, &new) {
+        let criticality = match change.criticality {
+            Criticality::Breaking => {
+                compatible = false;
+                "breaking"
+            }
+            Criticality::Dangerous => "dangerous",
+            Criticality::Safe => "safe",
+        };
+        // Removed elements are only in the old schema
+        let location = match (change.new_position, change.old_position) {
+            (Some(pos), _) => format!("{}:{}", name(new_file), pos),
+            (None, Some(pos)) => format!("{}:{}", name(old_file), pos),
+            (None, None) => name(new_file).to_string(),
+        };
+        println!("{}: {}: {}", location, criticality, change.message);
+    }
+    Ok(compatible)
+}
+
+fn name(file: &str) -> &str {
+    if file == "-" {
+        "<stdin>"
+    } else {
+        file
+    }
+}
+
+fn main() {
+    let options = parse_args();
+    if options.command == Command::Diff {
+        match diff(&options.files[0], &options.files[1]) {
+            Ok(true) => return,
+            Ok(false) => {}
+            Err((name, Error::Io(e))) => eprintln!("{}: {}", name, e),
+            Err((name, Error::Parse { position, message })) => {
+                eprintln!("{}:{}: {}", name, position, message)
+            }
+        }
+        exit(1);
+    }
+    let mut failed = false;
+    for file in &options.files {
+        let name = name(file);
+        match process(file, &options) {
+            Ok(true) => {}
+            Ok(false) => {
+                println!("{}: not formatted", name);
+                failed = true;
+            }
+            Err(Error::Io(e)) => {
+                eprintln!("{}: {}", name, e);
+                failed = true;
+            }
+            Err(Error::Parse { position, message }) => {
+                eprintln!("{}:{}: {}", name, position, message);
+                failed = true;
+            }
+        }
+    }
+    if failed {
+        exit(1);
+    }
+}
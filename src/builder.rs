@@ -0,0 +1,22 @@
+//! Helpers shared by the schema and query builders
+use crate::common::{Directive, Text, Type};
+
+/// Returns a named type, like `ID`
+pub fn named<'a, T: Text<'a>>(name: impl Into<T::Value>) -> Type<'a, T> {
+    Type::NamedType(name.into())
+}
+
+/// Returns a list of `item_type`, like `[ID]`
+pub fn list<'a, T: Text<'a>>(item_type: Type<'a, T>) -> Type<'a, T> {
+    Type::ListType(Box::new(item_type))
+}
+
+/// Returns a non-null `value_type`, like `ID!`
+pub fn non_null<'a, T: Text<'a>>(value_type: Type<'a, T>) -> Type<'a, T> {
+    Type::NonNullType(Box::new(value_type))
+}
+
+/// Returns a directive, arguments are added with `Directive::argument`
+pub fn directive<'a, T: Text<'a>>(name: impl Into<T::Value>) -> Directive<'a, T> {
+    Directive::new(name.into())
+}
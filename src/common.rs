@@ -1,12 +1,14 @@
+use std::borrow::Cow;
+use std::marker::PhantomData;
+use std::ops::Deref;
 use std::{collections::BTreeMap, fmt};
 
 use combine::easy::{Error, Info};
-use combine::{choice, many, many1, optional, position, StdParseResult};
-use combine::{parser, Parser};
+use combine::Positioned;
 
-use crate::helpers::{ident, kind, name, punct};
-use crate::position::Pos;
-use crate::tokenizer::{Kind as T, Token, TokenStream};
+use crate::helpers::{ParseResult, VALUE};
+use crate::position::{self, Pos};
+use crate::tokenizer::{Kind, Token, TokenStream};
 
 /// Text abstracts over types that hold a string value.
 /// It is used to make the AST generic over the string type.
@@ -21,10 +23,22 @@ pub trait Text<'a>: 'a {
         + Ord
         + fmt::Debug
         + Clone;
+
+    /// Returns a slice of the source as a value, if it doesn't allocate
+    ///
+    /// Raw string literals are only kept for texts returning `Some` here,
+    /// so owned ASTs don't store every literal twice.
+    fn borrow_source(_source: &'a str) -> Option<Self::Value> {
+        None
+    }
 }
 
 impl<'a> Text<'a> for &'a str {
     type Value = Self;
+
+    fn borrow_source(source: &'a str) -> Option<Self::Value> {
+        Some(source)
+    }
 }
 
 impl<'a> Text<'a> for String {
@@ -33,6 +47,18 @@ impl<'a> Text<'a> for String {
 
 impl<'a> Text<'a> for std::borrow::Cow<'a, str> {
     type Value = Self;
+
+    fn borrow_source(source: &'a str) -> Option<Self::Value> {
+        Some(Cow::Borrowed(source))
+    }
+}
+
+impl<'a> Text<'a> for std::sync::Arc<str> {
+    type Value = Self;
+}
+
+impl<'a> Text<'a> for std::rc::Rc<str> {
+    type Value = Self;
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -42,6 +68,22 @@ pub struct Directive<'a, T: Text<'a>> {
     pub arguments: Vec<(T::Value, Value<'a, T>)>,
 }
 
+impl<'a, T: Text<'a>> Directive<'a, T> {
+    pub fn new(name: T::Value) -> Self {
+        Self {
+            position: Pos::default(),
+            name,
+            arguments: vec![],
+        }
+    }
+
+    /// Adds an argument
+    pub fn argument(mut self, name: impl Into<T::Value>, value: Value<'a, T>) -> Self {
+        self.arguments.push((name.into(), value));
+        self
+    }
+}
+
 /// This represents integer number
 ///
 /// But since there is no definition on limit of number in spec
@@ -58,7 +100,7 @@ pub enum Value<'a, T: Text<'a>> {
     Variable(T::Value),
     Int(Number),
     Float(f64),
-    String(String),
+    String(StringValue<'a, T>),
     Boolean(bool),
     Null,
     Enum(T::Value),
@@ -72,7 +114,7 @@ impl<'a, T: Text<'a>> Value<'a, T> {
             Self::Variable(v) => Value::Variable(v.as_ref().into()),
             Self::Int(i) => Value::Int(i.clone()),
             Self::Float(f) => Value::Float(*f),
-            Self::String(s) => Value::String(s.clone()),
+            Self::String(s) => Value::String(s.into_static()),
             Self::Boolean(b) => Value::Boolean(*b),
             Self::Null => Value::Null,
             Self::Enum(v) => Value::Enum(v.as_ref().into()),
@@ -86,6 +128,173 @@ impl<'a, T: Text<'a>> Value<'a, T> {
     }
 }
 
+/// A string literal or a description
+///
+/// When the literal contains no escape sequences the value is a slice of
+/// the raw literal, so with `Text = &str` no allocation is made. The raw
+/// literal, including the quotes, is kept for parsed strings if the text
+/// borrows from the source (`&str` or `Cow<str>`), owned texts keep only
+/// the value.
+///
+/// Strings are compared by value only, regardless of how they were quoted.
+#[derive(Clone)]
+pub struct StringValue<'a, T: Text<'a>> {
+    raw: Option<T::Value>,
+    value: Unescaped<'a>,
+    block: bool,
+}
+
+#[derive(Debug, Clone)]
+enum Unescaped<'a> {
+    /// Byte range of the value in the raw literal
+    Raw(usize, usize),
+    Owned(String),
+    /// Value allocated elsewhere, like in an arena, only created for
+    /// `StringValue<'a, &'a str>` by `StringValue::move_value`
+    Borrowed(&'a str),
+}
+
+impl<'a, T: Text<'a>> StringValue<'a, T> {
+    /// Creates a regular string with the specified value
+    pub fn new<S: Into<String>>(value: S) -> Self {
+        StringValue {
+            raw: None,
+            value: Unescaped::Owned(value.into()),
+            block: false,
+        }
+    }
+
+    /// Creates a block (triple quoted) string with the specified value
+    pub fn block<S: Into<String>>(value: S) -> Self {
+        StringValue {
+            raw: None,
+            value: Unescaped::Owned(value.into()),
+            block: true,
+        }
+    }
+
+    fn parsed(raw: &'a str, value: Cow<'a, str>, block: bool) -> Self {
+        let raw_value = T::borrow_source(raw);
+        let value = match value {
+            Cow::Borrowed(slice) if raw_value.is_some() => {
+                let start = slice.as_ptr() as usize - raw.as_ptr() as usize;
+                Unescaped::Raw(start, start + slice.len())
+            }
+            value => Unescaped::Owned(value.into_owned()),
+        };
+        StringValue {
+            raw: raw_value,
+            value,
+            block,
+        }
+    }
+
+    /// Returns unescaped value of the string
+    pub fn as_str(&self) -> &str {
+        match self.value {
+            Unescaped::Raw(start, end) => {
+                let raw = self.raw.as_ref().expect("parsed string has raw value");
+                &raw.as_ref()[start..end]
+            }
+            Unescaped::Owned(ref value) => value,
+            Unescaped::Borrowed(value) => value,
+        }
+    }
+
+    /// Returns the literal as written in the source, including quotes
+    ///
+    /// Returns `None` for strings that were not parsed and for texts that
+    /// don't borrow from the source, like `String`.
+    pub fn raw(&self) -> Option<&str> {
+        self.raw.as_ref().map(|raw| raw.as_ref())
+    }
+
+    /// Returns `true` if the string is a block (triple quoted) string
+    pub fn is_block(&self) -> bool {
+        self.block
+    }
+
+    /// Returns `true` if the value is borrowed from the raw literal
+    pub fn is_borrowed(&self) -> bool {
+        matches!(self.value, Unescaped::Raw(..))
+    }
+
+    pub fn into_static(&self) -> StringValue<'static, String> {
+        StringValue {
+            raw: None,
+            value: Unescaped::Owned(self.as_str().into()),
+            block: self.block,
+        }
+    }
+}
+
+impl<'a> StringValue<'a, &'a str> {
+    /// Moves the value out of the heap, if it was unescaped there
+    ///
+    /// Only defined for `&'a str` texts, so that strings of owned ASTs
+    /// never hold references.
+    #[cfg_attr(not(feature = "arena"), allow(dead_code))]
+    pub(crate) fn move_value(mut self, alloc: impl FnOnce(&str) -> &'a str) -> Self {
+        if let Unescaped::Owned(ref value) = self.value {
+            self.value = Unescaped::Borrowed(alloc(value));
+        }
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> Deref for StringValue<'a, T> {
+    type Target = str;
+    fn deref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a, T: Text<'a>> AsRef<str> for StringValue<'a, T> {
+    fn as_ref(&self) -> &str {
+        self.as_str()
+    }
+}
+
+impl<'a, T: Text<'a>> fmt::Debug for StringValue<'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("StringValue")
+            .field("value", &self.as_str())
+            .field("raw", &self.raw())
+            .field("block", &self.block)
+            .finish()
+    }
+}
+
+impl<'a, T: Text<'a>> PartialEq for StringValue<'a, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.as_str() == other.as_str()
+    }
+}
+
+impl<'a, T: Text<'a>> PartialEq<str> for StringValue<'a, T> {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl<'a, 'b, T: Text<'a>> PartialEq<&'b str> for StringValue<'a, T> {
+    fn eq(&self, other: &&'b str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl<'a, T: Text<'a>> From<&str> for StringValue<'a, T> {
+    fn from(value: &str) -> Self {
+        StringValue::new(value)
+    }
+}
+
+impl<'a, T: Text<'a>> From<String> for StringValue<'a, T> {
+    fn from(value: String) -> Self {
+        StringValue::new(value)
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Type<'a, T: Text<'a>> {
     NamedType(T::Value),
@@ -106,74 +315,223 @@ impl From<i32> for Number {
     }
 }
 
-pub fn directives<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<Directive<'a, T>>, TokenStream<'a>>
+/// Allocates nodes built by the grammar
+///
+/// The grammar is generic over it, so that the same functions build both
+/// the regular AST (`Heap`) and the arena allocated one (`query::arena`).
+/// Lists are collected into `Seq` buffers, which the allocator turns into
+/// the lists of the tree.
+pub trait Alloc<'a>: Copy {
+    type Text: Text<'a>;
+    type Value;
+    type List: Seq<Self::Value>;
+    type Object: Seq<(Name<'a, Self>, Self::Value)>;
+    type Arguments: Seq<(Name<'a, Self>, Self::Value)>;
+    type Type;
+    type Directive;
+    type Directives: Seq<Self::Directive>;
+
+    fn list(self) -> Self::List;
+    fn object(self) -> Self::Object;
+    fn arguments(self) -> Self::Arguments;
+    fn directives(self) -> Self::Directives;
+
+    /// Converts a value that is neither a list nor an object
+    fn scalar(self, value: Value<'a, Self::Text>) -> Self::Value;
+    fn list_value(self, items: Self::List) -> Self::Value;
+    fn object_value(self, fields: Self::Object) -> Self::Value;
+    fn named_type(self, name: Name<'a, Self>) -> Self::Type;
+    fn list_type(self, inner: Self::Type) -> Self::Type;
+    fn non_null_type(self, inner: Self::Type) -> Self::Type;
+    fn directive(
+        self,
+        position: Pos,
+        name: Name<'a, Self>,
+        arguments: Self::Arguments,
+    ) -> Self::Directive;
+}
+
+/// Name in the tree built by the allocator `A`
+pub type Name<'a, A> = <<A as Alloc<'a>>::Text as Text<'a>>::Value;
+
+/// A buffer the grammar collects lists into
+pub trait Seq<T> {
+    fn push(&mut self, item: T);
+    fn is_empty(&self) -> bool;
+}
+
+impl<T> Seq<T> for Vec<T> {
+    fn push(&mut self, item: T) {
+        Vec::push(self, item);
+    }
+    fn is_empty(&self) -> bool {
+        Vec::is_empty(self)
+    }
+}
+
+/// Later fields with the same name replace earlier ones
+impl<K: Ord, V> Seq<(K, V)> for BTreeMap<K, V> {
+    fn push(&mut self, (key, value): (K, V)) {
+        self.insert(key, value);
+    }
+    fn is_empty(&self) -> bool {
+        BTreeMap::is_empty(self)
+    }
+}
+
+/// Allocator of the regular AST
+pub struct Heap<T>(PhantomData<T>);
+
+impl<T> Heap<T> {
+    pub fn new() -> Heap<T> {
+        Heap(PhantomData)
+    }
+}
+
+impl<T> Clone for Heap<T> {
+    fn clone(&self) -> Heap<T> {
+        *self
+    }
+}
+
+impl<T> Copy for Heap<T> {}
+
+impl<'a, T: Text<'a>> Alloc<'a> for Heap<T> {
+    type Text = T;
+    type Value = Value<'a, T>;
+    type List = Vec<Value<'a, T>>;
+    type Object = BTreeMap<T::Value, Value<'a, T>>;
+    type Arguments = Vec<(T::Value, Value<'a, T>)>;
+    type Type = Type<'a, T>;
+    type Directive = Directive<'a, T>;
+    type Directives = Vec<Directive<'a, T>>;
+
+    fn list(self) -> Self::List {
+        Vec::new()
+    }
+    fn object(self) -> Self::Object {
+        BTreeMap::new()
+    }
+    fn arguments(self) -> Self::Arguments {
+        Vec::new()
+    }
+    fn directives(self) -> Self::Directives {
+        Vec::new()
+    }
+    fn scalar(self, value: Value<'a, T>) -> Self::Value {
+        value
+    }
+    fn list_value(self, items: Self::List) -> Self::Value {
+        Value::List(items)
+    }
+    fn object_value(self, fields: Self::Object) -> Self::Value {
+        Value::Object(fields)
+    }
+    fn named_type(self, name: T::Value) -> Self::Type {
+        Type::NamedType(name)
+    }
+    fn list_type(self, inner: Self::Type) -> Self::Type {
+        Type::ListType(Box::new(inner))
+    }
+    fn non_null_type(self, inner: Self::Type) -> Self::Type {
+        Type::NonNullType(Box::new(inner))
+    }
+    fn directive(
+        self,
+        position: Pos,
+        name: T::Value,
+        arguments: Self::Arguments,
+    ) -> Self::Directive {
+        Directive {
+            position,
+            name,
+            arguments,
+        }
+    }
+}
+
+pub fn directives<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Vec<Directive<'a, T>>>
 where
     T: Text<'a>,
 {
-    many(
-        position()
-            .skip(punct("@"))
-            .and(name::<'a, T>())
-            .and(parser(arguments))
-            .map(|((position, name), arguments)| Directive {
-                position,
-                name,
-                arguments,
-            }),
-    )
-    .parse_stream(input)
-    .into_result()
-}
-
-#[allow(clippy::type_complexity)]
-pub fn arguments<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<(T::Value, Value<'a, T>)>, TokenStream<'a>>
+    directives_in(input, Heap::new())
+}
+
+pub fn directives_in<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Directives>
+where
+    A: Alloc<'a>,
+{
+    let mut directives = alloc.directives();
+    while input.is_punct("@") {
+        directives.push(directive_in(input, alloc)?);
+    }
+    Ok(directives)
+}
+
+pub fn directive<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Directive<'a, T>>
 where
     T: Text<'a>,
 {
-    optional(
-        punct("(")
-            .with(many1(name::<'a, T>().skip(punct(":")).and(parser(value))))
-            .skip(punct(")")),
-    )
-    .map(|opt| opt.unwrap_or_default())
-    .parse_stream(input)
-    .into_result()
+    directive_in(input, Heap::new())
 }
 
-pub fn int_value<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
+fn directive_in<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Directive>
 where
-    S: Text<'a>,
+    A: Alloc<'a>,
 {
-    kind(T::IntValue)
-        .and_then(|tok| tok.value.parse())
-        .map(Number)
-        .map(Value::Int)
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.punct("@")?;
+    let name = input.name::<A::Text>()?;
+    let arguments = arguments_in(input, alloc)?;
+    Ok(alloc.directive(position, name, arguments))
 }
 
-pub fn float_value<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
+pub fn arguments_in<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Arguments>
+where
+    A: Alloc<'a>,
+{
+    let mut arguments = alloc.arguments();
+    if input.eat_punct("(") {
+        loop {
+            let name = input.name::<A::Text>()?;
+            input.punct(":")?;
+            arguments.push((name, value_in(input, alloc, false)?));
+            if !input.is_name() {
+                break;
+            }
+        }
+        input.punct_or(")", &["Name", ":", ")"])?;
+    }
+    Ok(arguments)
+}
+
+fn number<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<'a, Value<'a, S>>
 where
     S: Text<'a>,
 {
-    kind(T::FloatValue)
-        .and_then(|tok| tok.value.parse())
-        .map(Value::Float)
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    let token = input.bump();
+    if token.kind == Kind::IntValue {
+        token
+            .value
+            .parse()
+            .map(|n| Value::Int(Number(n)))
+            .map_err(|e| input.error_at(position, Error::Other(e.into())))
+    } else {
+        token
+            .value
+            .parse()
+            .map(Value::Float)
+            .map_err(|e| input.error_at(position, Error::Other(e.into())))
+    }
 }
 
-fn unquote_block_string(src: &str) -> Result<String, Error<Token<'_>, Token<'_>>> {
+/// Block strings have no escape sequences except `\"""`, so they never
+/// fail to unquote
+fn unquote_block_string(src: &str) -> Result<Cow<'_, str>, EscapeError<'_>> {
     debug_assert!(src.starts_with("\"\"\"") && src.ends_with("\"\"\""));
-    let lines = src[3..src.len() - 3].lines();
+    let content = &src[3..src.len() - 3];
+    let lines = position::lines(content).map(|(start, end)| &content[start..end]);
 
     let mut common_indent = usize::MAX;
     let mut first_non_empty_line: Option<usize> = None;
@@ -194,12 +552,11 @@ fn unquote_block_string(src: &str) -> Result<String, Error<Token<'_>, Token<'_>>
 
     if first_non_empty_line.is_none() {
         // The block string contains only whitespace.
-        return Ok("".to_string());
+        return Ok(Cow::Borrowed(&content[..0]));
     }
     let first_non_empty_line = first_non_empty_line.unwrap();
 
-    let mut result = String::with_capacity(src.len() - 6);
-    let mut lines = lines
+    let lines = lines
         .enumerate()
         // Skip leading and trailing empty lines.
         .skip(first_non_empty_line)
@@ -211,9 +568,35 @@ fn unquote_block_string(src: &str) -> Result<String, Error<Token<'_>, Token<'_>>
             } else {
                 line
             }
-        })
-        // Handle escaped triple-quote (\""").
-        .map(|x| x.replace(r#"\""""#, r#"""""#));
+        });
+
+    // If the lines are adjacent in the source and there are no escapes,
+    // the value is a plain slice of the source
+    if !content.contains(r#"\""""#) {
+        let mut span: Option<&str> = None;
+        let mut contiguous = true;
+        for line in lines.clone() {
+            span = match span {
+                None => Some(line),
+                Some(prev) => {
+                    let end = prev.as_ptr() as usize - content.as_ptr() as usize + prev.len();
+                    let start = line.as_ptr() as usize - content.as_ptr() as usize;
+                    if start != end + 1 || content.as_bytes()[end] != b'\n' {
+                        contiguous = false;
+                        break;
+                    }
+                    Some(&content[end - prev.len()..start + line.len()])
+                }
+            };
+        }
+        if let (true, Some(span)) = (contiguous, span) {
+            return Ok(Cow::Borrowed(span));
+        }
+    }
+
+    let mut result = String::with_capacity(src.len() - 6);
+    // Handle escaped triple-quote (\""").
+    let mut lines = lines.map(|x| x.replace(r#"\""""#, r#"""""#));
 
     if let Some(line) = lines.next() {
         result.push_str(&line);
@@ -223,189 +606,303 @@ fn unquote_block_string(src: &str) -> Result<String, Error<Token<'_>, Token<'_>>
             result.push_str(&line);
         }
     }
-    Ok(result)
+    Ok(Cow::Owned(result))
 }
 
-fn unquote_string(s: &str) -> Result<String, Error<Token, Token>> {
-    let mut res = String::with_capacity(s.len());
+/// Error in an escape sequence, with offset of the sequence in chars from
+/// the start of the string token
+type EscapeError<'a> = (usize, Error<Token<'a>, Token<'a>>);
+
+fn unquote_string(s: &str) -> Result<Cow<'_, str>, EscapeError<'_>> {
     debug_assert!(s.starts_with('"') && s.ends_with('"'));
-    let mut chars = s[1..s.len() - 1].chars();
-    let mut temp_code_point = String::with_capacity(4);
-    while let Some(c) = chars.next() {
-        match c {
-            '\\' => {
-                match chars.next().expect("slash cant be at the end") {
-                    c @ '"' | c @ '\\' | c @ '/' => res.push(c),
-                    'b' => res.push('\u{0010}'),
-                    'f' => res.push('\u{000C}'),
-                    'n' => res.push('\n'),
-                    'r' => res.push('\r'),
-                    't' => res.push('\t'),
-                    'u' => {
-                        temp_code_point.clear();
-                        for _ in 0..4 {
-                            match chars.next() {
-                                Some(inner_c) => temp_code_point.push(inner_c),
-                                None => {
-                                    return Err(Error::Unexpected(Info::Owned(
-                                        format_args!(
-                                            "\\u must have 4 characters after it, only found '{}'",
-                                            temp_code_point
-                                        )
-                                        .to_string(),
-                                    )))
-                                }
+    if !s.contains('\\') {
+        return Ok(Cow::Borrowed(&s[1..s.len() - 1]));
+    }
+    let mut res = String::with_capacity(s.len());
+    let mut chars = s[..s.len() - 1].chars().enumerate().skip(1);
+    while let Some((offset, c)) = chars.next() {
+        if c != '\\' {
+            res.push(c);
+            continue;
+        }
+        match chars.next().expect("slash cant be at the end").1 {
+            c @ '"' | c @ '\\' | c @ '/' => res.push(c),
+            'b' => res.push('\u{0008}'),
+            'f' => res.push('\u{000C}'),
+            'n' => res.push('\n'),
+            'r' => res.push('\r'),
+            't' => res.push('\t'),
+            'u' => {
+                let (code, fixed_width) = unicode_escape(&mut chars).map_err(|e| (offset, e))?;
+                let code = match code {
+                    // Only a fixed width escape may be a leading surrogate,
+                    // followed by a trailing one
+                    0xD800..=0xDBFF if fixed_width => {
+                        let mut ahead = chars.clone();
+                        let trailing = match (ahead.next(), ahead.next()) {
+                            (Some((_, '\\')), Some((_, 'u'))) => unicode_escape(&mut ahead).ok(),
+                            _ => None,
+                        };
+                        match trailing {
+                            Some((low @ 0xDC00..=0xDFFF, true)) => {
+                                chars = ahead;
+                                0x10000 + ((code - 0xD800) << 10) + (low - 0xDC00)
                             }
+                            _ => code,
                         }
-
-                        // convert our hex string into a u32, then convert that into a char
-                        match u32::from_str_radix(&temp_code_point, 16).map(std::char::from_u32) {
-                            Ok(Some(unicode_char)) => res.push(unicode_char),
-                            _ => {
-                                return Err(Error::Unexpected(Info::Owned(
-                                    format_args!(
-                                        "{} is not a valid unicode code point",
-                                        temp_code_point
-                                    )
+                    }
+                    _ => code,
+                };
+                match std::char::from_u32(code) {
+                    Some(unicode_char) => res.push(unicode_char),
+                    None if (0xD800..=0xDFFF).contains(&code) => {
+                        return Err((
+                            offset,
+                            Error::Unexpected(Info::Owned(
+                                format_args!("lone surrogate \\u{:X}", code).to_string(),
+                            )),
+                        ))
+                    }
+                    None => {
+                        return Err((
+                            offset,
+                            Error::Unexpected(Info::Owned(
+                                format_args!("{:X} is not a valid unicode code point", code)
                                     .to_string(),
-                                )))
-                            }
-                        }
+                            )),
+                        ))
                     }
-                    c => {
+                }
+            }
+            c => {
+                return Err((
+                    offset,
+                    Error::Unexpected(Info::Owned(
+                        format_args!("bad escaped char {:?}", c).to_string(),
+                    )),
+                ));
+            }
+        }
+    }
+
+    Ok(Cow::Owned(res))
+}
+
+/// Reads code of `\u` escape, either `XXXX` or `{X...}`, returns whether it
+/// was a fixed width one
+fn unicode_escape<'a, I>(chars: &mut I) -> Result<(u32, bool), Error<Token<'a>, Token<'a>>>
+where
+    I: Iterator<Item = (usize, char)>,
+{
+    let mut digits = String::with_capacity(6);
+    let fixed_width = match chars.next() {
+        Some((_, '{')) => {
+            loop {
+                match chars.next() {
+                    Some((_, '}')) => break,
+                    Some((_, c)) => digits.push(c),
+                    None => {
+                        return Err(Error::Unexpected(Info::Owned(
+                            "\\u{ must be closed with }".to_string(),
+                        )))
+                    }
+                }
+            }
+            false
+        }
+        Some((_, c)) => {
+            digits.push(c);
+            for _ in 0..3 {
+                match chars.next() {
+                    Some((_, c)) => digits.push(c),
+                    None => {
                         return Err(Error::Unexpected(Info::Owned(
-                            format_args!("bad escaped char {:?}", c).to_string(),
-                        )));
+                            format_args!(
+                                "\\u must have 4 characters after it, only found '{}'",
+                                digits
+                            )
+                            .to_string(),
+                        )))
                     }
                 }
             }
-            c => res.push(c),
+            true
         }
+        None => {
+            return Err(Error::Unexpected(Info::Owned(
+                "\\u must have 4 characters after it, only found ''".to_string(),
+            )))
+        }
+    };
+    // Leading zeros are allowed, so the number of digits is not limited,
+    // only the value is
+    let code = digits.chars().try_fold(0u32, |code, c| {
+        c.to_digit(16)
+            .map(|d| code * 16 + d)
+            .filter(|&code| code <= 0x10FFFF)
+    });
+    match code {
+        Some(code) if !digits.is_empty() => Ok((code, fixed_width)),
+        _ => Err(Error::Unexpected(Info::Owned(
+            format_args!("{} is not a valid unicode code point", digits).to_string(),
+        ))),
     }
+}
 
-    Ok(res)
+/// Parses a string or a block string, borrowing from the source if possible
+pub fn unquoted<'a>(input: &mut TokenStream<'a>) -> ParseResult<'a, (Token<'a>, Cow<'a, str>)> {
+    let position = input.position();
+    let result = match input.peek() {
+        Ok(Some(token)) if token.kind == Kind::StringValue => {
+            unquote_string(token.value).map(|v| (token, v))
+        }
+        Ok(Some(token)) if token.kind == Kind::BlockString => {
+            unquote_block_string(token.value).map(|v| (token, v))
+        }
+        _ => return Err(input.unexpected(&["StringValue", "BlockString"])),
+    };
+    input.bump();
+    // Strings are single line, so the escape is on the line of the token
+    result.map_err(|(offset, e)| {
+        let position = Pos {
+            line: position.line,
+            column: position.column + offset,
+        };
+        input.error_at(position, e)
+    })
 }
 
-pub fn string<'a>(input: &mut TokenStream<'a>) -> StdParseResult<String, TokenStream<'a>> {
-    choice((
-        kind(T::StringValue).and_then(|tok| unquote_string(tok.value)),
-        kind(T::BlockString).and_then(|tok| unquote_block_string(tok.value)),
+pub fn string<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, StringValue<'a, T>>
+where
+    T: Text<'a>,
+{
+    let (token, value) = unquoted(input)?;
+    Ok(StringValue::parsed(
+        token.value,
+        value,
+        token.kind == Kind::BlockString,
     ))
-    .parse_stream(input)
-    .into_result()
 }
 
-pub fn string_value<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
+/// Returns `true` if the next token is a string or a block string
+pub fn is_string(input: &mut TokenStream) -> bool {
+    input.peek_kind(Kind::StringValue).is_some() || input.peek_kind(Kind::BlockString).is_some()
+}
+
+pub fn plain_value<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Value<'a, T>>
 where
-    S: Text<'a>,
+    T: Text<'a>,
 {
-    kind(T::StringValue)
-        .and_then(|tok| unquote_string(tok.value))
-        .map(Value::String)
-        .parse_stream(input)
-        .into_result()
+    let token = match input.peek() {
+        Ok(Some(token)) => token,
+        _ => return Err(input.unexpected_choice(VALUE)),
+    };
+    match token.kind {
+        Kind::Name => {
+            input.bump();
+            Ok(match token.value {
+                "true" => Value::Boolean(true),
+                "false" => Value::Boolean(false),
+                "null" => Value::Null,
+                name => Value::Enum(name.into()),
+            })
+        }
+        Kind::IntValue | Kind::FloatValue => number(input),
+        Kind::StringValue | Kind::BlockString => string(input).map(Value::String),
+        Kind::Punctuator => Err(input.unexpected_choice(VALUE)),
+    }
 }
 
-pub fn block_string_value<'a, S>(
+pub fn is_value_start(input: &mut TokenStream, constant: bool) -> bool {
+    match input.peek() {
+        Ok(Some(token)) => match token.kind {
+            Kind::Punctuator => {
+                token.value == "[" || token.value == "{" || (!constant && token.value == "$")
+            }
+            _ => true,
+        },
+        _ => false,
+    }
+}
+
+/// Parses a value, without variables if it's `constant`
+pub fn value_in<'a, A>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, S>, TokenStream<'a>>
+    alloc: A,
+    constant: bool,
+) -> ParseResult<'a, A::Value>
 where
-    S: Text<'a>,
+    A: Alloc<'a>,
 {
-    kind(T::BlockString)
-        .and_then(|tok| unquote_block_string(tok.value))
-        .map(Value::String)
-        .parse_stream(input)
-        .into_result()
+    if !constant && input.eat_punct("$") {
+        Ok(alloc.scalar(Value::Variable(input.name::<A::Text>()?)))
+    } else if input.eat_punct("[") {
+        let mut items = alloc.list();
+        while is_value_start(input, constant) {
+            items.push(value_in(input, alloc, constant)?);
+        }
+        input.punct("]")?;
+        Ok(alloc.list_value(items))
+    } else if input.eat_punct("{") {
+        let mut fields = alloc.object();
+        while input.is_name() {
+            let name = input.name::<A::Text>()?;
+            input.punct(":")?;
+            fields.push((name, value_in(input, alloc, constant)?));
+        }
+        input.punct_or("}", &["Name", ":", "}"])?;
+        Ok(alloc.object_value(fields))
+    } else {
+        plain_value(input).map(|value| alloc.scalar(value))
+    }
 }
 
-pub fn plain_value<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, T>, TokenStream<'a>>
+pub fn value<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Value<'a, T>>
 where
     T: Text<'a>,
 {
-    ident("true")
-        .map(|_| Value::Boolean(true))
-        .or(ident("false").map(|_| Value::Boolean(false)))
-        .or(ident("null").map(|_| Value::Null))
-        .or(name::<'a, T>().map(Value::Enum))
-        .or(parser(int_value))
-        .or(parser(float_value))
-        .or(parser(string_value))
-        .or(parser(block_string_value))
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn value<'a, T>(input: &mut TokenStream<'a>) -> StdParseResult<Value<'a, T>, TokenStream<'a>>
+    value_in(input, Heap::new(), false)
+}
+
+pub fn default_value<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Value<'a, T>>
 where
     T: Text<'a>,
 {
-    parser(plain_value)
-        .or(punct("$").with(name::<'a, T>()).map(Value::Variable))
-        .or(punct("[")
-            .with(many(parser(value)))
-            .skip(punct("]"))
-            .map(Value::List))
-        .or(punct("{")
-            .with(many(name::<'a, T>().skip(punct(":")).and(parser(value))))
-            .skip(punct("}"))
-            .map(Value::Object))
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn default_value<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Value<'a, T>, TokenStream<'a>>
+    value_in(input, Heap::new(), true)
+}
+
+pub fn parse_type<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Type<'a, T>>
 where
     T: Text<'a>,
 {
-    parser(plain_value)
-        .or(punct("[")
-            .with(many(parser(default_value)))
-            .skip(punct("]"))
-            .map(Value::List))
-        .or(punct("{")
-            .with(many(
-                name::<'a, T>().skip(punct(":")).and(parser(default_value)),
-            ))
-            .skip(punct("}"))
-            .map(Value::Object))
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn parse_type<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Type<'a, T>, TokenStream<'a>>
+    parse_type_in(input, Heap::new())
+}
+
+pub fn parse_type_in<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Type>
 where
-    T: Text<'a>,
+    A: Alloc<'a>,
 {
-    name::<'a, T>()
-        .map(Type::NamedType)
-        .or(punct("[")
-            .with(parser(parse_type))
-            .skip(punct("]"))
-            .map(Box::new)
-            .map(Type::ListType))
-        .and(optional(punct("!")).map(|v| v.is_some()))
-        .map(|(typ, strict)| {
-            if strict {
-                Type::NonNullType(Box::new(typ))
-            } else {
-                typ
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let typ = if input.eat_punct("[") {
+        let inner = parse_type_in(input, alloc)?;
+        input.punct("]")?;
+        alloc.list_type(inner)
+    } else {
+        if !input.is_name() {
+            return Err(input.unexpected_choice(&["Name", "["]));
+        }
+        alloc.named_type(input.name::<A::Text>()?)
+    };
+    if input.eat_punct("!") {
+        Ok(alloc.non_null_type(typ))
+    } else {
+        Ok(typ)
+    }
 }
 
 #[cfg(test)]
 mod tests {
+    use std::borrow::Cow;
+
     use super::unquote_block_string;
     use super::unquote_string;
     use super::Number;
@@ -434,12 +931,58 @@ mod tests {
         );
     }
 
+    #[test]
+    fn unquote_escapes() {
+        assert_eq!(
+            unquote_string(r#""\b\f\n\r\t\"\\\/""#).expect(""),
+            "\u{0008}\u{000C}\n\r\t\"\\/"
+        );
+    }
+
+    #[test]
+    fn unquote_variable_width_unicode() {
+        assert_eq!(unquote_string(r#""\u{1F600}""#).expect(""), "\u{1F600}");
+        assert_eq!(
+            unquote_string(r#""\u{41}\u{0000E9}""#).expect(""),
+            "A\u{e9}"
+        );
+        assert_eq!(unquote_string(r#""\u{10FFFF}""#).expect(""), "\u{10FFFF}");
+        assert!(unquote_string(r#""\u{110000}""#).is_err());
+        assert!(unquote_string(r#""\u{}""#).is_err());
+        assert_eq!(unquote_string(r#""\u{0000000041}""#).expect(""), "A");
+        assert!(unquote_string(r#""\u{00000110000}""#).is_err());
+        assert!(unquote_string(r#""\u{FFFFFFFFFF}""#).is_err());
+        assert!(unquote_string(r#""\u{41""#).is_err());
+    }
+
+    #[test]
+    fn unquote_surrogate_pairs() {
+        assert_eq!(unquote_string(r#""\uD83D\uDE00""#).expect(""), "\u{1F600}");
+        assert_eq!(
+            unquote_string(r#""a\uD83D\uDE00b""#).expect(""),
+            "a\u{1F600}b"
+        );
+        let lone = |s| unquote_string(s).unwrap_err();
+        assert_eq!(lone(r#""\uD83D""#).0, 1);
+        assert_eq!(lone(r#""ab\uDE00""#).0, 3);
+        assert_eq!(lone(r#""\uD83Dx""#).0, 1);
+        assert_eq!(lone(r#""a\uD83D\u0041""#).0, 2);
+        // variable width escapes are never combined
+        assert_eq!(lone(r#""\u{D83D}\u{DE00}""#).0, 1);
+    }
+
+    #[test]
+    fn block_string_line_terminators() {
+        let block = triple_quote("\r\n  a\r  b\r\n    c\n");
+        assert_eq!(unquote_block_string(&block), Result::Ok("a\nb\n  c".into()));
+    }
+
     #[test]
     fn block_string_leading_and_trailing_empty_lines() {
         let block = &triple_quote("   \n\n  Hello,\n    World!\n\n  Yours,\n    GraphQL.\n\n\n");
         assert_eq!(
             unquote_block_string(block),
-            Result::Ok("Hello,\n  World!\n\nYours,\n  GraphQL.".to_string())
+            Result::Ok("Hello,\n  World!\n\nYours,\n  GraphQL.".into())
         );
     }
 
@@ -448,25 +991,45 @@ mod tests {
         let block = &triple_quote("Hello   \n\n  Hello,\n    World!\n");
         assert_eq!(
             unquote_block_string(block),
-            Result::Ok("Hello   \n\nHello,\n  World!".to_string())
+            Result::Ok("Hello   \n\nHello,\n  World!".into())
         );
     }
 
     #[test]
     fn block_string_escaping() {
         let block = triple_quote(r#"\""""#);
-        assert_eq!(
-            unquote_block_string(&block),
-            Result::Ok("\"\"\"".to_string())
-        );
+        assert_eq!(unquote_block_string(&block), Result::Ok("\"\"\"".into()));
     }
 
     #[test]
     fn block_string_empty() {
         let block = triple_quote("");
-        assert_eq!(unquote_block_string(&block), Result::Ok("".to_string()));
+        assert_eq!(unquote_block_string(&block), Result::Ok("".into()));
         let block = triple_quote("   \n\t\n");
-        assert_eq!(unquote_block_string(&block), Result::Ok("".to_string()));
+        assert_eq!(unquote_block_string(&block), Result::Ok("".into()));
+    }
+
+    #[test]
+    fn borrowed() {
+        assert!(matches!(
+            unquote_string(r#""hello""#),
+            Ok(Cow::Borrowed("hello"))
+        ));
+        assert!(matches!(unquote_string(r#""a\nb""#), Ok(Cow::Owned(_))));
+        let block = triple_quote("\n    Hello, World!\n  ");
+        assert!(matches!(
+            unquote_block_string(&block),
+            Ok(Cow::Borrowed("Hello, World!"))
+        ));
+        let block = triple_quote("Hello,\nWorld!\n");
+        assert!(matches!(
+            unquote_block_string(&block),
+            Ok(Cow::Borrowed("Hello,\nWorld!"))
+        ));
+        let block = triple_quote("\n  Hello,\n  World!\n");
+        assert!(matches!(unquote_block_string(&block), Ok(Cow::Owned(_))));
+        let block = triple_quote("Hello,\r\nWorld!");
+        assert!(matches!(unquote_block_string(&block), Ok(Cow::Owned(_))));
     }
 
     fn triple_quote(input: &str) -> String {
@@ -0,0 +1,51 @@
+//! Abstract Syntax Tree (AST) of a document with any definitions
+//!
+//! The spec allows a document to contain both executable and type system
+//! definitions, like client-side schema extensions next to operations.
+//! Definitions reuse the types of the `query` and `schema` modules.
+pub use crate::common::Text;
+use crate::{query, schema};
+
+/// Root of a document with both executable and type system definitions
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document<'a, T: Text<'a>> {
+    pub definitions: Vec<Definition<'a, T>>,
+}
+
+impl<'a> Document<'a, String> {
+    pub fn into_static(self) -> Document<'static, String> {
+        // See `query::Document::into_static` for why this is safe
+        unsafe { std::mem::transmute::<_, Document<'static, String>>(self) }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition<'a, T: Text<'a>> {
+    /// Operation or fragment
+    Executable(query::Definition<'a, T>),
+    /// Schema, type, type extension or directive definition
+    TypeSystem(schema::Definition<'a, T>),
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Splits the document into executable and type system definitions,
+    /// keeping their order
+    pub fn split(self) -> (query::Document<'a, T>, schema::Document<'a, T>) {
+        let mut executable = Vec::new();
+        let mut type_system = Vec::new();
+        for definition in self.definitions {
+            match definition {
+                Definition::Executable(def) => executable.push(def),
+                Definition::TypeSystem(def) => type_system.push(def),
+            }
+        }
+        (
+            query::Document {
+                definitions: executable,
+            },
+            schema::Document {
+                definitions: type_system,
+            },
+        )
+    }
+}
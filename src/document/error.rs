@@ -0,0 +1,34 @@
+use combine::easy::Errors;
+use thiserror::Error;
+
+use crate::position::Pos;
+use crate::tokenizer::Token;
+
+pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
+
+/// Error parsing document
+///
+/// This structure is opaque for forward compatibility. We are exploring a
+/// way to improve both error message and API.
+#[derive(Error, Debug)]
+#[error("document parse error: {}", message)]
+pub struct ParseError {
+    message: String,
+    position: Pos,
+}
+
+impl ParseError {
+    /// Position in the source where the error occurred
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+}
+
+impl<'a> From<InternalError<'a>> for ParseError {
+    fn from(e: InternalError<'a>) -> ParseError {
+        ParseError {
+            message: format!("{}", e),
+            position: e.position,
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use std::fmt;
+use std::io;
+
+use crate::format::{self, Displayable, Formatter, Style};
+
+use crate::document::ast::*;
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Format a document according to style
+    pub fn format(&self, style: &Style) -> String {
+        format::format(self, style)
+    }
+
+    /// Format a document according to style writing it to `out`
+    ///
+    /// Unlike `format` it doesn't keep the whole output in memory.
+    pub fn format_to<W: fmt::Write>(&self, style: &Style, out: &mut W) -> fmt::Result {
+        format::format_to(self, style, out)
+    }
+
+    /// Format a document according to style writing it to `out`
+    ///
+    /// Output is buffered, so `out` doesn't need to be.
+    pub fn write_to<W: io::Write>(&self, style: &Style, out: &mut W) -> io::Result<()> {
+        format::write_to(self, style, out)
+    }
+}
+
+impl<'a, T: Text<'a>> Displayable for Document<'a, T> {
+    fn display(&self, f: &mut Formatter) {
+        for item in &self.definitions {
+            item.display(f);
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Displayable for Definition<'a, T> {
+    fn display(&self, f: &mut Formatter) {
+        match *self {
+            Definition::Executable(ref def) => def.display(f),
+            Definition::TypeSystem(ref def) => def.display(f),
+        }
+    }
+}
+
+impl_display!(
+    'a
+    Document,
+    Definition,
+);
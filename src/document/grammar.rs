@@ -0,0 +1,121 @@
+use crate::common::Text;
+use crate::helpers::ParseResult;
+use crate::tokenizer::TokenStream;
+use crate::{query, schema};
+
+use crate::document::ast::*;
+use crate::document::error::ParseError;
+
+/// Returns `true` if the next token starts any definition
+fn is_definition_start(input: &mut TokenStream) -> bool {
+    query::grammar::is_definition_start(input) || schema::grammar::is_definition_start(input)
+}
+
+pub fn definition<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Definition<'a, T>>
+where
+    T: Text<'a>,
+{
+    if query::grammar::is_definition_start(input) {
+        query::grammar::definition(input).map(Definition::Executable)
+    } else if schema::grammar::is_definition_start(input) {
+        schema::grammar::definition(input).map(Definition::TypeSystem)
+    } else {
+        Err(input.unexpected_choice(&[
+            "{",
+            "query",
+            "mutation",
+            "subscription",
+            "fragment",
+            "schema",
+            "extend",
+            "scalar",
+            "type",
+            "interface",
+            "union",
+            "enum",
+            "input",
+            "directive",
+            "StringValue",
+            "BlockString",
+        ]))
+    }
+}
+
+/// Parses a document that may contain both executable and type system
+/// definitions
+pub fn parse_document<'a, T>(s: &'a str) -> Result<Document<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let mut definitions = vec![definition(&mut tokens)?];
+    while is_definition_start(&mut tokens) {
+        definitions.push(definition(&mut tokens)?);
+    }
+    tokens.eof()?;
+    Ok(Document { definitions })
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_document;
+    use crate::document::{Definition, Document};
+    use crate::{query, schema};
+
+    fn ast(s: &str) -> Document<'_, String> {
+        parse_document::<String>(s).unwrap()
+    }
+
+    #[test]
+    fn mixed() {
+        let doc = ast("query { a } extend type Query { local: Int } fragment f on Query { b }");
+        assert!(matches!(
+            doc.definitions[..],
+            [
+                Definition::Executable(query::Definition::Operation(_)),
+                Definition::TypeSystem(schema::Definition::TypeExtension(_)),
+                Definition::Executable(query::Definition::Fragment(_)),
+            ]
+        ));
+        let (executable, type_system) = doc.split();
+        assert_eq!(
+            executable.to_string(),
+            "query {\n  a\n}\n\nfragment f on Query {\n  b\n}\n"
+        );
+        assert_eq!(
+            type_system.to_string(),
+            "extend type Query {\n  local: Int\n}\n"
+        );
+    }
+
+    #[test]
+    fn format() {
+        let doc = ast("extend type Query{local:Int}query{local}");
+        assert_eq!(
+            doc.to_string(),
+            "extend type Query {\n  local: Int\n}\n\nquery {\n  local\n}\n"
+        );
+    }
+
+    #[test]
+    fn described_definition() {
+        let doc = ast("{ a }\n\"description\" type A { a: Int }");
+        assert!(matches!(
+            doc.definitions[1],
+            Definition::TypeSystem(schema::Definition::TypeDefinition(_))
+        ));
+    }
+
+    #[test]
+    fn errors() {
+        let err = parse_document::<String>("{ a } foo").unwrap_err();
+        assert_eq!(err.position().column, 7);
+        let err = parse_document::<String>("foo").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "document parse error: Parse error at 1:1\nUnexpected `foo[Name]`\n\
+             Expected {, query, mutation, subscription, fragment, schema, extend, scalar, \
+             type, interface, union, enum, input, directive, StringValue or BlockString\n"
+        );
+    }
+}
@@ -0,0 +1,10 @@
+//! Documents mixing executable and type system definitions
+//!
+mod ast;
+mod error;
+mod format;
+mod grammar;
+
+pub use self::ast::*;
+pub use self::error::ParseError;
+pub use self::grammar::parse_document;
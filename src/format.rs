@@ -1,23 +1,153 @@
 //! Formatting graphql
 use std::default::Default;
+use std::fmt;
+use std::io;
+use std::ops::Range;
 
-use crate::common::Directive;
+use combine::{Positioned, StreamOnce};
+
+use crate::common::{Directive, StringValue, Text};
+use crate::position::{self, Pos};
+use crate::tokenizer::TokenStream;
 
-#[derive(Debug, PartialEq)]
 pub(crate) struct Formatter<'a> {
-    buf: String,
+    out: Output<'a>,
     style: &'a Style,
     indent: u32,
+    sequences: Vec<Sequence>,
+    // Commands that can't be laid out yet, because they contain a group
+    // which may or may not fit in `max_width`. Used only when `max_width`
+    // is set.
+    pending: Vec<Command>,
+    pending_text: String,
+    depth: usize,
+    // Whitespace was skipped in minified output since the last character
+    separated: bool,
+    // Tokens of the source the nodes come from, if it's known
+    tokens: Option<&'a Tokens<'a>>,
+}
+
+/// Text is collected into a buffer before it's written to the sink, to
+/// avoid writing small pieces and to allow trailing spaces of the current
+/// line to be removed
+struct Output<'a> {
+    sink: &'a mut dyn fmt::Write,
+    buf: String,
+    // Column at the end of the text written to the sink
+    column: usize,
+    // The last character written to the sink
+    last: Option<char>,
+    result: fmt::Result,
+}
+
+const OUTPUT_BUFFER: usize = 8192;
+
+impl<'a> Output<'a> {
+    fn new(sink: &'a mut dyn fmt::Write) -> Output<'a> {
+        Output {
+            sink,
+            buf: String::with_capacity(OUTPUT_BUFFER),
+            column: 0,
+            last: None,
+            result: Ok(()),
+        }
+    }
+
+    fn push_str(&mut self, s: &str) {
+        self.buf.push_str(s);
+        if self.buf.len() >= OUTPUT_BUFFER {
+            // Trailing spaces are kept in case they are to be removed
+            self.write_out(self.buf.trim_end_matches(' ').len());
+        }
+    }
+
+    fn push(&mut self, c: char) {
+        self.push_str(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn is_empty(&self) -> bool {
+        self.last.is_none() && self.buf.is_empty()
+    }
+
+    fn last(&self) -> Option<char> {
+        self.buf.chars().next_back().or(self.last)
+    }
+
+    fn column(&self) -> usize {
+        match self.buf.rfind('\n') {
+            Some(idx) => self.buf[idx + 1..].chars().count(),
+            None => self.column + self.buf.chars().count(),
+        }
+    }
+
+    /// Removes spaces at the end of the current line
+    fn trim_end(&mut self) {
+        let trimmed = self.buf.trim_end_matches(' ').len();
+        self.buf.truncate(trimmed);
+    }
+
+    /// Writes `len` bytes of the buffer to the sink
+    fn write_out(&mut self, len: usize) {
+        if len == 0 {
+            return;
+        }
+        self.column = self.column();
+        self.column -= self.buf[len..].chars().count();
+        self.last = self.buf[..len].chars().next_back();
+        if self.result.is_ok() {
+            self.result = self.sink.write_str(&self.buf[..len]);
+        }
+        self.buf.drain(..len);
+    }
+
+    fn finish(mut self) -> fmt::Result {
+        self.write_out(self.buf.len());
+        self.result
+    }
+}
+
+/// A list of items that is either written on a single line or an item per
+/// line, see `Formatter::start_sequence`
+#[derive(Debug, PartialEq)]
+struct Sequence {
+    first: bool,
+    multiline: bool,
+    spacing: bool,
+}
+
+#[derive(Debug, PartialEq)]
+enum Command {
+    /// Range of the text in `pending_text`
+    Text(usize, usize),
+    /// Written as `flat` if the group fits, otherwise as a line break
+    Line {
+        flat: &'static str,
+        indent: u32,
+    },
+    /// Written only when the group doesn't fit
+    IfBreak(&'static str),
+    /// Unconditional line break
+    Newline,
+    Group {
+        broken: bool,
+    },
+    EndGroup,
 }
 
 /// A configuration of formatting style
-///
-/// Currently we only have indentation configured, other things might be
-/// added later (such as minification).
 #[derive(Debug, PartialEq, Clone)]
 pub struct Style {
     indent: u32,
     multiline_arguments: bool,
+    max_width: Option<usize>,
+    minified: bool,
+    descriptions: bool,
+    directives: bool,
+    sort_definitions: bool,
+    group_extensions: bool,
+    sort_fields: bool,
+    sort_arguments: bool,
+    preserve_blank_lines: bool,
 }
 
 impl Default for Style {
@@ -25,11 +155,107 @@ impl Default for Style {
         Style {
             indent: 2,
             multiline_arguments: false,
+            max_width: None,
+            minified: false,
+            descriptions: true,
+            directives: true,
+            sort_definitions: false,
+            group_extensions: false,
+            sort_fields: false,
+            sort_arguments: false,
+            preserve_blank_lines: false,
         }
     }
 }
 
 impl Style {
+    /// Style that prints documents in the most compact form
+    ///
+    /// All insignificant whitespace and commas are omitted, only a single
+    /// space is kept where two names or numbers would otherwise be merged.
+    /// Strings are always printed with escapes, not as block strings.
+    /// Indentation and width settings are ignored.
+    pub fn minified() -> Style {
+        Style {
+            minified: true,
+            ..Style::default()
+        }
+    }
+
+    /// Set whether to print descriptions of schema definitions
+    pub fn descriptions(&mut self, descriptions: bool) -> &mut Self {
+        self.descriptions = descriptions;
+        self
+    }
+
+    /// Set whether to print directives applied to definitions, fields,
+    /// etc.
+    ///
+    /// Directive definitions are printed regardless of this setting.
+    pub fn directives(&mut self, directives: bool) -> &mut Self {
+        self.directives = directives;
+        self
+    }
+
+    /// Set whether to sort definitions by kind and then by name
+    ///
+    /// Schema definition goes first, followed by scalars, object types,
+    /// interfaces, unions, enums, input objects and directive definitions.
+    /// Type extensions follow all the definitions, unless
+    /// `group_extensions` is set. In queries, operations go before
+    /// fragments.
+    pub fn sort_definitions(&mut self, sort_definitions: bool) -> &mut Self {
+        self.sort_definitions = sort_definitions;
+        self
+    }
+
+    /// Set whether to write type extensions right after the type they
+    /// extend
+    ///
+    /// Extensions of types that aren't defined in the document are kept
+    /// in place, or sorted if `sort_definitions` is set.
+    pub fn group_extensions(&mut self, group_extensions: bool) -> &mut Self {
+        self.group_extensions = group_extensions;
+        self
+    }
+
+    /// Set whether to sort fields of object types, interfaces and input
+    /// objects and values of enums by name
+    ///
+    /// Selection sets in queries are never sorted, because the order of
+    /// fields determines the order of the response.
+    pub fn sort_fields(&mut self, sort_fields: bool) -> &mut Self {
+        self.sort_fields = sort_fields;
+        self
+    }
+
+    /// Set whether to sort arguments by name
+    ///
+    /// Applies to both argument definitions of fields and directives and
+    /// arguments passed to fields and directives.
+    pub fn sort_arguments(&mut self, sort_arguments: bool) -> &mut Self {
+        self.sort_arguments = sort_arguments;
+        self
+    }
+
+    /// Set whether to keep blank lines between fields, arguments and enum
+    ///
+    /// Several blank lines in a row are written as a single one, blank
+    /// lines are never kept before the first item or between items that
+    /// are sorted. Comments aren't kept by the parser, so a line with
+    /// a comment is treated as blank. A blank line between arguments
+    /// makes the argument list multiline.
+    ///
+    /// Range formatting knows the source, so blank lines are kept exactly.
+    /// Otherwise only the positions where nodes start are known, so a blank
+    /// line after a description or a multiline list of arguments is only
+    /// kept if they are followed by a line break, as is usual, and one
+    /// after a multiline value isn't noticed.
+    pub fn preserve_blank_lines(&mut self, preserve_blank_lines: bool) -> &mut Self {
+        self.preserve_blank_lines = preserve_blank_lines;
+        self
+    }
+
     /// Change the number of spaces used for indentation
     pub fn indent(&mut self, indent: u32) -> &mut Self {
         self.indent = indent;
@@ -41,6 +267,22 @@ impl Style {
         self.multiline_arguments = multiline_arguments;
         self
     }
+
+    /// Wrap lines that are longer than `max_width` characters
+    ///
+    /// Arguments, variable definitions, list and object values and lists
+    /// of implemented interfaces are kept on a single line if they fit,
+    /// otherwise every item is written on its own line, the way Prettier
+    /// does it (no commas between items of a wrapped list, spaces inside
+    /// of braces of an object). Each list is checked separately, starting
+    /// from the outermost one.
+    ///
+    /// By default lines are never wrapped. When `multiline_arguments` is
+    /// set too, field and directive arguments are always wrapped.
+    pub fn max_width(&mut self, max_width: usize) -> &mut Self {
+        self.max_width = Some(max_width);
+        self
+    }
 }
 
 pub(crate) trait Displayable {
@@ -48,56 +290,174 @@ pub(crate) trait Displayable {
 }
 
 impl<'a> Formatter<'a> {
-    pub fn new(style: &Style) -> Formatter {
+    pub fn new(style: &'a Style, sink: &'a mut dyn fmt::Write) -> Formatter<'a> {
         Formatter {
-            buf: String::with_capacity(1024),
+            out: Output::new(sink),
             style,
             indent: 0,
+            sequences: Vec::new(),
+            pending: Vec::new(),
+            pending_text: String::new(),
+            depth: 0,
+            separated: false,
+            tokens: None,
         }
     }
 
     pub fn indent(&mut self) {
+        if self.style.minified {
+            return;
+        }
         for _ in 0..self.indent {
-            self.buf.push(' ');
+            self.write(" ");
         }
     }
 
     pub fn endline(&mut self) {
-        self.buf.push('\n');
+        if self.style.minified {
+            self.separated = true;
+        } else if self.pending.is_empty() {
+            self.out.push('\n');
+        } else {
+            self.pending.push(Command::Newline);
+            if self.depth == 0 {
+                self.flush();
+            }
+        }
+    }
+
+    pub fn start_argument_block(&mut self, open: char) {
+        self.start_sequence(open, self.style.multiline_arguments);
+    }
+
+    pub fn end_argument_block(&mut self, close: char) {
+        self.end_sequence(close);
+    }
+
+    pub fn start_argument(&mut self) {
+        self.sequence_item();
     }
 
-    pub fn start_argument_block(&mut self, open_char: char) {
-        self.buf.push(open_char);
-        if self.style.multiline_arguments {
+    /// Starts a list of items enclosed in `open` and a matching closing char
+    ///
+    /// Items are separated by commas and written on a single line unless
+    /// `multiline` is set or the list doesn't fit in `max_width`.
+    pub fn start_sequence(&mut self, open: char, multiline: bool) {
+        let width_aware = self.width_aware();
+        if width_aware {
+            self.start_group(multiline);
+        }
+        self.write(open.encode_utf8(&mut [0; 4]));
+        if multiline || width_aware {
             self.inc_indent();
         }
+        self.sequences.push(Sequence {
+            first: true,
+            multiline,
+            spacing: width_aware && open == '{',
+        });
+    }
+
+    /// Starts next item of the sequence
+    pub fn sequence_item(&mut self) {
+        self.item(false);
+    }
+
+    /// Starts next item of the sequence separated from the previous one
+    /// by a blank line, which makes the sequence multiline
+    pub fn sequence_item_after_blank(&mut self) {
+        self.item(true);
     }
 
-    pub fn end_argument_block(&mut self, close_char: char) {
-        if self.style.multiline_arguments {
+    fn item(&mut self, blank: bool) {
+        let width_aware = self.width_aware();
+        let seq = self.sequences.last_mut().expect("sequence started");
+        let first = seq.first;
+        seq.first = false;
+        if width_aware {
+            let flat = match (first, seq.spacing) {
+                (true, true) => " ",
+                (true, false) => "",
+                (false, _) => ", ",
+            };
+            if blank {
+                self.endline();
+            }
+            self.line(flat);
+            return;
+        }
+        let multiline = seq.multiline;
+        if !first {
+            self.write(if multiline { "," } else { ", " });
+        }
+        if multiline {
+            if blank {
+                self.endline();
+            }
             self.endline();
-            self.dec_indent();
             self.indent();
         }
-        self.buf.push(close_char);
     }
 
-    pub fn start_argument(&mut self) {
-        if self.style.multiline_arguments {
+    pub fn end_sequence(&mut self, close: char) {
+        let seq = self.sequences.pop().expect("sequence started");
+        let mut buf = [0; 4];
+        let close = close.encode_utf8(&mut buf);
+        if self.width_aware() {
+            self.dec_indent();
+            if !seq.first {
+                self.line(if seq.spacing { " " } else { "" });
+            }
+            self.write(close);
+            self.end_group();
+            return;
+        }
+        if seq.multiline {
             self.endline();
+            self.dec_indent();
             self.indent();
         }
+        self.write(close);
     }
 
-    pub fn deliniate_argument(&mut self) {
-        self.buf.push(',');
-        if !self.style.multiline_arguments {
-            self.buf.push(' ');
+    /// Writes a list of names, like implemented interfaces, wrapping it
+    /// with a leading separator on each line if it doesn't fit
+    pub fn write_separated<I, S>(&mut self, prefix: &str, separator: &'static str, items: I)
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<str>,
+    {
+        let width_aware = self.width_aware();
+        if width_aware {
+            self.start_group(false);
+            self.write(prefix.trim_end());
+            self.inc_indent();
+        } else {
+            self.write(prefix);
+        }
+        let mut first = true;
+        for item in items {
+            if width_aware {
+                self.line(" ");
+                if first {
+                    self.if_break(separator.trim_start());
+                } else {
+                    self.write(separator.trim_start());
+                }
+            } else if !first {
+                self.write(separator);
+            }
+            first = false;
+            self.write(item.as_ref());
+        }
+        if width_aware {
+            self.dec_indent();
+            self.end_group();
         }
     }
 
     pub fn start_block(&mut self) {
-        self.buf.push('{');
+        self.write("{");
         self.endline();
         self.inc_indent();
     }
@@ -105,66 +465,179 @@ impl<'a> Formatter<'a> {
     pub fn end_block(&mut self) {
         self.dec_indent();
         self.indent();
-        self.buf.push('}');
+        self.write("}");
         self.endline();
     }
 
     pub fn margin(&mut self) {
-        if !self.buf.is_empty() {
-            self.buf.push('\n');
+        if !self.out.is_empty() || !self.pending.is_empty() {
+            self.endline();
         }
     }
 
     pub fn write(&mut self, s: &str) {
-        self.buf.push_str(s);
+        if self.style.minified {
+            self.write_minified(s, false);
+        } else if self.pending.is_empty() {
+            self.out.push_str(s);
+        } else {
+            let start = self.pending_text.len();
+            self.pending_text.push_str(s);
+            self.pending
+                .push(Command::Text(start, self.pending_text.len()));
+        }
     }
 
-    pub fn into_string(self) -> String {
-        self.buf
+    /// Writes out everything that is left and returns the first error
+    /// of the sink
+    pub fn finish(mut self) -> fmt::Result {
+        self.flush();
+        self.out.finish()
     }
 
-    pub fn write_quoted(&mut self, s: &str) {
-        let mut has_newline = false;
-        let mut has_nonprintable = false;
-        for c in s.chars() {
-            match c {
-                '\n' => has_newline = true,
-                '\r' | '\t' | '\u{0020}'..='\u{FFFF}' => {}
-                _ => has_nonprintable = true,
+    /// Writes a string value keeping whether it was a block string
+    ///
+    /// Block strings spanning several lines are written from their value
+    /// at the current indentation. Other strings are quoted, keeping the
+    /// original literal if it's known. Minified output never contains block
+    /// strings.
+    pub fn write_string<'b, T: Text<'b>>(&mut self, s: &StringValue<'b, T>) {
+        if s.is_block() && !self.style.minified && s.contains('\n') && printable_as_block(s) {
+            self.write_block(s);
+            return;
+        }
+        match s.raw() {
+            Some(raw) if !s.is_block() => self.write_raw(raw),
+            _ => self.write_escaped(s),
+        }
+    }
+
+    /// Writes a quoted literal as is
+    fn write_raw(&mut self, raw: &str) {
+        if self.style.minified {
+            self.write_minified(raw, true);
+        } else {
+            self.write(raw);
+        }
+    }
+
+    fn write_block(&mut self, s: &str) {
+        self.write(r#"""""#);
+        self.endline();
+        self.indent += self.style.indent;
+        for line in s.split('\n') {
+            if !line.is_empty() {
+                self.indent();
+                self.write(&line.replace(r#"""""#, r#"\""""#));
             }
+            self.endline();
         }
-        if !has_newline || has_nonprintable {
-            use std::fmt::Write;
-            self.buf.push('"');
-            for c in s.chars() {
-                match c {
-                    '\r' => self.write(r"\r"),
-                    '\n' => self.write(r"\n"),
-                    '\t' => self.write(r"\t"),
-                    '"' => self.write("\\\""),
-                    '\\' => self.write(r"\\"),
-                    '\u{0020}'..='\u{FFFF}' => self.buf.push(c),
-                    _ => write!(&mut self.buf, "\\u{:04}", c as u32).unwrap(),
+        self.indent -= self.style.indent;
+        self.indent();
+        self.write(r#"""""#);
+    }
+
+    fn write_escaped(&mut self, s: &str) {
+        use std::fmt::Write;
+        let mut quoted = String::with_capacity(s.len() + 2);
+        quoted.push('"');
+        for c in s.chars() {
+            match c {
+                '\u{0008}' => quoted.push_str(r"\b"),
+                '\u{000C}' => quoted.push_str(r"\f"),
+                '\r' => quoted.push_str(r"\r"),
+                '\n' => quoted.push_str(r"\n"),
+                '\t' => quoted.push_str(r"\t"),
+                '"' => quoted.push_str("\\\""),
+                '\\' => quoted.push_str(r"\\"),
+                '\u{0000}'..='\u{001F}' | '\u{007F}' => {
+                    write!(&mut quoted, "\\u{:04X}", c as u32).unwrap()
                 }
+                _ => quoted.push(c),
             }
-            self.buf.push('"');
+        }
+        quoted.push('"');
+        if self.style.minified {
+            self.write_minified(&quoted, true);
         } else {
-            self.buf.push_str(r#"""""#);
-            self.endline();
-            self.indent += self.style.indent;
-            for line in s.lines() {
-                if !line.trim().is_empty() {
-                    self.indent();
-                    self.write(&line.replace(r#"""""#, r#"\""""#));
+            self.write(&quoted);
+        }
+    }
+
+    /// Returns `true` if descriptions should be printed
+    pub fn descriptions(&self) -> bool {
+        self.style.descriptions
+    }
+
+    /// Returns `true` if directives should be printed
+    pub fn directives(&self) -> bool {
+        self.style.directives
+    }
+
+    /// Returns `true` if definitions should be sorted
+    pub fn sort_definitions(&self) -> bool {
+        self.style.sort_definitions
+    }
+
+    /// Returns `true` if type extensions should follow their base types
+    pub fn group_extensions(&self) -> bool {
+        self.style.group_extensions
+    }
+
+    /// Returns `true` if fields and enum values should be sorted
+    pub fn sort_fields(&self) -> bool {
+        self.style.sort_fields
+    }
+
+    /// Returns `true` if arguments should be sorted
+    pub fn sort_arguments(&self) -> bool {
+        self.style.sort_arguments
+    }
+
+    /// Returns `true` if there is a blank line between an item that ends
+    /// on line `end` and the next item that starts at `start` in the
+    /// source and it should be kept
+    ///
+    /// If the source is known, the line where the previous token ends is
+    /// used instead of `end`, which is estimated from where nodes start.
+    /// The item is `preceded` if it starts with a token before `start`,
+    /// like `...` of fragments.
+    pub fn blank_line_between(&self, end: usize, start: Pos, preceded: bool) -> bool {
+        if !self.style.preserve_blank_lines {
+            return false;
+        }
+        let end = self
+            .tokens
+            .and_then(|tokens| tokens.end_line_before(start, preceded))
+            .unwrap_or(end);
+        start.line > end + 1
+    }
+
+    /// Writes text skipping whitespace and commas, unless it's `verbatim`
+    fn write_minified(&mut self, s: &str, verbatim: bool) {
+        for c in s.chars() {
+            if !verbatim && (c.is_whitespace() || c == ',') {
+                self.separated = true;
+                continue;
+            }
+            if self.separated {
+                self.separated = false;
+                let word = |c: char| c.is_alphanumeric() || c == '_';
+                match self.out.last() {
+                    Some(prev) if word(prev) && word(c) => self.out.push(' '),
+                    // Empty string followed by a string is a block string
+                    Some('"') if c == '"' => self.out.push(' '),
+                    _ => {}
                 }
-                self.endline();
             }
-            self.indent -= self.style.indent;
-            self.indent();
-            self.buf.push_str(r#"""""#);
+            self.out.push(c);
         }
     }
 
+    fn width_aware(&self) -> bool {
+        self.style.max_width.is_some() && !self.style.minified
+    }
+
     fn inc_indent(&mut self) {
         self.indent += self.style.indent;
     }
@@ -175,24 +648,385 @@ impl<'a> Formatter<'a> {
             .checked_sub(self.style.indent)
             .expect("negative indent");
     }
+
+    fn start_group(&mut self, broken: bool) {
+        self.pending.push(Command::Group { broken });
+        self.depth += 1;
+    }
+
+    fn end_group(&mut self) {
+        self.pending.push(Command::EndGroup);
+        self.depth -= 1;
+    }
+
+    fn line(&mut self, flat: &'static str) {
+        self.pending.push(Command::Line {
+            flat,
+            indent: self.indent,
+        });
+    }
+
+    fn if_break(&mut self, text: &'static str) {
+        self.pending.push(Command::IfBreak(text));
+    }
+
+    /// Lays out pending groups and writes them into the buffer
+    fn flush(&mut self) {
+        let max_width = match self.style.max_width {
+            Some(width) => width,
+            None => return,
+        };
+        let pending = std::mem::take(&mut self.pending);
+        // Whether each of the enclosing groups is written on a single line
+        let mut flat = Vec::new();
+        for (idx, cmd) in pending.iter().enumerate() {
+            let is_flat = flat.last().copied().unwrap_or(false);
+            match *cmd {
+                Command::Text(start, end) => self.out.push_str(&self.pending_text[start..end]),
+                Command::Line { flat: text, .. } if is_flat => self.out.push_str(text),
+                Command::Line { indent, .. } => {
+                    self.out.trim_end();
+                    self.out.push('\n');
+                    for _ in 0..indent {
+                        self.out.push(' ');
+                    }
+                }
+                Command::IfBreak(text) => {
+                    if !is_flat {
+                        self.out.push_str(text);
+                    }
+                }
+                Command::Newline => self.out.push('\n'),
+                Command::Group { broken } => {
+                    let column = self.out.column();
+                    let fits = !broken
+                        && (is_flat
+                            || self.fits(&pending[idx..], max_width.saturating_sub(column)));
+                    flat.push(fits);
+                }
+                Command::EndGroup => {
+                    flat.pop();
+                }
+            }
+        }
+        self.pending_text.clear();
+    }
+
+    /// Checks whether the group at the start of `commands` written on a
+    /// single line fits in `width` together with the text following it
+    /// up to the next possible line break
+    fn fits(&self, commands: &[Command], width: usize) -> bool {
+        let mut width = width as isize;
+        let mut depth = 0;
+        for (idx, cmd) in commands.iter().enumerate() {
+            let inside = depth > 0;
+            match *cmd {
+                Command::Text(start, end) => {
+                    width -= self.pending_text[start..end].chars().count() as isize;
+                }
+                Command::Line { flat, .. } if inside => width -= flat.len() as isize,
+                Command::Line { .. } => return true,
+                Command::IfBreak(text) if !inside => width -= text.len() as isize,
+                Command::IfBreak(_) => {}
+                Command::Newline => return !inside,
+                Command::Group { broken: true } if inside => return false,
+                Command::Group { .. } => {
+                    if inside || idx == 0 {
+                        depth += 1;
+                    }
+                }
+                Command::EndGroup => {
+                    if inside {
+                        depth -= 1;
+                    }
+                }
+            }
+            if width < 0 {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+/// Checks whether the value written as a block string, indented and with
+/// each line on its own, parses back to the same value
+///
+/// Block strings can't contain control characters other than tabs and
+/// line feeds, and leading and trailing blank lines as well as the
+/// indentation common to all lines are stripped from them.
+fn printable_as_block(s: &str) -> bool {
+    if s.is_empty() {
+        return false;
+    }
+    let blank = |line: &str| line.trim_matches(&[' ', '\t'][..]).is_empty();
+    let mut common_indent = true;
+    for line in s.split('\n') {
+        if line
+            .chars()
+            .any(|c| c < ' ' && c != '\t' || c == '\u{007F}')
+        {
+            return false;
+        }
+        if !blank(line) && !line.starts_with(&[' ', '\t'][..]) {
+            common_indent = false;
+        }
+    }
+    let first = s.split('\n').next().unwrap_or("");
+    let last = s.rsplit('\n').next().unwrap_or("");
+    !common_indent && !blank(first) && !blank(last)
+}
+
+/// Returns references to `items`, sorted by `key` if `sort` is set
+///
+/// Sorting is stable, so items with the same key keep their order.
+pub(crate) fn ordered<'x, I, K, F>(items: &'x [I], sort: bool, key: F) -> Vec<&'x I>
+where
+    K: Ord,
+    F: Fn(&'x I) -> K,
+{
+    let mut items: Vec<&I> = items.iter().collect();
+    if sort {
+        items.sort_by_key(|item| key(item));
+    }
+    items
 }
 
 pub(crate) fn format_directives<'a, T>(dirs: &[Directive<'a, T>], f: &mut Formatter)
 where
     T: crate::common::Text<'a>,
 {
+    if !f.directives() {
+        return;
+    }
     for dir in dirs {
         f.write(" ");
         dir.display(f);
     }
 }
 
+/// Formats `value` parsed from the source of `tokens` into a string, with
+/// every line indented by `indent` spaces except the first one
+pub(crate) fn format_indented<D: Displayable>(
+    value: &D,
+    style: &Style,
+    tokens: &Tokens,
+    indent: u32,
+) -> String {
+    let mut buf = String::with_capacity(1024);
+    let mut formatter = Formatter::new(style, &mut buf);
+    formatter.indent = indent;
+    formatter.tokens = Some(tokens);
+    value.display(&mut formatter);
+    formatter.finish().expect("writing to a string never fails");
+    buf
+}
+
+/// Formats `value` into a string
+pub(crate) fn format<D: Displayable>(value: &D, style: &Style) -> String {
+    let mut buf = String::with_capacity(1024);
+    format_to(value, style, &mut buf).expect("writing to a string never fails");
+    buf
+}
+
+/// Formats `value` writing it to `out` as it goes
+pub(crate) fn format_to<D: Displayable>(
+    value: &D,
+    style: &Style,
+    out: &mut dyn fmt::Write,
+) -> fmt::Result {
+    let mut formatter = Formatter::new(style, out);
+    value.display(&mut formatter);
+    formatter.finish()
+}
+
+/// Formats `value` writing it to `out` as it goes
+pub(crate) fn write_to<D: Displayable>(
+    value: &D,
+    style: &Style,
+    out: &mut dyn io::Write,
+) -> io::Result<()> {
+    let mut adapter = IoAdapter {
+        inner: out,
+        error: None,
+    };
+    match format_to(value, style, &mut adapter) {
+        Ok(()) => Ok(()),
+        Err(fmt::Error) => Err(adapter
+            .error
+            .unwrap_or_else(|| io::Error::new(io::ErrorKind::Other, "formatter error"))),
+    }
+}
+
+/// Writes into `io::Write` keeping the error, which can't be returned
+/// through `fmt::Write`
+struct IoAdapter<'a> {
+    inner: &'a mut dyn io::Write,
+    error: Option<io::Error>,
+}
+
+impl<'a> fmt::Write for IoAdapter<'a> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// A replacement of a part of the source, returned by range formatting
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextEdit {
+    /// Byte range of the source to replace
+    pub range: Range<usize>,
+    /// Text to write instead
+    pub new_text: String,
+}
+
+/// Returns byte range of lines from `first` to `last` inclusive
+///
+/// Lines are one-based, like in `Pos`. The range doesn't include the line
+/// break at the end of the `last` line.
+pub fn line_range(source: &str, first: usize, last: usize) -> Range<usize> {
+    let mut start = source.len();
+    let mut end = source.len();
+    for (idx, (line_start, line_end)) in position::lines(source).enumerate() {
+        let line_no = idx + 1;
+        if line_no == first {
+            start = line_start;
+        }
+        if line_no == last {
+            end = line_end;
+            break;
+        }
+    }
+    start..end.max(start)
+}
+
+/// Returns `true` if the `range` selected in an editor overlaps `bytes`
+///
+/// An empty range is a cursor, which overlaps the node it touches.
+pub(crate) fn overlaps(range: &Range<usize>, bytes: &Range<usize>) -> bool {
+    if range.start == range.end {
+        bytes.start <= range.start && range.start <= bytes.end
+    } else {
+        range.start < bytes.end && bytes.start < range.end
+    }
+}
+
+/// Tokens of a valid document with their positions and byte ranges, used
+/// to find which part of the source a node comes from
+pub(crate) struct Tokens<'a> {
+    source: &'a str,
+    tokens: Vec<(Pos, usize, usize)>,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(source: &'a str) -> Tokens<'a> {
+        let mut stream = TokenStream::new(source);
+        let mut tokens = Vec::new();
+        loop {
+            let position = stream.position();
+            let start = stream.offset();
+            match stream.uncons() {
+                Ok(token) => tokens.push((position, start, start + token.value.len())),
+                Err(_) => break,
+            }
+        }
+        Tokens { source, tokens }
+    }
+
+    pub fn len(&self) -> usize {
+        self.tokens.len()
+    }
+
+    /// Index of the token that starts at `position`
+    pub fn find(&self, position: Pos) -> usize {
+        self.tokens
+            .binary_search_by_key(&position, |token| token.0)
+            .expect("node starts with a token")
+    }
+
+    /// Byte range from the start of token `first` to the end of `last`
+    pub fn bytes(&self, first: usize, last: usize) -> Range<usize> {
+        self.tokens[first].1..self.tokens[last].2
+    }
+
+    /// Number of spaces the line containing the token is indented with
+    pub fn indent(&self, token: usize) -> u32 {
+        let start = self.tokens[token].1;
+        let line_start = self.source[..start]
+            .rfind(['\n', '\r'])
+            .map_or(0, |idx| idx + 1);
+        self.source[line_start..start]
+            .chars()
+            .take_while(|&c| c == ' ' || c == '\t')
+            .count() as u32
+    }
+
+    /// Position of the token
+    pub fn position(&self, token: usize) -> Pos {
+        self.tokens[token].0
+    }
+
+    /// Line where the token before the one at `position` ends, or before
+    /// the token preceding it if the node is `preceded`
+    pub fn end_line_before(&self, position: Pos, preceded: bool) -> Option<usize> {
+        let token = self
+            .tokens
+            .binary_search_by_key(&position, |token| token.0)
+            .ok()?;
+        let previous = token.checked_sub(1 + preceded as usize)?;
+        let (start, from, to) = self.tokens[previous];
+        Some(start.line + position::lines(&self.source[from..to]).count() - 1)
+    }
+
+    /// Returns `true` if there are comments between tokens `first` and `last`
+    pub fn has_comments(&self, first: usize, last: usize) -> bool {
+        self.tokens[first..=last]
+            .windows(2)
+            .any(|pair| self.source[pair[0].2..pair[1].1].contains('#'))
+    }
+
+    /// Returns an edit replacing tokens from `first` to `last` with `text`
+    ///
+    /// Nothing is returned if the text is the same or if there are comments
+    /// between the tokens, as they would be lost.
+    pub fn edit(&self, first: usize, last: usize, text: &str) -> Option<TextEdit> {
+        let range = self.bytes(first, last);
+        let text = text.trim_end_matches('\n');
+        if self.has_comments(first, last) || &self.source[range.clone()] == text {
+            return None;
+        }
+        Some(TextEdit {
+            range,
+            new_text: text.to_string(),
+        })
+    }
+
+    /// Returns an edit leaving a single blank line between token `last`
+    /// and the next one, which end and start adjacent definitions
+    ///
+    /// Nothing is returned if there are comments between the definitions.
+    pub fn separate(&self, last: usize) -> Option<TextEdit> {
+        let range = self.tokens[last].2..self.tokens[last + 1].1;
+        let gap = &self.source[range.clone()];
+        if gap.contains('#') || gap == "\n\n" {
+            return None;
+        }
+        Some(TextEdit {
+            range,
+            new_text: "\n\n".to_string(),
+        })
+    }
+}
+
 macro_rules! impl_display {
     ($( $typ: ident, )+) => {
         $(
             impl fmt::Display for $typ {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                    crate::format::format_to(self, &Style::default(), f)
                 }
             }
         )+
@@ -204,9 +1038,70 @@ macro_rules! impl_display {
                 where T: Text<'a>,
             {
                 fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-                    f.write_str(&to_string(self))
+                    crate::format::format_to(self, &Style::default(), f)
                 }
             }
         )+
     };
 }
+
+#[cfg(test)]
+mod test {
+    use super::{printable_as_block, Formatter, Style};
+    use crate::common::StringValue;
+
+    fn write(value: StringValue<'static, String>, style: &Style) -> String {
+        let mut buf = String::new();
+        let mut f = Formatter::new(style, &mut buf);
+        f.write_string(&value);
+        f.finish().unwrap();
+        buf
+    }
+
+    #[test]
+    fn escapes() {
+        let style = Style::default();
+        assert_eq!(
+            write(StringValue::new("a\"b\\c\n\t\r\u{8}\u{c}"), &style),
+            r#""a\"b\\c\n\t\r\b\f""#
+        );
+        assert_eq!(
+            write(StringValue::new("\u{0}\u{1f}\u{7f}"), &style),
+            r#""\u0000\u001F\u007F""#
+        );
+        assert_eq!(
+            write(StringValue::new("é\u{1F600}"), &style),
+            "\"é\u{1F600}\""
+        );
+    }
+
+    #[test]
+    fn block() {
+        let style = Style::default();
+        assert_eq!(
+            write(StringValue::block("a\n  b \"\"\"\n\nc"), &style),
+            "\"\"\"\n  a\n    b \\\"\"\"\n\n  c\n\"\"\""
+        );
+        assert_eq!(
+            write(StringValue::block("a\nb"), &Style::minified()),
+            r#""a\nb""#
+        );
+        assert_eq!(write(StringValue::new("a\nb"), &style), r#""a\nb""#);
+    }
+
+    #[test]
+    fn not_printable_as_block() {
+        assert!(printable_as_block("a\n\n  b"));
+        assert!(printable_as_block("  a\nb"));
+        assert!(!printable_as_block(""));
+        assert!(!printable_as_block("\na"));
+        assert!(!printable_as_block("a\n  "));
+        assert!(!printable_as_block("  a\n\n  b"));
+        assert!(!printable_as_block("a\r\nb"));
+        assert!(!printable_as_block("a\u{0}"));
+        assert_eq!(
+            write(StringValue::block("  a\n  b"), &Style::default()),
+            r#""  a\n  b""#
+        );
+    }
+}
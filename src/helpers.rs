@@ -1,123 +1,203 @@
-use std::marker::PhantomData;
-
-use combine::error::Tracked;
-use combine::stream::easy::{Error, Errors, Info};
-use combine::{satisfy, ParseResult, Parser, StreamOnce};
+//! Primitives of the hand-written recursive descent parser
+//!
+//! Grammar functions peek at the next token to choose an alternative and
+//! never backtrack. When a token doesn't match, the error lists everything
+//! that was expected at that position, in the format that
+//! `combine::easy::Errors` prints.
+use combine::easy::{Error, Errors, Info};
+use combine::error::StreamError;
+use combine::stream::ResetStream;
+use combine::{Positioned, StreamOnce};
 
 use crate::position::Pos;
 use crate::tokenizer::{Kind, Token, TokenStream};
 
 use super::common::Text;
 
-#[derive(Debug, Clone)]
-pub struct TokenMatch<'a> {
-    kind: Kind,
-    phantom: PhantomData<&'a u8>,
-}
+pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
+
+pub type ParseResult<'a, T> = Result<T, InternalError<'a>>;
+
+/// Expected values that are listed in errors when a value doesn't start
+pub const VALUE: &[&str] = &[
+    "IntValue",
+    "FloatValue",
+    "StringValue",
+    "BlockString",
+    "true",
+    "false",
+    "null",
+    "Name",
+];
+
+impl<'a> TokenStream<'a> {
+    /// Returns next token without consuming it, `None` at the end of input
+    pub fn peek(&mut self) -> Result<Option<Token<'a>>, Error<Token<'a>, Token<'a>>> {
+        let checkpoint = self.checkpoint();
+        let result = self.uncons();
+        self.reset(checkpoint).ok();
+        match result {
+            Ok(token) => Ok(Some(token)),
+            Err(ref e) if e == &Error::end_of_input() => Ok(None),
+            Err(e) => Err(e),
+        }
+    }
 
-#[derive(Debug, Clone)]
-pub struct NameMatch<'a, T>
-where
-    T: Text<'a>,
-{
-    phantom: PhantomData<&'a T>,
-}
+    /// Returns next token if it's of `kind`, without consuming it
+    #[inline]
+    pub fn peek_kind(&mut self, kind: Kind) -> Option<Token<'a>> {
+        match self.peek() {
+            Ok(Some(token)) if token.kind == kind => Some(token),
+            _ => None,
+        }
+    }
 
-#[derive(Debug, Clone)]
-pub struct Value<'a> {
-    kind: Kind,
-    value: &'static str,
-    phantom: PhantomData<&'a u8>,
-}
+    /// Returns `true` if next token is the specified punctuator
+    #[inline]
+    pub fn is_punct(&mut self, value: &str) -> bool {
+        self.peek_kind(Kind::Punctuator)
+            .map_or(false, |t| t.value == value)
+    }
 
-pub fn kind<'x>(kind: Kind) -> TokenMatch<'x> {
-    TokenMatch {
-        kind,
-        phantom: PhantomData,
+    /// Returns `true` if next token is the specified keyword
+    #[inline]
+    pub fn is_ident(&mut self, value: &str) -> bool {
+        self.peek_kind(Kind::Name)
+            .map_or(false, |t| t.value == value)
+    }
+
+    /// Returns `true` if next token is a name
+    #[inline]
+    pub fn is_name(&mut self) -> bool {
+        self.peek_kind(Kind::Name).is_some()
     }
-}
 
-pub fn name<'a, T>() -> NameMatch<'a, T>
-where
-    T: Text<'a>,
-{
-    NameMatch {
-        phantom: PhantomData,
+    /// Consumes the next token, which must have been peeked before
+    #[inline]
+    pub fn bump(&mut self) -> Token<'a> {
+        self.uncons().expect("token is peeked")
     }
-}
 
-impl<'a> Parser<TokenStream<'a>> for TokenMatch<'a> {
-    type Output = Token<'a>;
-    type PartialState = ();
+    /// Consumes punctuator if it's the next token
+    #[inline]
+    pub fn eat_punct(&mut self, value: &str) -> bool {
+        if self.is_punct(value) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
 
+    /// Consumes keyword if it's the next token
     #[inline]
-    fn parse_lazy(
+    pub fn eat_ident(&mut self, value: &str) -> bool {
+        if self.is_ident(value) {
+            self.bump();
+            true
+        } else {
+            false
+        }
+    }
+
+    pub fn punct(&mut self, value: &'static str) -> ParseResult<'a, Token<'a>> {
+        self.punct_or(value, &[value])
+    }
+
+    /// Same as `punct` but lists `expected` in the error
+    pub fn punct_or(
         &mut self,
-        input: &mut TokenStream<'a>,
-    ) -> ParseResult<Self::Output, <TokenStream<'a> as StreamOnce>::Error> {
-        satisfy(|c: Token<'a>| c.kind == self.kind).parse_lazy(input)
+        value: &'static str,
+        expected: &[&'static str],
+    ) -> ParseResult<'a, Token<'a>> {
+        if self.is_punct(value) {
+            Ok(self.bump())
+        } else {
+            Err(self.unexpected(expected))
+        }
     }
 
-    fn add_error(&mut self, error: &mut Tracked<Errors<Token<'a>, Token<'a>, Pos>>) {
-        error
-            .error
-            .add_error(Error::Expected(Info::Owned(format!("{:?}", self.kind))));
+    pub fn ident(&mut self, value: &'static str) -> ParseResult<'a, Token<'a>> {
+        if self.is_ident(value) {
+            Ok(self.bump())
+        } else {
+            Err(self.unexpected(&[value]))
+        }
     }
-}
 
-pub fn punct<'s>(value: &'static str) -> Value<'s> {
-    Value {
-        kind: Kind::Punctuator,
-        value,
-        phantom: PhantomData,
+    pub fn kind(&mut self, kind: Kind, expected: &'static str) -> ParseResult<'a, Token<'a>> {
+        if self.peek_kind(kind).is_some() {
+            Ok(self.bump())
+        } else {
+            Err(self.unexpected(&[expected]))
+        }
     }
-}
 
-pub fn ident<'s>(value: &'static str) -> Value<'s> {
-    Value {
-        kind: Kind::Name,
-        value,
-        phantom: PhantomData,
+    pub fn name<T: Text<'a>>(&mut self) -> ParseResult<'a, T::Value> {
+        self.name_or::<T>(&["Name"])
     }
-}
 
-impl<'a> Parser<TokenStream<'a>> for Value<'a> {
-    type Output = Token<'a>;
-    type PartialState = ();
+    /// Same as `name` but lists `expected` in the error
+    pub fn name_or<T: Text<'a>>(&mut self, expected: &[&'static str]) -> ParseResult<'a, T::Value> {
+        if self.is_name() {
+            Ok(T::Value::from(self.bump().value))
+        } else {
+            Err(self.unexpected(expected))
+        }
+    }
 
-    #[inline]
-    fn parse_lazy(
-        &mut self,
-        input: &mut TokenStream<'a>,
-    ) -> ParseResult<Self::Output, <TokenStream<'a> as StreamOnce>::Error> {
-        satisfy(|c: Token<'a>| c.kind == self.kind && c.value == self.value).parse_lazy(input)
+    /// Builds an error for the next token
+    pub fn unexpected(&mut self, expected: &[&'static str]) -> InternalError<'a> {
+        self.unexpected_impl(expected, false)
     }
 
-    fn add_error(&mut self, error: &mut Tracked<<TokenStream<'a> as StreamOnce>::Error>) {
-        error
-            .error
-            .add_error(Error::Expected(Info::Static(self.value)));
+    /// Same as `unexpected`, but for a choice between several parsers
+    ///
+    /// Combinator grammar also reported end of input for tokenizer errors
+    /// there, this keeps the messages the same.
+    pub fn unexpected_choice(&mut self, expected: &[&'static str]) -> InternalError<'a> {
+        self.unexpected_impl(expected, true)
     }
-}
 
-impl<'a, S> Parser<TokenStream<'a>> for NameMatch<'a, S>
-where
-    S: Text<'a>,
-{
-    type Output = S::Value;
-    type PartialState = ();
+    fn unexpected_impl(&mut self, expected: &[&'static str], choice: bool) -> InternalError<'a> {
+        let mut err = Errors::empty(self.position());
+        let mut message = None;
+        match self.peek() {
+            Ok(Some(token)) => err.add_error(Error::Unexpected(Info::Token(token))),
+            Ok(None) => err.add_error(Error::end_of_input()),
+            Err(e @ Error::Unexpected(_)) => {
+                err.add_error(e);
+                if choice {
+                    err.add_error(Error::end_of_input());
+                }
+            }
+            Err(e) => message = Some(e),
+        }
+        for item in expected {
+            err.add_error(Error::Expected(Info::Static(item)));
+        }
+        if let Some(message) = message {
+            err.add_error(message);
+        }
+        err
+    }
 
-    #[inline]
-    fn parse_lazy(
-        &mut self,
-        input: &mut TokenStream<'a>,
-    ) -> ParseResult<Self::Output, <TokenStream<'a> as StreamOnce>::Error> {
-        satisfy(|c: Token<'a>| c.kind == Kind::Name)
-            .map(|t: Token<'a>| -> S::Value { S::Value::from(t.value) })
-            .parse_lazy(input)
+    /// Builds an error at `position` that is not related to a token
+    pub fn error_at(&self, position: Pos, error: Error<Token<'a>, Token<'a>>) -> InternalError<'a> {
+        let mut err = Errors::empty(position);
+        err.add_error(error);
+        err
     }
 
-    fn add_error(&mut self, error: &mut Tracked<Errors<Token<'a>, Token<'a>, Pos>>) {
-        error.error.add_error(Error::Expected(Info::Static("Name")));
+    /// Ensures that there is no input left
+    pub fn eof(&mut self) -> ParseResult<'a, ()> {
+        let mut err = Errors::empty(self.position());
+        match self.peek() {
+            Ok(None) => return Ok(()),
+            Ok(Some(token)) => err.add_error(Error::Unexpected(Info::Token(token))),
+            Err(_) => {}
+        }
+        err.add_error(Error::expected_static_message("end of input"));
+        Err(err)
     }
 }
@@ -0,0 +1,146 @@
+//! Interned strings
+use std::borrow::Borrow;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::fmt;
+use std::ops::Deref;
+use std::sync::Arc;
+
+use crate::common::Text;
+
+thread_local! {
+    static POOL: RefCell<HashSet<Arc<str>>> = RefCell::new(HashSet::new());
+}
+
+/// Interned string, can be used as a `Text` type of the AST
+///
+/// Identical names parsed on the same thread share a single reference
+/// counted allocation. This is useful for big schemas, where the same type
+/// names are repeated thousands of times.
+///
+/// Strings are kept in a thread-local pool until
+/// [`Interned::collect_garbage`] is called.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Interned(Arc<str>);
+
+impl Interned {
+    /// Returns interned string, adding it to the pool if needed
+    pub fn new(s: &str) -> Interned {
+        POOL.with(|pool| {
+            let mut pool = pool.borrow_mut();
+            if let Some(value) = pool.get(s) {
+                return Interned(value.clone());
+            }
+            let value: Arc<str> = Arc::from(s);
+            pool.insert(value.clone());
+            Interned(value)
+        })
+    }
+
+    /// Returns string value
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// Returns `true` if both values point to the same allocation
+    pub fn ptr_eq(&self, other: &Interned) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+
+    /// Removes strings that are not referenced anywhere from the pool of
+    /// the current thread
+    pub fn collect_garbage() {
+        POOL.with(|pool| {
+            pool.borrow_mut()
+                .retain(|value| Arc::strong_count(value) > 1)
+        })
+    }
+
+    /// Returns number of strings in the pool of the current thread
+    pub fn pool_size() -> usize {
+        POOL.with(|pool| pool.borrow().len())
+    }
+}
+
+impl<'a> Text<'a> for Interned {
+    type Value = Interned;
+}
+
+impl<'a> From<&'a str> for Interned {
+    fn from(s: &'a str) -> Interned {
+        Interned::new(s)
+    }
+}
+
+impl AsRef<str> for Interned {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Interned {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Interned {
+    type Target = str;
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.0, f)
+    }
+}
+
+impl fmt::Display for Interned {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Interned;
+    use crate::schema::{parse_schema, Definition, Type, TypeDefinition};
+
+    #[test]
+    fn shared() {
+        let a = Interned::new("User");
+        let b = Interned::from("User");
+        assert!(a.ptr_eq(&b));
+        assert!(!a.ptr_eq(&Interned::new("Users")));
+    }
+
+    #[test]
+    fn parse_shares_names() {
+        let doc = parse_schema::<Interned>("type A { x: A y: A }").unwrap();
+        let fields = match doc.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Object(ref o)) => &o.fields,
+            _ => unreachable!(),
+        };
+        let names: Vec<_> = fields
+            .iter()
+            .map(|f| match f.field_type {
+                Type::NamedType(ref n) => n.clone(),
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(names[0], "A".into());
+        assert!(names[0].ptr_eq(&names[1]));
+    }
+
+    #[test]
+    fn garbage() {
+        let keep = Interned::new("garbage_test_keep");
+        Interned::new("garbage_test_drop");
+        let before = Interned::pool_size();
+        Interned::collect_garbage();
+        assert!(Interned::pool_size() < before);
+        assert!(keep.ptr_eq(&Interned::new("garbage_test_keep")));
+    }
+}
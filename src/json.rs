@@ -0,0 +1,168 @@
+//! Conversions between `Value` and `serde_json::Value`
+//!
+//! Enums become JSON strings, and JSON strings always become GraphQL
+//! strings, since JSON has no way to tell them apart. Integers that don't
+//! fit into `i64` become floats.
+use serde_json::{Map, Number as JsonNumber, Value as Json};
+use thiserror::Error;
+
+use crate::common::{Number, StringValue, Text, Value};
+
+/// Error converting a value from or to JSON
+#[derive(Debug, Error, Clone, PartialEq)]
+pub enum JsonError {
+    #[error("variable `${0}` is not defined")]
+    UndefinedVariable(String),
+    #[error("float {0} can't be represented in JSON")]
+    NonFiniteFloat(f64),
+    #[error("number {0} is out of range")]
+    NumberOutOfRange(JsonNumber),
+}
+
+impl<'a, T: Text<'a>> Value<'a, T> {
+    /// Converts a constant value to JSON
+    ///
+    /// Fails on variables, use `to_json_with_variables` to substitute them.
+    pub fn to_json(&self) -> Result<Json, JsonError> {
+        self.to_json_with_variables(&Map::new())
+    }
+
+    /// Converts a value to JSON replacing variables with their values
+    pub fn to_json_with_variables(&self, variables: &Map<String, Json>) -> Result<Json, JsonError> {
+        let json = match self {
+            Value::Variable(name) => variables
+                .get(name.as_ref())
+                .cloned()
+                .ok_or_else(|| JsonError::UndefinedVariable(name.as_ref().into()))?,
+            Value::Int(Number(value)) => Json::Number((*value).into()),
+            Value::Float(value) => JsonNumber::from_f64(*value)
+                .map(Json::Number)
+                .ok_or(JsonError::NonFiniteFloat(*value))?,
+            Value::String(value) => Json::String(value.as_str().into()),
+            Value::Boolean(value) => Json::Bool(*value),
+            Value::Null => Json::Null,
+            Value::Enum(name) => Json::String(name.as_ref().into()),
+            Value::List(items) => Json::Array(
+                items
+                    .iter()
+                    .map(|item| item.to_json_with_variables(variables))
+                    .collect::<Result<_, _>>()?,
+            ),
+            Value::Object(fields) => Json::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| {
+                        Ok((
+                            name.as_ref().into(),
+                            value.to_json_with_variables(variables)?,
+                        ))
+                    })
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        Ok(json)
+    }
+
+    /// Converts JSON to a value, borrowing object keys from it
+    pub fn from_json(json: &'a Json) -> Result<Self, JsonError> {
+        let value = match json {
+            Json::Null => Value::Null,
+            Json::Bool(value) => Value::Boolean(*value),
+            Json::Number(number) => match number.as_i64() {
+                Some(value) => Value::Int(Number(value)),
+                None => Value::Float(
+                    number
+                        .as_f64()
+                        .ok_or_else(|| JsonError::NumberOutOfRange(number.clone()))?,
+                ),
+            },
+            Json::String(value) => Value::String(StringValue::new(value.as_str())),
+            Json::Array(items) => Value::List(
+                items
+                    .iter()
+                    .map(Value::from_json)
+                    .collect::<Result<_, _>>()?,
+            ),
+            Json::Object(fields) => Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| Ok((name.as_str().into(), Value::from_json(value)?)))
+                    .collect::<Result<_, _>>()?,
+            ),
+        };
+        Ok(value)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use serde_json::{json, Map};
+
+    use super::JsonError;
+    use crate::common::Value;
+    use crate::parse_value;
+
+    #[test]
+    fn to_json() {
+        let value = parse_value::<&str>(
+            r#"{int: 1, float: 1.5, str: "x", bool: true, null: null, enum: RED, list: [1, [2]]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            value.to_json().unwrap(),
+            json!({
+                "int": 1,
+                "float": 1.5,
+                "str": "x",
+                "bool": true,
+                "null": null,
+                "enum": "RED",
+                "list": [1, [2]],
+            })
+        );
+    }
+
+    #[test]
+    fn variables() {
+        let value = parse_value::<String>("{a: [$x], b: $y}").unwrap();
+        assert_eq!(
+            value.to_json().unwrap_err(),
+            JsonError::UndefinedVariable("x".into())
+        );
+        let mut variables = Map::new();
+        variables.insert("x".into(), json!({"z": 1}));
+        assert_eq!(
+            value
+                .to_json_with_variables(&variables)
+                .unwrap_err()
+                .to_string(),
+            "variable `$y` is not defined"
+        );
+        variables.insert("y".into(), json!(null));
+        assert_eq!(
+            value.to_json_with_variables(&variables).unwrap(),
+            json!({"a": [{"z": 1}], "b": null})
+        );
+    }
+
+    #[test]
+    fn non_finite() {
+        let value = Value::<&str>::Float(f64::INFINITY);
+        assert_eq!(
+            value.to_json().unwrap_err(),
+            JsonError::NonFiniteFloat(f64::INFINITY)
+        );
+    }
+
+    #[test]
+    fn from_json() {
+        let json = json!({"a": [1, 2.5, 1.0, u64::MAX], "b": "RED", "c": null, "d": false});
+        let value = Value::<&str>::from_json(&json).unwrap();
+        assert_eq!(
+            value.to_string(),
+            r#"{a: [1, 2.5, 1.0, 1.8446744073709552e19], b: "RED", c: null, d: false}"#
+        );
+        assert_eq!(value.to_json().unwrap()["a"][0], json!(1));
+        assert_eq!(value.to_json().unwrap()["a"][2], json!(1.0));
+    }
+}
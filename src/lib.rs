@@ -95,17 +95,34 @@
 #[macro_use]
 extern crate pretty_assertions;
 
+mod builder;
 mod common;
 #[macro_use]
 mod format;
+pub mod document;
 mod helpers;
+#[cfg(feature = "intern")]
+mod intern;
+#[cfg(feature = "json")]
+mod json;
 mod position;
 pub mod query;
 pub mod schema;
+mod semantic;
 mod tokenizer;
 
-pub use crate::format::Style;
-pub use crate::position::Pos;
+pub use crate::document::parse_document;
+pub use crate::format::{line_range, Style, TextEdit};
+#[cfg(feature = "intern")]
+pub use crate::intern::Interned;
+#[cfg(feature = "json")]
+pub use crate::json::JsonError;
+pub use crate::position::{ColumnUnit, LineIndex, Pos};
 pub use crate::query::minify_query;
 pub use crate::query::parse_query;
+pub use crate::query::{format_query_range, skipped_query_parts};
+pub use crate::query::{parse_directive, parse_selection_set, parse_type, parse_value};
+pub use crate::schema::parse_field_definition;
 pub use crate::schema::parse_schema;
+pub use crate::schema::{format_schema_range, skipped_schema_definitions};
+pub use crate::semantic::{FieldOrder, Semantic, SemanticEq};
@@ -1,6 +1,10 @@
 use std::fmt;
 
 /// Original position of element in source code
+///
+/// Lines are separated by `\n`, `\r\n` or `\r`. Columns are counted in
+/// chars, use [`Pos::column_in`] or [`LineIndex`] to get them in other
+/// units.
 #[derive(PartialOrd, Ord, PartialEq, Eq, Clone, Copy, Default, Hash)]
 pub struct Pos {
     /// One-based line number
@@ -9,6 +13,117 @@ pub struct Pos {
     pub column: usize,
 }
 
+/// Unit to count columns in
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ColumnUnit {
+    /// Bytes of UTF-8, like in Rust strings
+    Bytes,
+    /// Unicode scalar values, which is what `Pos::column` contains
+    Chars,
+    /// UTF-16 code units, like in JavaScript strings and the Language
+    /// Server Protocol
+    Utf16,
+}
+
+impl Pos {
+    /// Returns one-based column counted in `unit`
+    ///
+    /// The `source` must be the one the position comes from. This scans the
+    /// source up to the position, use [`LineIndex`] to convert many
+    /// positions.
+    pub fn column_in(&self, source: &str, unit: ColumnUnit) -> usize {
+        match lines(source).nth(self.line.saturating_sub(1)) {
+            Some((start, end)) => column_in_line(&source[start..end], self.column, unit),
+            None => self.column,
+        }
+    }
+
+    /// Returns byte offset of the position in the `source`
+    ///
+    /// This scans the source up to the position, use [`LineIndex`] to
+    /// convert many positions.
+    pub fn byte_offset(&self, source: &str) -> usize {
+        match lines(source).nth(self.line.saturating_sub(1)) {
+            Some((start, end)) => {
+                let line = &source[start..end];
+                start + column_in_line(line, self.column, ColumnUnit::Bytes) - 1
+            }
+            None => source.len(),
+        }
+    }
+}
+
+/// Byte ranges of the lines of a source, to convert many positions in it
+/// without rescanning the source each time
+#[derive(Debug, Clone)]
+pub struct LineIndex<'a> {
+    source: &'a str,
+    lines: Vec<(usize, usize)>,
+}
+
+impl<'a> LineIndex<'a> {
+    pub fn new(source: &'a str) -> LineIndex<'a> {
+        LineIndex {
+            source,
+            lines: lines(source).collect(),
+        }
+    }
+
+    /// Returns one-based column of the `pos` counted in `unit`
+    pub fn column(&self, pos: Pos, unit: ColumnUnit) -> usize {
+        match self.lines.get(pos.line.saturating_sub(1)) {
+            Some(&(start, end)) => column_in_line(&self.source[start..end], pos.column, unit),
+            None => pos.column,
+        }
+    }
+
+    /// Returns byte offset of the `pos` in the source
+    pub fn byte_offset(&self, pos: Pos) -> usize {
+        match self.lines.get(pos.line.saturating_sub(1)) {
+            Some(&(start, end)) => {
+                let line = &self.source[start..end];
+                start + column_in_line(line, pos.column, ColumnUnit::Bytes) - 1
+            }
+            None => self.source.len(),
+        }
+    }
+}
+
+/// Converts one-based char `column` of the `line` to `unit`
+fn column_in_line(line: &str, column: usize, unit: ColumnUnit) -> usize {
+    let prefix = line.chars().take(column.saturating_sub(1));
+    let len = match unit {
+        ColumnUnit::Bytes => prefix.map(char::len_utf8).sum(),
+        ColumnUnit::Chars => prefix.count(),
+        ColumnUnit::Utf16 => prefix.map(char::len_utf16).sum(),
+    };
+    len + 1
+}
+
+/// Returns byte ranges of lines, without line terminators
+pub(crate) fn lines(source: &str) -> impl Iterator<Item = (usize, usize)> + Clone + '_ {
+    let bytes = source.as_bytes();
+    let mut start = Some(0);
+    std::iter::from_fn(move || {
+        let line_start = start?;
+        match bytes[line_start..]
+            .iter()
+            .position(|&b| b == b'\n' || b == b'\r')
+        {
+            Some(len) => {
+                let end = line_start + len;
+                let crlf = bytes[end] == b'\r' && bytes.get(end + 1) == Some(&b'\n');
+                start = Some(end + if crlf { 2 } else { 1 });
+                Some((line_start, end))
+            }
+            None => {
+                start = None;
+                Some((line_start, bytes.len()))
+            }
+        }
+    })
+}
+
 impl fmt::Debug for Pos {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Pos({}:{})", self.line, self.column)
@@ -20,3 +135,43 @@ impl fmt::Display for Pos {
         write!(f, "{}:{}", self.line, self.column)
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::{lines, ColumnUnit, LineIndex, Pos};
+
+    #[test]
+    fn line_terminators() {
+        assert_eq!(
+            lines("a\nb\r\nc\rd").collect::<Vec<_>>(),
+            vec![(0, 1), (2, 3), (5, 6), (7, 8)]
+        );
+        assert_eq!(lines("a\n").collect::<Vec<_>>(), vec![(0, 1), (2, 2)]);
+    }
+
+    #[test]
+    fn column_units() {
+        let source = "x\r\n\"é😀\" y";
+        let pos = Pos { line: 2, column: 6 };
+        assert_eq!(pos.column_in(source, ColumnUnit::Chars), 6);
+        assert_eq!(pos.column_in(source, ColumnUnit::Bytes), 10);
+        assert_eq!(pos.column_in(source, ColumnUnit::Utf16), 7);
+        assert_eq!(pos.byte_offset(source), 12);
+        assert_eq!(&source[pos.byte_offset(source)..], "y");
+    }
+
+    #[test]
+    fn line_index() {
+        let source = "a\r\n\tb\rc\u{1F600} d";
+        let index = LineIndex::new(source);
+        let pos = Pos { line: 3, column: 3 };
+        assert_eq!(index.column(pos, ColumnUnit::Utf16), 4);
+        assert_eq!(index.column(pos, ColumnUnit::Bytes), 6);
+        assert_eq!(&source[index.byte_offset(pos)..], " d");
+        let tab = Pos { line: 2, column: 2 };
+        assert_eq!(&source[index.byte_offset(tab)..], "b\rc\u{1F600} d");
+        let missing = Pos { line: 4, column: 1 };
+        assert_eq!(index.byte_offset(missing), source.len());
+        assert_eq!(index.column(missing, ColumnUnit::Bytes), 1);
+    }
+}
@@ -0,0 +1,657 @@
+//! Arena allocated query AST
+//!
+//! The whole document, including all lists, is allocated in a single
+//! [`Bump`] arena, so parsing does only a few large allocations and
+//! the document is freed in one step by dropping (or resetting) the arena.
+//! This is useful for servers that parse a query for every request:
+//!
+//! ```rust
+//! use graphql_parser::query::arena::{parse_query, Bump};
+//!
+//! let mut arena = Bump::new();
+//! for _ in 0..3 {
+//!     let doc = parse_query("{ user(id: 1) { name } }", &arena)?;
+//!     assert_eq!(doc.definitions.len(), 1);
+//!     arena.reset();
+//! }
+//! # Ok::<(), graphql_parser::query::ParseError>(())
+//! ```
+//!
+//! The document is built by the same grammar as the regular one. Names
+//! borrow from the source text, so do strings without escape sequences,
+//! values of other strings are allocated in the arena.
+//! The tree mirrors the one in
+//! [`query`](crate::query) except that lists are slices and object values
+//! keep fields in source order (including duplicates). Use
+//! [`Document::to_document`] to get the regular AST, for example to print
+//! the query.
+//!
+//! The feature requires a newer compiler than the rest of the crate:
+//! the minimum supported Rust version of `bumpalo` is 1.71.1.
+use std::collections::BTreeMap;
+
+use bumpalo::collections::Vec as ArenaVec;
+pub use bumpalo::Bump;
+
+use crate::common::{Alloc, Number, Seq, StringValue};
+use crate::position::Pos;
+use crate::query::ast as heap;
+use crate::query::error::ParseError;
+use crate::query::grammar::{document_in, QueryAlloc};
+
+/// Root of query data
+#[derive(Debug, Clone, PartialEq)]
+pub struct Document<'a> {
+    pub definitions: &'a [Definition<'a>],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Definition<'a> {
+    Operation(OperationDefinition<'a>),
+    Fragment(FragmentDefinition<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentDefinition<'a> {
+    pub position: Pos,
+    pub name: &'a str,
+    pub type_condition: TypeCondition<'a>,
+    pub directives: &'a [Directive<'a>],
+    pub selection_set: SelectionSet<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum OperationDefinition<'a> {
+    SelectionSet(SelectionSet<'a>),
+    Query(Query<'a>),
+    Mutation(Mutation<'a>),
+    Subscription(Subscription<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query<'a> {
+    pub position: Pos,
+    pub name: Option<&'a str>,
+    pub variable_definitions: &'a [VariableDefinition<'a>],
+    pub directives: &'a [Directive<'a>],
+    pub selection_set: SelectionSet<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Mutation<'a> {
+    pub position: Pos,
+    pub name: Option<&'a str>,
+    pub variable_definitions: &'a [VariableDefinition<'a>],
+    pub directives: &'a [Directive<'a>],
+    pub selection_set: SelectionSet<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Subscription<'a> {
+    pub position: Pos,
+    pub name: Option<&'a str>,
+    pub variable_definitions: &'a [VariableDefinition<'a>],
+    pub directives: &'a [Directive<'a>],
+    pub selection_set: SelectionSet<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SelectionSet<'a> {
+    pub span: (Pos, Pos),
+    pub items: &'a [Selection<'a>],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct VariableDefinition<'a> {
+    pub position: Pos,
+    pub name: &'a str,
+    pub var_type: Type<'a>,
+    pub default_value: Option<Value<'a>>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Selection<'a> {
+    Field(Field<'a>),
+    FragmentSpread(FragmentSpread<'a>),
+    InlineFragment(InlineFragment<'a>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Field<'a> {
+    pub position: Pos,
+    pub alias: Option<&'a str>,
+    pub name: &'a str,
+    pub arguments: &'a [(&'a str, Value<'a>)],
+    pub directives: &'a [Directive<'a>],
+    pub selection_set: SelectionSet<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FragmentSpread<'a> {
+    pub position: Pos,
+    pub fragment_name: &'a str,
+    pub directives: &'a [Directive<'a>],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum TypeCondition<'a> {
+    On(&'a str),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct InlineFragment<'a> {
+    pub position: Pos,
+    pub type_condition: Option<TypeCondition<'a>>,
+    pub directives: &'a [Directive<'a>],
+    pub selection_set: SelectionSet<'a>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Directive<'a> {
+    pub position: Pos,
+    pub name: &'a str,
+    pub arguments: &'a [(&'a str, Value<'a>)],
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value<'a> {
+    Variable(&'a str),
+    Int(Number),
+    Float(f64),
+    String(StringValue<'a, &'a str>),
+    Boolean(bool),
+    Null,
+    Enum(&'a str),
+    List(&'a [Value<'a>]),
+    /// Fields in the order of the source, duplicates are kept
+    Object(&'a [(&'a str, Value<'a>)]),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Type<'a> {
+    NamedType(&'a str),
+    ListType(&'a Type<'a>),
+    NonNullType(&'a Type<'a>),
+}
+
+impl<'a> Document<'a> {
+    /// Converts the document into a regular heap allocated AST
+    ///
+    /// Names still borrow from the source text.
+    pub fn to_document(&self) -> heap::Document<'a, &'a str> {
+        heap::Document {
+            definitions: self.definitions.iter().map(Definition::convert).collect(),
+        }
+    }
+}
+
+impl<'a> Definition<'a> {
+    fn convert(&self) -> heap::Definition<'a, &'a str> {
+        use self::OperationDefinition as O;
+        use crate::query::ast::OperationDefinition as H;
+        match self {
+            Definition::Operation(O::SelectionSet(s)) => {
+                heap::Definition::Operation(H::SelectionSet(s.convert()))
+            }
+            Definition::Operation(O::Query(q)) => {
+                heap::Definition::Operation(H::Query(heap::Query {
+                    position: q.position,
+                    name: q.name,
+                    variable_definitions: convert_variables(q.variable_definitions),
+                    directives: convert_directives(q.directives),
+                    selection_set: q.selection_set.convert(),
+                }))
+            }
+            Definition::Operation(O::Mutation(m)) => {
+                heap::Definition::Operation(H::Mutation(heap::Mutation {
+                    position: m.position,
+                    name: m.name,
+                    variable_definitions: convert_variables(m.variable_definitions),
+                    directives: convert_directives(m.directives),
+                    selection_set: m.selection_set.convert(),
+                }))
+            }
+            Definition::Operation(O::Subscription(s)) => {
+                heap::Definition::Operation(H::Subscription(heap::Subscription {
+                    position: s.position,
+                    name: s.name,
+                    variable_definitions: convert_variables(s.variable_definitions),
+                    directives: convert_directives(s.directives),
+                    selection_set: s.selection_set.convert(),
+                }))
+            }
+            Definition::Fragment(f) => heap::Definition::Fragment(heap::FragmentDefinition {
+                position: f.position,
+                name: f.name,
+                type_condition: f.type_condition.convert(),
+                directives: convert_directives(f.directives),
+                selection_set: f.selection_set.convert(),
+            }),
+        }
+    }
+}
+
+impl<'a> SelectionSet<'a> {
+    fn convert(&self) -> heap::SelectionSet<'a, &'a str> {
+        heap::SelectionSet {
+            span: self.span,
+            items: self.items.iter().map(Selection::convert).collect(),
+        }
+    }
+}
+
+impl<'a> Selection<'a> {
+    fn convert(&self) -> heap::Selection<'a, &'a str> {
+        match self {
+            Selection::Field(f) => heap::Selection::Field(heap::Field {
+                position: f.position,
+                alias: f.alias,
+                name: f.name,
+                arguments: convert_arguments(f.arguments),
+                directives: convert_directives(f.directives),
+                selection_set: f.selection_set.convert(),
+            }),
+            Selection::FragmentSpread(s) => heap::Selection::FragmentSpread(heap::FragmentSpread {
+                position: s.position,
+                fragment_name: s.fragment_name,
+                directives: convert_directives(s.directives),
+            }),
+            Selection::InlineFragment(i) => heap::Selection::InlineFragment(heap::InlineFragment {
+                position: i.position,
+                type_condition: i.type_condition.as_ref().map(TypeCondition::convert),
+                directives: convert_directives(i.directives),
+                selection_set: i.selection_set.convert(),
+            }),
+        }
+    }
+}
+
+impl<'a> TypeCondition<'a> {
+    fn convert(&self) -> heap::TypeCondition<'a, &'a str> {
+        match *self {
+            TypeCondition::On(name) => heap::TypeCondition::On(name),
+        }
+    }
+}
+
+impl<'a> Type<'a> {
+    fn convert(&self) -> heap::Type<'a, &'a str> {
+        match *self {
+            Type::NamedType(name) => heap::Type::NamedType(name),
+            Type::ListType(inner) => heap::Type::ListType(Box::new(inner.convert())),
+            Type::NonNullType(inner) => heap::Type::NonNullType(Box::new(inner.convert())),
+        }
+    }
+}
+
+impl<'a> Value<'a> {
+    /// Converts value into a regular heap allocated one
+    pub fn to_value(&self) -> heap::Value<'a, &'a str> {
+        match *self {
+            Value::Variable(name) => heap::Value::Variable(name),
+            Value::Int(ref n) => heap::Value::Int(n.clone()),
+            Value::Float(f) => heap::Value::Float(f),
+            Value::String(ref s) => heap::Value::String(s.clone()),
+            Value::Boolean(b) => heap::Value::Boolean(b),
+            Value::Null => heap::Value::Null,
+            Value::Enum(name) => heap::Value::Enum(name),
+            Value::List(items) => heap::Value::List(items.iter().map(Value::to_value).collect()),
+            Value::Object(fields) => heap::Value::Object(
+                fields
+                    .iter()
+                    .map(|(name, value)| (*name, value.to_value()))
+                    .collect::<BTreeMap<_, _>>(),
+            ),
+        }
+    }
+}
+
+fn convert_variables<'a>(
+    variables: &[VariableDefinition<'a>],
+) -> Vec<heap::VariableDefinition<'a, &'a str>> {
+    variables
+        .iter()
+        .map(|v| heap::VariableDefinition {
+            position: v.position,
+            name: v.name,
+            var_type: v.var_type.convert(),
+            default_value: v.default_value.as_ref().map(Value::to_value),
+        })
+        .collect()
+}
+
+fn convert_directives<'a>(directives: &[Directive<'a>]) -> Vec<heap::Directive<'a, &'a str>> {
+    directives
+        .iter()
+        .map(|d| heap::Directive {
+            position: d.position,
+            name: d.name,
+            arguments: convert_arguments(d.arguments),
+        })
+        .collect()
+}
+
+fn convert_arguments<'a>(
+    arguments: &[(&'a str, Value<'a>)],
+) -> Vec<(&'a str, heap::Value<'a, &'a str>)> {
+    arguments
+        .iter()
+        .map(|(name, value)| (*name, value.to_value()))
+        .collect()
+}
+
+impl<'a> Alloc<'a> for &'a Bump {
+    type Text = &'a str;
+    type Value = Value<'a>;
+    type List = ArenaVec<'a, Value<'a>>;
+    type Object = ArenaVec<'a, (&'a str, Value<'a>)>;
+    type Arguments = ArenaVec<'a, (&'a str, Value<'a>)>;
+    type Type = Type<'a>;
+    type Directive = Directive<'a>;
+    type Directives = ArenaVec<'a, Directive<'a>>;
+
+    fn list(self) -> Self::List {
+        ArenaVec::new_in(self)
+    }
+    fn object(self) -> Self::Object {
+        ArenaVec::new_in(self)
+    }
+    fn arguments(self) -> Self::Arguments {
+        ArenaVec::new_in(self)
+    }
+    fn directives(self) -> Self::Directives {
+        ArenaVec::new_in(self)
+    }
+    fn scalar(self, value: heap::Value<'a, &'a str>) -> Self::Value {
+        match value {
+            heap::Value::Variable(name) => Value::Variable(name),
+            heap::Value::Int(n) => Value::Int(n),
+            heap::Value::Float(f) => Value::Float(f),
+            // Escaped strings are unescaped on the heap, keep them in
+            // the arena instead, since it doesn't run destructors
+            heap::Value::String(s) => Value::String(s.move_value(|v| self.alloc_str(v))),
+            heap::Value::Boolean(b) => Value::Boolean(b),
+            heap::Value::Null => Value::Null,
+            heap::Value::Enum(name) => Value::Enum(name),
+            heap::Value::List(_) | heap::Value::Object(_) => {
+                unreachable!("scalar is not a list or an object")
+            }
+        }
+    }
+    fn list_value(self, items: Self::List) -> Self::Value {
+        Value::List(items.into_bump_slice())
+    }
+    fn object_value(self, fields: Self::Object) -> Self::Value {
+        Value::Object(fields.into_bump_slice())
+    }
+    fn named_type(self, name: &'a str) -> Self::Type {
+        Type::NamedType(name)
+    }
+    fn list_type(self, inner: Self::Type) -> Self::Type {
+        Type::ListType(self.alloc(inner))
+    }
+    fn non_null_type(self, inner: Self::Type) -> Self::Type {
+        Type::NonNullType(self.alloc(inner))
+    }
+    fn directive(
+        self,
+        position: Pos,
+        name: &'a str,
+        arguments: Self::Arguments,
+    ) -> Self::Directive {
+        Directive {
+            position,
+            name,
+            arguments: arguments.into_bump_slice(),
+        }
+    }
+}
+
+impl<'a> QueryAlloc<'a> for &'a Bump {
+    type VariableDefinition = VariableDefinition<'a>;
+    type VariableDefinitions = ArenaVec<'a, VariableDefinition<'a>>;
+    type Selection = Selection<'a>;
+    type Selections = ArenaVec<'a, Selection<'a>>;
+    type SelectionSet = SelectionSet<'a>;
+    type Definition = Definition<'a>;
+    type Definitions = ArenaVec<'a, Definition<'a>>;
+    type Document = Document<'a>;
+
+    fn variable_definitions(self) -> Self::VariableDefinitions {
+        ArenaVec::new_in(self)
+    }
+    fn selections(self) -> Self::Selections {
+        ArenaVec::new_in(self)
+    }
+    fn definitions(self) -> Self::Definitions {
+        ArenaVec::new_in(self)
+    }
+
+    fn variable_definition(
+        self,
+        position: Pos,
+        name: &'a str,
+        var_type: Type<'a>,
+        default_value: Option<Value<'a>>,
+    ) -> Self::VariableDefinition {
+        VariableDefinition {
+            position,
+            name,
+            var_type,
+            default_value,
+        }
+    }
+    fn field(
+        self,
+        position: Pos,
+        alias: Option<&'a str>,
+        name: &'a str,
+        arguments: Self::Arguments,
+        directives: Self::Directives,
+        selection_set: SelectionSet<'a>,
+    ) -> Self::Selection {
+        Selection::Field(Field {
+            position,
+            alias,
+            name,
+            arguments: arguments.into_bump_slice(),
+            directives: directives.into_bump_slice(),
+            selection_set,
+        })
+    }
+    fn fragment_spread(
+        self,
+        position: Pos,
+        fragment_name: &'a str,
+        directives: Self::Directives,
+    ) -> Self::Selection {
+        Selection::FragmentSpread(FragmentSpread {
+            position,
+            fragment_name,
+            directives: directives.into_bump_slice(),
+        })
+    }
+    fn inline_fragment(
+        self,
+        position: Pos,
+        type_condition: Option<&'a str>,
+        directives: Self::Directives,
+        selection_set: SelectionSet<'a>,
+    ) -> Self::Selection {
+        Selection::InlineFragment(InlineFragment {
+            position,
+            type_condition: type_condition.map(TypeCondition::On),
+            directives: directives.into_bump_slice(),
+            selection_set,
+        })
+    }
+    fn selection_set(self, span: (Pos, Pos), items: Self::Selections) -> Self::SelectionSet {
+        SelectionSet {
+            span,
+            items: items.into_bump_slice(),
+        }
+    }
+    fn operation(
+        self,
+        keyword: &'a str,
+        position: Pos,
+        name: Option<&'a str>,
+        variable_definitions: Self::VariableDefinitions,
+        directives: Self::Directives,
+        selection_set: SelectionSet<'a>,
+    ) -> Self::Definition {
+        let variable_definitions = variable_definitions.into_bump_slice();
+        let directives = directives.into_bump_slice();
+        Definition::Operation(match keyword {
+            "query" => OperationDefinition::Query(Query {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            "mutation" => OperationDefinition::Mutation(Mutation {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            _ => OperationDefinition::Subscription(Subscription {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+        })
+    }
+    fn shorthand(self, selection_set: SelectionSet<'a>) -> Self::Definition {
+        Definition::Operation(OperationDefinition::SelectionSet(selection_set))
+    }
+    fn fragment_definition(
+        self,
+        position: Pos,
+        name: &'a str,
+        type_condition: &'a str,
+        directives: Self::Directives,
+        selection_set: SelectionSet<'a>,
+    ) -> Self::Definition {
+        Definition::Fragment(FragmentDefinition {
+            position,
+            name,
+            type_condition: TypeCondition::On(type_condition),
+            directives: directives.into_bump_slice(),
+            selection_set,
+        })
+    }
+    fn document(self, definitions: Self::Definitions) -> Self::Document {
+        Document {
+            definitions: definitions.into_bump_slice(),
+        }
+    }
+}
+
+impl<'a, T> Seq<T> for ArenaVec<'a, T> {
+    fn push(&mut self, item: T) {
+        ArenaVec::push(self, item);
+    }
+    fn is_empty(&self) -> bool {
+        ArenaVec::is_empty(self)
+    }
+}
+
+/// Parses a piece of query language into a document allocated in `arena`
+///
+/// Errors are the same as of [`crate::query::parse_query`].
+pub fn parse_query<'a>(s: &'a str, arena: &'a Bump) -> Result<Document<'a>, ParseError> {
+    document_in(s, arena)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{parse_query, Bump, Definition, OperationDefinition, Selection, Value};
+
+    #[test]
+    fn same_as_heap() {
+        let arena = Bump::new();
+        for entry in fs::read_dir("tests/queries").unwrap() {
+            let path = entry.unwrap().path();
+            let source = fs::read_to_string(&path).unwrap();
+            let doc = parse_query(&source, &arena).unwrap();
+            assert_eq!(
+                doc.to_document(),
+                crate::query::parse_query::<&str>(&source).unwrap(),
+                "{}",
+                path.display(),
+            );
+        }
+    }
+
+    #[test]
+    fn same_errors() {
+        let arena = Bump::new();
+        for source in &[
+            "{ a",
+            "query Q($x: [Int) { a }",
+            "{ a(x: {y: }) }",
+            "",
+            "{ ...on }",
+            "{ a . }",
+            "fragment F on T .",
+        ] {
+            assert_eq!(
+                parse_query(source, &arena).unwrap_err().to_string(),
+                crate::query::parse_query::<&str>(source)
+                    .unwrap_err()
+                    .to_string(),
+            );
+        }
+    }
+
+    #[test]
+    fn object_order() {
+        let arena = Bump::new();
+        let doc = parse_query(r#"{ a(x: {b: "1", a: 2, b: 3}) }"#, &arena).unwrap();
+        let value = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref s)) => match s.items[0] {
+                Selection::Field(ref f) => &f.arguments[0].1,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let fields = [
+            ("b", Value::String("1".into())),
+            ("a", Value::Int(2.into())),
+            ("b", Value::Int(3.into())),
+        ];
+        assert_eq!(value, &Value::Object(&fields));
+        assert_eq!(value.to_value().to_string(), r#"{a: 2, b: 3}"#,);
+    }
+
+    #[test]
+    fn strings() {
+        let arena = Bump::new();
+        let source = r#"{ a(x: "\u0041", y: """b""") }"#;
+        let doc = parse_query(source, &arena).unwrap();
+        let heap = crate::query::parse_query::<&str>(source).unwrap();
+        assert_eq!(doc.to_document().to_string(), heap.to_string());
+        let arguments = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref s)) => match s.items[0] {
+                Selection::Field(ref f) => f.arguments,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        match (&arguments[0].1, &arguments[1].1) {
+            (Value::String(x), Value::String(y)) => {
+                assert_eq!(x.as_str(), "A");
+                assert_eq!(x.raw(), Some(r#""\u0041""#));
+                assert!(y.is_block());
+            }
+            _ => unreachable!(),
+        }
+    }
+}
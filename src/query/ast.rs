@@ -5,7 +5,7 @@
 //!
 //! [graphql grammar]: http://facebook.github.io/graphql/October2016/#sec-Appendix-Grammar-Summary
 //!
-pub use crate::common::{Directive, Number, Text, Type, Value};
+pub use crate::common::{Directive, Number, StringValue, Text, Type, Value};
 use crate::position::Pos;
 
 /// Root of query data
@@ -16,19 +16,13 @@ pub struct Document<'a, T: Text<'a>> {
 
 impl<'a> Document<'a, String> {
     pub fn into_static(self) -> Document<'static, String> {
-        // To support both reference and owned values in the AST,
-        // all string data is represented with the ::common::Str<'a, T: Text<'a>>
-        // wrapper type.
-        // This type must carry the lifetime of the query string,
-        // and is stored in a PhantomData value on the Str type.
-        // When using owned String types, the actual lifetime of
-        // the Ast nodes is 'static, since no references are kept,
-        // but the nodes will still carry the input lifetime.
-        // To continue working with Document<String> in a owned fasion
-        // the lifetime needs to be transmuted to 'static.
-        //
-        // This is safe because no references are present.
-        // Just the PhantomData lifetime reference is transmuted away.
+        // The AST is generic over the string type and carries the lifetime
+        // of the source. The only reference the lifetime can be used for
+        // besides `T::Value` is a string value allocated elsewhere
+        // (`Unescaped::Borrowed`), which is only created for
+        // `StringValue<'a, &'a str>` by `move_value`. So with `String`
+        // texts nothing is borrowed and the lifetime can be transmuted to
+        // 'static.
         unsafe { std::mem::transmute::<_, Document<'static, String>>(self) }
     }
 }
@@ -89,6 +83,15 @@ pub struct SelectionSet<'a, T: Text<'a>> {
     pub items: Vec<Selection<'a, T>>,
 }
 
+impl<'a, T: Text<'a>> Default for SelectionSet<'a, T> {
+    fn default() -> Self {
+        Self {
+            span: (Pos::default(), Pos::default()),
+            items: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct VariableDefinition<'a, T: Text<'a>> {
     pub position: Pos,
@@ -114,6 +117,19 @@ pub struct Field<'a, T: Text<'a>> {
     pub selection_set: SelectionSet<'a, T>,
 }
 
+impl<'a, T: Text<'a>> Field<'a, T> {
+    pub fn new(name: T::Value) -> Self {
+        Self {
+            position: Pos::default(),
+            alias: None,
+            name,
+            arguments: vec![],
+            directives: vec![],
+            selection_set: SelectionSet::default(),
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct FragmentSpread<'a, T: Text<'a>> {
     pub position: Pos,
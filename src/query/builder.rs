@@ -0,0 +1,343 @@
+//! Builders for constructing query ASTs in code
+//!
+//! Every node is created with a default position, so the result is meant to
+//! be formatted rather than mapped back to a source:
+//!
+//! ```rust
+//! use graphql_parser::query::builder::*;
+//! use graphql_parser::query::{Document, Value};
+//!
+//! let doc: Document<String> = document().definition(
+//!     query()
+//!         .name("User")
+//!         .variable("id", non_null(named("ID")))
+//!         .selection(
+//!             field("user")
+//!                 .argument("id", Value::Variable("id".into()))
+//!                 .selection(field("name")),
+//!         ),
+//! );
+//! assert_eq!(doc.to_string(), "\
+//! query User($id: ID!) {
+//!   user(id: $id) {
+//!     name
+//!   }
+//! }
+//! ");
+//! ```
+pub use crate::builder::{directive, list, named, non_null};
+use crate::common::{Directive, Text, Type, Value};
+use crate::position::Pos;
+use crate::query::ast::*;
+
+/// Returns an empty document
+pub fn document<'a, T: Text<'a>>() -> Document<'a, T> {
+    Document {
+        definitions: vec![],
+    }
+}
+
+/// Returns an anonymous query operation
+pub fn query<'a, T: Text<'a>>() -> Query<'a, T> {
+    Query {
+        position: Pos::default(),
+        name: None,
+        variable_definitions: vec![],
+        directives: vec![],
+        selection_set: SelectionSet::default(),
+    }
+}
+
+/// Returns an anonymous mutation operation
+pub fn mutation<'a, T: Text<'a>>() -> Mutation<'a, T> {
+    Mutation {
+        position: Pos::default(),
+        name: None,
+        variable_definitions: vec![],
+        directives: vec![],
+        selection_set: SelectionSet::default(),
+    }
+}
+
+/// Returns an anonymous subscription operation
+pub fn subscription<'a, T: Text<'a>>() -> Subscription<'a, T> {
+    Subscription {
+        position: Pos::default(),
+        name: None,
+        variable_definitions: vec![],
+        directives: vec![],
+        selection_set: SelectionSet::default(),
+    }
+}
+
+/// Returns an empty selection set, formatted as a shorthand query
+pub fn selection_set<'a, T: Text<'a>>() -> SelectionSet<'a, T> {
+    SelectionSet::default()
+}
+
+/// Returns a fragment definition on `type_condition`
+pub fn fragment<'a, T: Text<'a>>(
+    name: impl Into<T::Value>,
+    type_condition: impl Into<T::Value>,
+) -> FragmentDefinition<'a, T> {
+    FragmentDefinition {
+        position: Pos::default(),
+        name: name.into(),
+        type_condition: TypeCondition::On(type_condition.into()),
+        directives: vec![],
+        selection_set: SelectionSet::default(),
+    }
+}
+
+/// Returns a field selection
+pub fn field<'a, T: Text<'a>>(name: impl Into<T::Value>) -> Field<'a, T> {
+    Field::new(name.into())
+}
+
+/// Returns a fragment spread, like `...name`
+pub fn spread<'a, T: Text<'a>>(name: impl Into<T::Value>) -> FragmentSpread<'a, T> {
+    FragmentSpread {
+        position: Pos::default(),
+        fragment_name: name.into(),
+        directives: vec![],
+    }
+}
+
+/// Returns an inline fragment without a type condition
+pub fn inline_fragment<'a, T: Text<'a>>() -> InlineFragment<'a, T> {
+    InlineFragment {
+        position: Pos::default(),
+        type_condition: None,
+        directives: vec![],
+        selection_set: SelectionSet::default(),
+    }
+}
+
+/// Returns a variable definition for `add_variable`
+pub fn variable<'a, T: Text<'a>>(
+    name: impl Into<T::Value>,
+    var_type: Type<'a, T>,
+) -> VariableDefinition<'a, T> {
+    VariableDefinition {
+        position: Pos::default(),
+        name: name.into(),
+        var_type,
+        default_value: None,
+    }
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Appends a definition
+    pub fn definition(mut self, definition: impl Into<Definition<'a, T>>) -> Self {
+        self.definitions.push(definition.into());
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> SelectionSet<'a, T> {
+    /// Appends a selection
+    pub fn selection(mut self, selection: impl Into<Selection<'a, T>>) -> Self {
+        self.items.push(selection.into());
+        self
+    }
+}
+
+macro_rules! impl_operation {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Sets the operation name
+                pub fn name(mut self, name: impl Into<T::Value>) -> Self {
+                    self.name = Some(name.into());
+                    self
+                }
+
+                /// Appends a variable without a default value
+                pub fn variable(self, name: impl Into<T::Value>, var_type: Type<'a, T>) -> Self {
+                    self.add_variable(variable(name, var_type))
+                }
+
+                /// Appends a variable built with [`variable`]
+                pub fn add_variable(mut self, variable: VariableDefinition<'a, T>) -> Self {
+                    self.variable_definitions.push(variable);
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_operation!(Query, Mutation, Subscription);
+
+macro_rules! impl_directive {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Appends a directive
+                pub fn directive(mut self, directive: Directive<'a, T>) -> Self {
+                    self.directives.push(directive);
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_directive!(
+    Query,
+    Mutation,
+    Subscription,
+    FragmentDefinition,
+    Field,
+    FragmentSpread,
+    InlineFragment
+);
+
+macro_rules! impl_selection {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Appends a selection
+                pub fn selection(mut self, selection: impl Into<Selection<'a, T>>) -> Self {
+                    self.selection_set.items.push(selection.into());
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_selection!(
+    Query,
+    Mutation,
+    Subscription,
+    FragmentDefinition,
+    Field,
+    InlineFragment
+);
+
+impl<'a, T: Text<'a>> Field<'a, T> {
+    /// Sets the alias
+    pub fn alias(mut self, alias: impl Into<T::Value>) -> Self {
+        self.alias = Some(alias.into());
+        self
+    }
+
+    /// Appends an argument
+    pub fn argument(mut self, name: impl Into<T::Value>, value: Value<'a, T>) -> Self {
+        self.arguments.push((name.into(), value));
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> InlineFragment<'a, T> {
+    /// Sets the type condition
+    pub fn on(mut self, type_condition: impl Into<T::Value>) -> Self {
+        self.type_condition = Some(TypeCondition::On(type_condition.into()));
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> VariableDefinition<'a, T> {
+    /// Sets the default value
+    pub fn default_value(mut self, value: Value<'a, T>) -> Self {
+        self.default_value = Some(value);
+        self
+    }
+}
+
+macro_rules! impl_into {
+    ($($typ: ident => $outer: ident :: $variant: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> From<$typ<'a, T>> for $outer<'a, T> {
+                fn from(value: $typ<'a, T>) -> Self {
+                    $outer::$variant(value)
+                }
+            }
+        )+
+    };
+}
+
+impl_into!(
+    Field => Selection::Field,
+    FragmentSpread => Selection::FragmentSpread,
+    InlineFragment => Selection::InlineFragment,
+    OperationDefinition => Definition::Operation,
+    FragmentDefinition => Definition::Fragment,
+    SelectionSet => OperationDefinition::SelectionSet,
+    Query => OperationDefinition::Query,
+    Mutation => OperationDefinition::Mutation,
+    Subscription => OperationDefinition::Subscription
+);
+
+macro_rules! impl_operation_into_definition {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> From<$typ<'a, T>> for Definition<'a, T> {
+                fn from(value: $typ<'a, T>) -> Self {
+                    Definition::Operation(value.into())
+                }
+            }
+        )+
+    };
+}
+
+impl_operation_into_definition!(SelectionSet, Query, Mutation, Subscription);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::query::parse_query;
+
+    #[test]
+    fn shorthand() {
+        let doc: Document<&str> =
+            document().definition(selection_set().selection(field("user").alias("me")));
+        assert_eq!(doc.to_string(), "{\n  me: user\n}\n");
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = "\
+mutation Like($id: ID!, $times: [Int] = 1) @trace {
+  like(post: $id, times: $times) {
+    ...Post
+    ... on Video @include(if: true) {
+      length
+    }
+  }
+}
+
+fragment Post on Post {
+  id
+}
+";
+        let built: Document<String> = document()
+            .definition(
+                mutation()
+                    .name("Like")
+                    .variable("id", non_null(named("ID")))
+                    .add_variable(
+                        variable("times", list(named("Int"))).default_value(Value::Int(1.into())),
+                    )
+                    .directive(directive("trace"))
+                    .selection(
+                        field("like")
+                            .argument("post", Value::Variable("id".into()))
+                            .argument("times", Value::Variable("times".into()))
+                            .selection(spread("Post"))
+                            .selection(
+                                inline_fragment()
+                                    .on("Video")
+                                    .directive(
+                                        directive("include").argument("if", Value::Boolean(true)),
+                                    )
+                                    .selection(field("length")),
+                            ),
+                    ),
+            )
+            .definition(fragment("Post", "Post").selection(field("id")));
+        assert_eq!(built.to_string(), source);
+        assert_eq!(parse_query::<String>(source).unwrap().to_string(), source);
+    }
+}
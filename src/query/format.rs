@@ -1,33 +1,188 @@
 use std::fmt;
+use std::io;
+use std::ops::Range;
 
-use crate::format::{format_directives, Displayable, Formatter, Style};
+use crate::format::{self, format_directives, ordered, overlaps, Displayable, Formatter, Style};
+use crate::format::{TextEdit, Tokens};
 
+use crate::position::Pos;
 use crate::query::ast::*;
+use crate::query::{parse_query, ParseError};
 
 impl<'a, T: Text<'a>> Document<'a, T> {
     /// Format a document according to style
     pub fn format(&self, style: &Style) -> String {
-        let mut formatter = Formatter::new(style);
-        self.display(&mut formatter);
-        formatter.into_string()
+        format::format(self, style)
+    }
+
+    /// Format a document according to style writing it to `out`
+    ///
+    /// Unlike `format` it doesn't keep the whole output in memory.
+    pub fn format_to<W: fmt::Write>(&self, style: &Style, out: &mut W) -> fmt::Result {
+        format::format_to(self, style, out)
+    }
+
+    /// Format a document according to style writing it to `out`
+    ///
+    /// Output is buffered, so `out` doesn't need to be.
+    pub fn write_to<W: io::Write>(&self, style: &Style, out: &mut W) -> io::Result<()> {
+        format::write_to(self, style, out)
+    }
+}
+
+/// Reformats the parts of the query that overlap byte `range` of the source
+///
+/// Every definition overlapping the range is reformatted, unless the range
+/// is inside a nested selection set. Then only the innermost selection set
+/// containing the whole range is. Nothing else in the source is changed,
+/// except that adjacent definitions in the range are separated by a single
+/// blank line. Definitions and selection sets containing comments are left
+/// intact, as comments would be lost, use `skipped_query_parts` to find
+/// them.
+///
+/// Returns the edits ordered by their position. Use `line_range` to get
+/// a range of lines.
+pub fn format_query_range(
+    source: &str,
+    range: Range<usize>,
+    style: &Style,
+) -> Result<Vec<TextEdit>, ParseError> {
+    range_edits(source, range, style).map(|(edits, _)| edits)
+}
+
+/// Returns positions of the definitions and selection sets that
+/// `format_query_range` leaves intact for byte `range`, because they
+/// contain comments
+pub fn skipped_query_parts(source: &str, range: Range<usize>) -> Result<Vec<Pos>, ParseError> {
+    range_edits(source, range, &Style::default()).map(|(_, skipped)| skipped)
+}
+
+fn range_edits(
+    source: &str,
+    range: Range<usize>,
+    style: &Style,
+) -> Result<(Vec<TextEdit>, Vec<Pos>), ParseError> {
+    let document = parse_query::<&str>(source)?;
+    let tokens = Tokens::new(source);
+    let starts: Vec<usize> = document
+        .definitions
+        .iter()
+        .map(|def| tokens.find(definition_position(def)))
+        .collect();
+    let mut edits = Vec::new();
+    let mut skipped = Vec::new();
+    let mut preceded = false;
+    for (idx, def) in document.definitions.iter().enumerate() {
+        let first = starts[idx];
+        let last = starts.get(idx + 1).copied().unwrap_or(tokens.len()) - 1;
+        if !overlaps(&range, &tokens.bytes(first, last)) {
+            continue;
+        }
+        // Definitions in the range are adjacent
+        if preceded {
+            edits.extend(tokens.separate(first - 1));
+        }
+        preceded = true;
+        let (first, last, text) =
+            match innermost_selection_set(top_selection_set(def), &range, &tokens) {
+                Some(set) => {
+                    let first = tokens.find(set.span.0);
+                    let text = format::format_indented(
+                        &NestedSelectionSet(set),
+                        style,
+                        &tokens,
+                        tokens.indent(first),
+                    );
+                    (first, tokens.find(set.span.1), text)
+                }
+                None => (first, last, format::format_indented(def, style, &tokens, 0)),
+            };
+        if tokens.has_comments(first, last) {
+            skipped.push(tokens.position(first));
+        }
+        edits.extend(tokens.edit(first, last, &text));
+    }
+    Ok((edits, skipped))
+}
+
+fn definition_position<'a, T: Text<'a>>(definition: &Definition<'a, T>) -> Pos {
+    match *definition {
+        Definition::Operation(OperationDefinition::SelectionSet(ref set)) => set.span.0,
+        Definition::Operation(OperationDefinition::Query(ref q)) => q.position,
+        Definition::Operation(OperationDefinition::Mutation(ref m)) => m.position,
+        Definition::Operation(OperationDefinition::Subscription(ref s)) => s.position,
+        Definition::Fragment(ref frag) => frag.position,
     }
 }
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
-    v.display(&mut formatter);
-    formatter.into_string()
+fn top_selection_set<'x, 'a, T: Text<'a>>(
+    definition: &'x Definition<'a, T>,
+) -> &'x SelectionSet<'a, T> {
+    match *definition {
+        Definition::Operation(OperationDefinition::SelectionSet(ref set)) => set,
+        Definition::Operation(OperationDefinition::Query(ref q)) => &q.selection_set,
+        Definition::Operation(OperationDefinition::Mutation(ref m)) => &m.selection_set,
+        Definition::Operation(OperationDefinition::Subscription(ref s)) => &s.selection_set,
+        Definition::Fragment(ref frag) => &frag.selection_set,
+    }
+}
+
+/// Finds the innermost selection set nested in `set` which contains the
+/// whole `range`
+fn innermost_selection_set<'x, 'a, T: Text<'a>>(
+    set: &'x SelectionSet<'a, T>,
+    range: &Range<usize>,
+    tokens: &Tokens,
+) -> Option<&'x SelectionSet<'a, T>> {
+    for item in &set.items {
+        let nested = match *item {
+            Selection::Field(ref fld) => &fld.selection_set,
+            Selection::InlineFragment(ref frag) => &frag.selection_set,
+            Selection::FragmentSpread(_) => continue,
+        };
+        if nested.items.is_empty() {
+            continue;
+        }
+        let bytes = tokens.bytes(tokens.find(nested.span.0), tokens.find(nested.span.1));
+        if bytes.start <= range.start && range.end <= bytes.end {
+            return innermost_selection_set(nested, range, tokens).or(Some(nested));
+        }
+    }
+    None
+}
+
+/// Selection set of a field or an inline fragment, written without
+/// anything before the opening brace
+struct NestedSelectionSet<'x, 'a, T: Text<'a>>(&'x SelectionSet<'a, T>);
+
+impl<'x, 'a, T: Text<'a>> Displayable for NestedSelectionSet<'x, 'a, T> {
+    fn display(&self, f: &mut Formatter) {
+        f.start_block();
+        format_selections(&self.0.items, f);
+        f.end_block();
+    }
 }
 
 impl<'a, T: Text<'a>> Displayable for Document<'a, T> {
     fn display(&self, f: &mut Formatter) {
-        for item in &self.definitions {
+        for item in ordered(&self.definitions, f.sort_definitions(), definition_key) {
             item.display(f);
         }
     }
 }
 
+/// Operations go first, then fragments, each sorted by name
+fn definition_key<'x, 'a, T: Text<'a>>(definition: &'x Definition<'a, T>) -> (u8, &'x str) {
+    let name = match *definition {
+        Definition::Operation(OperationDefinition::SelectionSet(_)) => None,
+        Definition::Operation(OperationDefinition::Query(ref q)) => q.name.as_ref(),
+        Definition::Operation(OperationDefinition::Mutation(ref m)) => m.name.as_ref(),
+        Definition::Operation(OperationDefinition::Subscription(ref s)) => s.name.as_ref(),
+        Definition::Fragment(ref frag) => return (1, frag.name.as_ref()),
+    };
+    (0, name.map_or("", |name| name.as_ref()))
+}
+
 impl<'a, T: Text<'a>> Displayable for Definition<'a, T> {
     fn display(&self, f: &mut Formatter) {
         match *self {
@@ -59,9 +214,7 @@ impl<'a, T: Text<'a>> Displayable for FragmentDefinition<'a, T> {
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -71,9 +224,7 @@ impl<'a, T: Text<'a>> Displayable for SelectionSet<'a, T> {
         f.margin();
         f.indent();
         f.start_block();
-        for item in &self.items {
-            item.display(f);
-        }
+        format_selections(&self.items, f);
         f.end_block();
     }
 }
@@ -88,24 +239,71 @@ impl<'a, T: Text<'a>> Displayable for Selection<'a, T> {
     }
 }
 
+fn format_selections<'a, T: Text<'a>>(items: &[Selection<'a, T>], f: &mut Formatter) {
+    let mut end = None;
+    for item in items {
+        let (start, item_end) = selection_bounds(item);
+        let fragment = !matches!(*item, Selection::Field(_));
+        if let Some(end) = end {
+            if f.blank_line_between(end, start, fragment) {
+                f.endline();
+            }
+        }
+        end = Some(item_end);
+        item.display(f);
+    }
+}
+
+/// Position recorded for the selection, which is after `...` of fragments,
+/// and the last line where it's known to end in the source
+///
+/// Only positions where nodes start are recorded, so multiline arguments
+/// aren't taken into account.
+fn selection_bounds<'a, T: Text<'a>>(selection: &Selection<'a, T>) -> (Pos, usize) {
+    let (position, directives, selection_set) = match *selection {
+        Selection::Field(ref fld) => (fld.position, &fld.directives, Some(&fld.selection_set)),
+        Selection::InlineFragment(ref frag) => {
+            (frag.position, &frag.directives, Some(&frag.selection_set))
+        }
+        Selection::FragmentSpread(ref frag) => (frag.position, &frag.directives, None),
+    };
+    let end = match selection_set {
+        Some(set) if !set.items.is_empty() => set.span.1.line,
+        _ => directives
+            .iter()
+            .map(|dir| dir.position.line)
+            .fold(position.line, usize::max),
+    };
+    (position, end)
+}
+
 fn format_arguments<'a, T: Text<'a>>(arguments: &[(T::Value, Value<'a, T>)], f: &mut Formatter) {
     if !arguments.is_empty() {
         f.start_argument_block('(');
-        f.start_argument();
-        f.write(arguments[0].0.as_ref());
-        f.write(": ");
-        arguments[0].1.display(f);
-        for arg in &arguments[1..] {
-            f.deliniate_argument();
+        for (name, value) in ordered(arguments, f.sort_arguments(), |arg| arg.0.as_ref()) {
             f.start_argument();
-            f.write(arg.0.as_ref());
+            f.write(name.as_ref());
             f.write(": ");
-            arg.1.display(f);
+            value.display(f);
         }
         f.end_argument_block(')');
     }
 }
 
+fn format_variable_definitions<'a, T: Text<'a>>(
+    variables: &[VariableDefinition<'a, T>],
+    f: &mut Formatter,
+) {
+    if !variables.is_empty() {
+        f.start_sequence('(', false);
+        for var in variables {
+            f.sequence_item();
+            var.display(f);
+        }
+        f.end_sequence(')');
+    }
+}
+
 impl<'a, T: Text<'a>> Displayable for Field<'a, T> {
     fn display(&self, f: &mut Formatter) {
         f.indent();
@@ -119,9 +317,7 @@ impl<'a, T: Text<'a>> Displayable for Field<'a, T> {
         if !self.selection_set.items.is_empty() {
             f.write(" ");
             f.start_block();
-            for item in &self.selection_set.items {
-                item.display(f);
-            }
+            format_selections(&self.selection_set.items, f);
             f.end_block();
         } else {
             f.endline();
@@ -138,21 +334,11 @@ impl<'a, T: Text<'a>> Displayable for Query<'a, T> {
             f.write(" ");
             f.write(name.as_ref());
         }
-        if !self.variable_definitions.is_empty() {
-            f.write("(");
-            self.variable_definitions[0].display(f);
-            for var in &self.variable_definitions[1..] {
-                f.write(", ");
-                var.display(f);
-            }
-            f.write(")");
-        }
+        format_variable_definitions(&self.variable_definitions, f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -166,21 +352,11 @@ impl<'a, T: Text<'a>> Displayable for Mutation<'a, T> {
             f.write(" ");
             f.write(name.as_ref());
         }
-        if !self.variable_definitions.is_empty() {
-            f.write("(");
-            self.variable_definitions[0].display(f);
-            for var in &self.variable_definitions[1..] {
-                f.write(", ");
-                var.display(f);
-            }
-            f.write(")");
-        }
+        format_variable_definitions(&self.variable_definitions, f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -193,20 +369,12 @@ impl<'a, T: Text<'a>> Displayable for Subscription<'a, T> {
         if let Some(ref name) = self.name {
             f.write(" ");
             f.write(name.as_ref());
-            if !self.variable_definitions.is_empty() {
-                f.write("(");
-                for var in &self.variable_definitions {
-                    var.display(f);
-                }
-                f.write(")");
-            }
         }
+        format_variable_definitions(&self.variable_definitions, f);
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -249,8 +417,10 @@ impl<'a, T: Text<'a>> Displayable for Value<'a, T> {
                 f.write(name.as_ref());
             }
             Value::Int(ref num) => f.write(&format!("{}", num.0)),
-            Value::Float(val) => f.write(&format!("{}", val)),
-            Value::String(ref val) => f.write_quoted(val),
+            // Debug keeps the fraction or uses an exponent, so the value
+            // is read back as a float, unlike `1` or `18446744073709552000`
+            Value::Float(val) => f.write(&format!("{:?}", val)),
+            Value::String(ref val) => f.write_string(val),
             Value::Boolean(true) => f.write("true"),
             Value::Boolean(false) => f.write("false"),
             Value::Null => f.write("null"),
@@ -261,7 +431,6 @@ impl<'a, T: Text<'a>> Displayable for Value<'a, T> {
                     f.start_argument();
                     items[0].display(f);
                     for item in &items[1..] {
-                        f.deliniate_argument();
                         f.start_argument();
                         item.display(f);
                     }
@@ -270,13 +439,7 @@ impl<'a, T: Text<'a>> Displayable for Value<'a, T> {
             }
             Value::Object(ref items) => {
                 f.start_argument_block('{');
-                let mut first = true;
                 for (name, value) in items.iter() {
-                    if first {
-                        first = false;
-                    } else {
-                        f.deliniate_argument();
-                    }
                     f.start_argument();
                     f.write(name.as_ref());
                     f.write(": ");
@@ -299,9 +462,7 @@ impl<'a, T: Text<'a>> Displayable for InlineFragment<'a, T> {
         format_directives(&self.directives, f);
         f.write(" ");
         f.start_block();
-        for item in &self.selection_set.items {
-            item.display(f);
-        }
+        format_selections(&self.selection_set.items, f);
         f.end_block();
     }
 }
@@ -1,252 +1,422 @@
-use combine::{eof, many1, optional, position, StdParseResult};
-use combine::{parser, Parser};
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
 
-use crate::common::Directive;
-use crate::common::{arguments, default_value, directives, parse_type};
-use crate::helpers::{ident, name, punct};
+use combine::Positioned;
+
+use crate::common::{self, arguments_in, directives_in, parse_type_in, value_in};
+use crate::common::{Alloc, Directive, Heap, Name, Seq};
+use crate::helpers::ParseResult;
+use crate::position::Pos;
 use crate::query::ast::*;
 use crate::query::error::ParseError;
 use crate::tokenizer::TokenStream;
 
-pub fn field<'a, S>(input: &mut TokenStream<'a>) -> StdParseResult<Field<'a, S>, TokenStream<'a>>
+/// Allocates nodes of a query document, see `common::Alloc`
+pub trait QueryAlloc<'a>: Alloc<'a> {
+    type VariableDefinition;
+    type VariableDefinitions: Seq<Self::VariableDefinition>;
+    type Selection;
+    type Selections: Seq<Self::Selection>;
+    type SelectionSet;
+    type Definition;
+    type Definitions: Seq<Self::Definition>;
+    type Document;
+
+    fn variable_definitions(self) -> Self::VariableDefinitions;
+    fn selections(self) -> Self::Selections;
+    fn definitions(self) -> Self::Definitions;
+
+    fn variable_definition(
+        self,
+        position: Pos,
+        name: Name<'a, Self>,
+        var_type: Self::Type,
+        default_value: Option<Self::Value>,
+    ) -> Self::VariableDefinition;
+    fn field(
+        self,
+        position: Pos,
+        alias: Option<Name<'a, Self>>,
+        name: Name<'a, Self>,
+        arguments: Self::Arguments,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Selection;
+    fn fragment_spread(
+        self,
+        position: Pos,
+        fragment_name: Name<'a, Self>,
+        directives: Self::Directives,
+    ) -> Self::Selection;
+    fn inline_fragment(
+        self,
+        position: Pos,
+        type_condition: Option<Name<'a, Self>>,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Selection;
+    fn selection_set(self, span: (Pos, Pos), items: Self::Selections) -> Self::SelectionSet;
+    /// Builds a query, a mutation or a subscription depending on `keyword`
+    #[allow(clippy::too_many_arguments)]
+    fn operation(
+        self,
+        keyword: &'a str,
+        position: Pos,
+        name: Option<Name<'a, Self>>,
+        variable_definitions: Self::VariableDefinitions,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Definition;
+    /// Builds an operation written as a bare selection set
+    fn shorthand(self, selection_set: Self::SelectionSet) -> Self::Definition;
+    fn fragment_definition(
+        self,
+        position: Pos,
+        name: Name<'a, Self>,
+        type_condition: Name<'a, Self>,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Definition;
+    fn document(self, definitions: Self::Definitions) -> Self::Document;
+}
+
+impl<'a, T: Text<'a>> QueryAlloc<'a> for Heap<T> {
+    type VariableDefinition = VariableDefinition<'a, T>;
+    type VariableDefinitions = Vec<VariableDefinition<'a, T>>;
+    type Selection = Selection<'a, T>;
+    type Selections = Vec<Selection<'a, T>>;
+    type SelectionSet = SelectionSet<'a, T>;
+    type Definition = Definition<'a, T>;
+    type Definitions = Vec<Definition<'a, T>>;
+    type Document = Document<'a, T>;
+
+    fn variable_definitions(self) -> Self::VariableDefinitions {
+        Vec::new()
+    }
+    fn selections(self) -> Self::Selections {
+        Vec::new()
+    }
+    fn definitions(self) -> Self::Definitions {
+        Vec::new()
+    }
+
+    fn variable_definition(
+        self,
+        position: Pos,
+        name: T::Value,
+        var_type: Type<'a, T>,
+        default_value: Option<Value<'a, T>>,
+    ) -> Self::VariableDefinition {
+        VariableDefinition {
+            position,
+            name,
+            var_type,
+            default_value,
+        }
+    }
+    fn field(
+        self,
+        position: Pos,
+        alias: Option<T::Value>,
+        name: T::Value,
+        arguments: Self::Arguments,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Selection {
+        Selection::Field(Field {
+            position,
+            alias,
+            name,
+            arguments,
+            directives,
+            selection_set,
+        })
+    }
+    fn fragment_spread(
+        self,
+        position: Pos,
+        fragment_name: T::Value,
+        directives: Self::Directives,
+    ) -> Self::Selection {
+        Selection::FragmentSpread(FragmentSpread {
+            position,
+            fragment_name,
+            directives,
+        })
+    }
+    fn inline_fragment(
+        self,
+        position: Pos,
+        type_condition: Option<T::Value>,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Selection {
+        Selection::InlineFragment(InlineFragment {
+            position,
+            type_condition: type_condition.map(TypeCondition::On),
+            directives,
+            selection_set,
+        })
+    }
+    fn selection_set(self, span: (Pos, Pos), items: Self::Selections) -> Self::SelectionSet {
+        SelectionSet { span, items }
+    }
+    fn operation(
+        self,
+        keyword: &'a str,
+        position: Pos,
+        name: Option<T::Value>,
+        variable_definitions: Self::VariableDefinitions,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Definition {
+        Definition::Operation(match keyword {
+            "query" => OperationDefinition::Query(Query {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            "mutation" => OperationDefinition::Mutation(Mutation {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+            _ => OperationDefinition::Subscription(Subscription {
+                position,
+                name,
+                variable_definitions,
+                directives,
+                selection_set,
+            }),
+        })
+    }
+    fn shorthand(self, selection_set: Self::SelectionSet) -> Self::Definition {
+        Definition::Operation(OperationDefinition::SelectionSet(selection_set))
+    }
+    fn fragment_definition(
+        self,
+        position: Pos,
+        name: T::Value,
+        type_condition: T::Value,
+        directives: Self::Directives,
+        selection_set: Self::SelectionSet,
+    ) -> Self::Definition {
+        Definition::Fragment(FragmentDefinition {
+            position,
+            name,
+            type_condition: TypeCondition::On(type_condition),
+            directives,
+            selection_set,
+        })
+    }
+    fn document(self, definitions: Self::Definitions) -> Self::Document {
+        Document { definitions }
+    }
+}
+
+fn field<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Selection>
 where
-    S: Text<'a>,
+    A: QueryAlloc<'a>,
 {
-    (
-        position(),
-        name::<'a, S>(),
-        optional(punct(":").with(name::<'a, S>())),
-        parser(arguments),
-        parser(directives),
-        optional(parser(selection_set)),
-    )
-        .map(
-            |(position, name_or_alias, opt_name, arguments, directives, sel)| {
-                let (name, alias) = match opt_name {
-                    Some(name) => (name, Some(name_or_alias)),
-                    None => (name_or_alias, None),
-                };
-                Field {
-                    position,
-                    name,
-                    alias,
-                    arguments,
-                    directives,
-                    selection_set: sel.unwrap_or_else(|| SelectionSet {
-                        span: (position, position),
-                        items: Vec::new(),
-                    }),
-                }
-            },
-        )
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    let name_or_alias = input.name::<A::Text>()?;
+    let (name, alias) = if input.eat_punct(":") {
+        (input.name::<A::Text>()?, Some(name_or_alias))
+    } else {
+        (name_or_alias, None)
+    };
+    let arguments = arguments_in(input, alloc)?;
+    let directives = directives_in(input, alloc)?;
+    let selection_set = if input.is_punct("{") {
+        selection_set_in(input, alloc)?
+    } else {
+        alloc.selection_set((position, position), alloc.selections())
+    };
+    Ok(alloc.field(position, alias, name, arguments, directives, selection_set))
 }
 
-pub fn selection<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Selection<'a, S>, TokenStream<'a>>
+fn selection<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Selection>
 where
-    S: Text<'a>,
+    A: QueryAlloc<'a>,
 {
-    parser(field)
-        .map(Selection::Field)
-        .or(punct("...").with(
-            (
-                position(),
-                optional(ident("on").with(name::<'a, S>()).map(TypeCondition::On)),
-                parser(directives),
-                parser(selection_set),
-            )
-                .map(
-                    |(position, type_condition, directives, selection_set)| InlineFragment {
-                        position,
-                        type_condition,
-                        selection_set,
-                        directives,
-                    },
-                )
-                .map(Selection::InlineFragment)
-                .or((position(), name::<'a, S>(), parser(directives))
-                    .map(|(position, fragment_name, directives)| FragmentSpread {
-                        position,
-                        fragment_name,
-                        directives,
-                    })
-                    .map(Selection::FragmentSpread)),
-        ))
-        .parse_stream(input)
-        .into_result()
+    if !input.eat_punct("...") {
+        if !input.is_name() {
+            return Err(input.unexpected_choice(&["Name"]));
+        }
+        return field(input, alloc);
+    }
+    let position = input.position();
+    if input.is_ident("on") || input.is_punct("@") || input.is_punct("{") {
+        let type_condition = if input.eat_ident("on") {
+            Some(input.name::<A::Text>()?)
+        } else {
+            None
+        };
+        let directives = directives_in(input, alloc)?;
+        let selection_set = selection_set_in(input, alloc)?;
+        Ok(alloc.inline_fragment(position, type_condition, directives, selection_set))
+    } else {
+        if !input.is_name() {
+            return Err(input.unexpected_choice(&["{", "on", "Name"]));
+        }
+        let fragment_name = input.name::<A::Text>()?;
+        let directives = directives_in(input, alloc)?;
+        Ok(alloc.fragment_spread(position, fragment_name, directives))
+    }
 }
 
-pub fn selection_set<'a, S>(
+fn selection_set_in<'a, A>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<SelectionSet<'a, S>, TokenStream<'a>>
+    alloc: A,
+) -> ParseResult<'a, A::SelectionSet>
 where
-    S: Text<'a>,
+    A: QueryAlloc<'a>,
 {
-    (
-        position().skip(punct("{")),
-        many1(parser(selection)),
-        position().skip(punct("}")),
-    )
-        .map(|(start, items, end)| SelectionSet {
-            span: (start, end),
-            items,
-        })
-        .parse_stream(input)
-        .into_result()
+    let start = input.position();
+    if !input.is_punct("{") {
+        return Err(input.unexpected_choice(&["{"]));
+    }
+    input.bump();
+    let mut items = alloc.selections();
+    items.push(selection(input, alloc)?);
+    while input.is_name() || input.is_punct("...") {
+        items.push(selection(input, alloc)?);
+    }
+    let end = input.position();
+    input.punct("}")?;
+    Ok(alloc.selection_set((start, end), items))
 }
 
-pub fn query<'a, T: Text<'a>>(
+fn variable_definitions<'a, A>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<Query<'a, T>, TokenStream<'a>> {
-    position()
-        .skip(ident("query"))
-        .and(parser(operation_common))
-        .map(
-            |(position, (name, variable_definitions, directives, selection_set))| Query {
-                position,
-                name,
-                selection_set,
-                variable_definitions,
-                directives,
-            },
-        )
-        .parse_stream(input)
-        .into_result()
+    alloc: A,
+) -> ParseResult<'a, A::VariableDefinitions>
+where
+    A: QueryAlloc<'a>,
+{
+    let mut variables = alloc.variable_definitions();
+    if input.eat_punct("(") {
+        loop {
+            let position = input.position();
+            input.punct("$")?;
+            let name = input.name::<A::Text>()?;
+            input.punct(":")?;
+            let var_type = parse_type_in(input, alloc)?;
+            let default_value = if input.eat_punct("=") {
+                Some(value_in(input, alloc, true)?)
+            } else {
+                None
+            };
+            variables.push(alloc.variable_definition(position, name, var_type, default_value));
+            if !input.is_punct("$") {
+                break;
+            }
+        }
+        input.punct_or(")", &["$", "Name", ":", "=", ")"])?;
+    }
+    Ok(variables)
 }
 
-/// A set of attributes common to a Query and a Mutation
-#[allow(type_alias_bounds)]
-type OperationCommon<'a, T: Text<'a>> = (
-    Option<T::Value>,
-    Vec<VariableDefinition<'a, T>>,
-    Vec<Directive<'a, T>>,
-    SelectionSet<'a, T>,
-);
-
-pub fn operation_common<'a, T: Text<'a>>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<OperationCommon<'a, T>, TokenStream<'a>> {
-    optional(name::<'a, T>())
-        .and(
-            optional(
-                punct("(")
-                    .with(many1(
-                        (
-                            position(),
-                            punct("$").with(name::<'a, T>()).skip(punct(":")),
-                            parser(parse_type),
-                            optional(punct("=").with(parser(default_value))),
-                        )
-                            .map(
-                                |(position, name, var_type, default_value)| VariableDefinition {
-                                    position,
-                                    name,
-                                    var_type,
-                                    default_value,
-                                },
-                            ),
-                    ))
-                    .skip(punct(")")),
-            )
-            .map(|vars| vars.unwrap_or_default()),
-        )
-        .and(parser(directives))
-        .and(parser(selection_set))
-        .map(|(((a, b), c), d)| (a, b, c, d))
-        .parse_stream(input)
-        .into_result()
+/// Parses a query, a mutation or a subscription
+fn operation<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Definition>
+where
+    A: QueryAlloc<'a>,
+{
+    let position = input.position();
+    let keyword = input.bump().value;
+    let name = if input.is_name() {
+        Some(input.name::<A::Text>()?)
+    } else {
+        None
+    };
+    let variable_definitions = variable_definitions(input, alloc)?;
+    let directives = directives_in(input, alloc)?;
+    if name.is_none()
+        && variable_definitions.is_empty()
+        && directives.is_empty()
+        && !input.is_punct("{")
+    {
+        return Err(input.unexpected_choice(&["{", "Name", "(", "$", ":", "=", ")"]));
+    }
+    let selection_set = selection_set_in(input, alloc)?;
+    Ok(alloc.operation(
+        keyword,
+        position,
+        name,
+        variable_definitions,
+        directives,
+        selection_set,
+    ))
 }
 
-pub fn mutation<'a, T: Text<'a>>(
+fn fragment_definition<'a, A>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<Mutation<'a, T>, TokenStream<'a>> {
-    position()
-        .skip(ident("mutation"))
-        .and(parser(operation_common))
-        .map(
-            |(position, (name, variable_definitions, directives, selection_set))| Mutation {
-                position,
-                name,
-                selection_set,
-                variable_definitions,
-                directives,
-            },
-        )
-        .parse_stream(input)
-        .into_result()
+    alloc: A,
+) -> ParseResult<'a, A::Definition>
+where
+    A: QueryAlloc<'a>,
+{
+    let position = input.position();
+    input.ident("fragment")?;
+    let name = input.name::<A::Text>()?;
+    input.ident("on")?;
+    let type_condition = input.name::<A::Text>()?;
+    let directives = directives_in(input, alloc)?;
+    let selection_set = selection_set_in(input, alloc)?;
+    Ok(alloc.fragment_definition(position, name, type_condition, directives, selection_set))
 }
 
-pub fn subscription<'a, T: Text<'a>>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Subscription<'a, T>, TokenStream<'a>> {
-    position()
-        .skip(ident("subscription"))
-        .and(parser(operation_common))
-        .map(
-            |(position, (name, variable_definitions, directives, selection_set))| Subscription {
-                position,
-                name,
-                selection_set,
-                variable_definitions,
-                directives,
-            },
-        )
-        .parse_stream(input)
-        .into_result()
+/// Returns `true` if the next token starts an executable definition
+pub fn is_definition_start(input: &mut TokenStream) -> bool {
+    input.is_punct("{")
+        || input.is_ident("query")
+        || input.is_ident("mutation")
+        || input.is_ident("subscription")
+        || input.is_ident("fragment")
 }
 
-pub fn operation_definition<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<OperationDefinition<'a, S>, TokenStream<'a>>
+pub fn definition<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<'a, Definition<'a, S>>
 where
     S: Text<'a>,
 {
-    parser(selection_set)
-        .map(OperationDefinition::SelectionSet)
-        .or(parser(query).map(OperationDefinition::Query))
-        .or(parser(mutation).map(OperationDefinition::Mutation))
-        .or(parser(subscription).map(OperationDefinition::Subscription))
-        .parse_stream(input)
-        .into_result()
+    definition_in(input, Heap::new())
 }
 
-pub fn fragment_definition<'a, T: Text<'a>>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<FragmentDefinition<'a, T>, TokenStream<'a>> {
-    (
-        position().skip(ident("fragment")),
-        name::<'a, T>(),
-        ident("on").with(name::<'a, T>()).map(TypeCondition::On),
-        parser(directives),
-        parser(selection_set),
-    )
-        .map(
-            |(position, name, type_condition, directives, selection_set)| FragmentDefinition {
-                position,
-                name,
-                type_condition,
-                directives,
-                selection_set,
-            },
-        )
-        .parse_stream(input)
-        .into_result()
+fn definition_in<'a, A>(input: &mut TokenStream<'a>, alloc: A) -> ParseResult<'a, A::Definition>
+where
+    A: QueryAlloc<'a>,
+{
+    if input.is_punct("{") {
+        selection_set_in(input, alloc).map(|s| alloc.shorthand(s))
+    } else if input.is_ident("query")
+        || input.is_ident("mutation")
+        || input.is_ident("subscription")
+    {
+        operation(input, alloc)
+    } else if input.is_ident("fragment") {
+        fragment_definition(input, alloc)
+    } else {
+        Err(input.unexpected_choice(&["{", "query", "mutation", "subscription", "fragment"]))
+    }
 }
 
-pub fn definition<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Definition<'a, S>, TokenStream<'a>>
+/// Parses a whole document into the tree built by `alloc`
+pub fn document_in<'a, A>(s: &'a str, alloc: A) -> Result<A::Document, ParseError>
 where
-    S: Text<'a>,
+    A: QueryAlloc<'a>,
 {
-    parser(operation_definition)
-        .map(Definition::Operation)
-        .or(parser(fragment_definition).map(Definition::Fragment))
-        .parse_stream(input)
-        .into_result()
+    let mut tokens = TokenStream::new(s);
+    let mut definitions = alloc.definitions();
+    definitions.push(definition_in(&mut tokens, alloc)?);
+    while is_definition_start(&mut tokens) {
+        definitions.push(definition_in(&mut tokens, alloc)?);
+    }
+    tokens.eof()?;
+    Ok(alloc.document(definitions))
 }
 
 /// Parses a piece of query language and returns an AST
@@ -254,15 +424,7 @@ pub fn parse_query<'a, S>(s: &'a str) -> Result<Document<'a, S>, ParseError>
 where
     S: Text<'a>,
 {
-    let mut tokens = TokenStream::new(s);
-    let (doc, _) = many1(parser(definition))
-        .map(|d| Document { definitions: d })
-        .skip(eof())
-        .parse_stream(&mut tokens)
-        .into_result()
-        .map_err(|e| e.into_inner().error)?;
-
-    Ok(doc)
+    document_in(s, Heap::new())
 }
 
 /// Parses a single ExecutableDefinition and returns an AST as well as the
@@ -271,19 +433,111 @@ pub fn consume_definition<'a, S>(s: &'a str) -> Result<(Definition<'a, S>, &'a s
 where
     S: Text<'a>,
 {
-    let tokens = TokenStream::new(s);
-    let (doc, tokens) = parser(definition).parse(tokens)?;
+    let mut tokens = TokenStream::new(s);
+    let doc = definition(&mut tokens)?;
 
     Ok((doc, &s[tokens.offset()..]))
 }
 
+/// Returns an iterator parsing definitions one at a time
+///
+/// Unlike `parse_query` it doesn't keep the whole document in memory.
+/// An error ends the iteration, definitions before it are returned as is.
+pub fn parse_definitions<'a, S>(s: &'a str) -> Definitions<'a, S>
+where
+    S: Text<'a>,
+{
+    Definitions {
+        tokens: TokenStream::new(s),
+        first: true,
+        done: false,
+        marker: PhantomData,
+    }
+}
+
+/// Iterator over executable definitions, created by `parse_definitions`
+#[derive(Debug)]
+pub struct Definitions<'a, S: Text<'a>> {
+    tokens: TokenStream<'a>,
+    first: bool,
+    done: bool,
+    marker: PhantomData<S>,
+}
+
+impl<'a, S: Text<'a>> Iterator for Definitions<'a, S> {
+    type Item = Result<Definition<'a, S>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // Document must have at least one definition
+        if !self.first && !is_definition_start(&mut self.tokens) {
+            self.done = true;
+            return self.tokens.eof().err().map(|e| Err(e.into()));
+        }
+        self.first = false;
+        let result = definition(&mut self.tokens);
+        self.done = result.is_err();
+        Some(result.map_err(Into::into))
+    }
+}
+
+impl<'a, S: Text<'a>> FusedIterator for Definitions<'a, S> {}
+
+/// Parses a single value, like `{ id: 1, tags: ["a", "b"] }`
+///
+/// Variables are accepted, like in arguments of a query.
+pub fn parse_value<'a, S>(s: &'a str) -> Result<Value<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let value = common::value(&mut tokens)?;
+    tokens.eof()?;
+    Ok(value)
+}
+
+/// Parses a type reference, like `[Int!]!`
+pub fn parse_type<'a, S>(s: &'a str) -> Result<Type<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let value_type = common::parse_type(&mut tokens)?;
+    tokens.eof()?;
+    Ok(value_type)
+}
+
+/// Parses a selection set, like `{ id name }`
+pub fn parse_selection_set<'a, S>(s: &'a str) -> Result<SelectionSet<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let set = selection_set_in(&mut tokens, Heap::new())?;
+    tokens.eof()?;
+    Ok(set)
+}
+
+/// Parses a single directive, like `@key(fields: "id")`
+pub fn parse_directive<'a, S>(s: &'a str) -> Result<Directive<'a, S>, ParseError>
+where
+    S: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let directive = common::directive(&mut tokens)?;
+    tokens.eof()?;
+    Ok(directive)
+}
+
 #[cfg(test)]
 mod test {
     use super::{consume_definition, parse_query};
     use crate::position::Pos;
     use crate::query::grammar::*;
 
-    fn ast(s: &str) -> Document<String> {
+    fn ast(s: &str) -> Document<'_, String> {
         parse_query::<String>(s).unwrap().to_owned()
     }
 
@@ -347,6 +601,62 @@ mod test {
         );
     }
 
+    #[test]
+    fn borrowed_strings() {
+        let source = r#"{ a(x: "plain", y: "esc\naped", z: """block""") }"#;
+        let doc = parse_query::<&str>(source).unwrap();
+        let args = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref s)) => match s.items[0] {
+                Selection::Field(ref f) => &f.arguments,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        let strings: Vec<_> = args
+            .iter()
+            .map(|(_, v)| match v {
+                Value::String(s) => s,
+                _ => unreachable!(),
+            })
+            .collect();
+        assert_eq!(*strings[0], "plain");
+        assert!(strings[0].is_borrowed());
+        assert!(source
+            .as_bytes()
+            .as_ptr_range()
+            .contains(&strings[0].as_ptr()));
+        assert_eq!(strings[0].raw(), Some(r#""plain""#));
+        assert_eq!(*strings[1], "esc\naped");
+        assert!(!strings[1].is_borrowed());
+        assert_eq!(strings[1].raw(), Some(r#""esc\naped""#));
+        assert_eq!(*strings[2], "block");
+        assert!(strings[2].is_block());
+        assert_eq!(strings[2].raw(), Some(r#""""block""""#));
+        assert!(strings[2].is_borrowed());
+    }
+
+    #[test]
+    fn owned_strings() {
+        let doc = ast(r#"{ a(x: "plain", y: "esc\naped") }"#);
+        let args = match doc.definitions[0] {
+            Definition::Operation(OperationDefinition::SelectionSet(ref s)) => match s.items[0] {
+                Selection::Field(ref f) => &f.arguments,
+                _ => unreachable!(),
+            },
+            _ => unreachable!(),
+        };
+        for (_, value) in args {
+            match value {
+                Value::String(s) => {
+                    assert_eq!(s.raw(), None);
+                    assert!(!s.is_borrowed());
+                }
+                _ => unreachable!(),
+            }
+        }
+        assert_eq!(args[1].1, Value::String("esc\naped".into()));
+    }
+
     #[test]
     fn one_field_roundtrip() {
         assert_eq!(ast("{ a }").to_string(), "{\n  a\n}\n");
@@ -404,4 +714,58 @@ mod test {
             "query parse error: Parse error at 1:114\nExpected ]\nRecursion limit exceeded\n"
         )
     }
+
+    #[test]
+    fn standalone() {
+        use super::{parse_directive, parse_selection_set, parse_type, parse_value};
+
+        let value = parse_value::<String>(r#"{ id: 1, tags: ["a", $b] }"#).unwrap();
+        assert_eq!(value.to_string(), r#"{id: 1, tags: ["a", $b]}"#);
+        let value_type = parse_type::<String>(" [Int!]! ").unwrap();
+        assert_eq!(value_type.to_string(), "[Int!]!");
+        let set = parse_selection_set::<String>("{ id name }").unwrap();
+        assert_eq!(set.items.len(), 2);
+        let directive = parse_directive::<String>(r#"@key(fields: "id")"#).unwrap();
+        assert_eq!(directive.name, "key");
+        assert_eq!(directive.arguments[0].0, "fields");
+
+        let err = parse_type::<String>("[Int!]! x").unwrap_err();
+        assert_eq!(err.position(), Pos { line: 1, column: 9 });
+        assert!(parse_value::<String>("").is_err());
+        assert!(parse_directive::<String>("@a @b").is_err());
+    }
+
+    #[test]
+    fn definitions_iterator() {
+        use super::parse_definitions;
+
+        let mut defs = parse_definitions::<String>("query A { a } fragment F on T { b }");
+        assert!(matches!(defs.next(), Some(Ok(Definition::Operation(_)))));
+        assert!(matches!(defs.next(), Some(Ok(Definition::Fragment(_)))));
+        assert!(defs.next().is_none());
+        assert!(defs.next().is_none());
+
+        let results: Vec<_> = parse_definitions::<String>("{ a } { b ] { c }").collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        let err = results[1].as_ref().unwrap_err();
+        assert_eq!(
+            err.position(),
+            Pos {
+                line: 1,
+                column: 11
+            }
+        );
+
+        let results: Vec<_> = parse_definitions::<String>("{ a } b").collect();
+        assert_eq!(results.len(), 2);
+        assert_eq!(
+            results[1].as_ref().unwrap_err().position(),
+            Pos { line: 1, column: 7 }
+        );
+
+        let results: Vec<_> = parse_definitions::<String>("").collect();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].is_err());
+    }
 }
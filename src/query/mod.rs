@@ -1,12 +1,19 @@
 //! Query language AST and parsing utilities
 //!
+#[cfg(feature = "arena")]
+pub mod arena;
 mod ast;
+pub mod builder;
 mod error;
 mod format;
-mod grammar;
+pub(crate) mod grammar;
 mod minify;
+mod owned;
 
 pub use self::ast::*;
 pub use self::error::ParseError;
-pub use self::grammar::{consume_definition, parse_query};
+pub use self::format::{format_query_range, skipped_query_parts};
+pub use self::grammar::{consume_definition, parse_definitions, parse_query, Definitions};
+pub use self::grammar::{parse_directive, parse_selection_set, parse_type, parse_value};
 pub use self::minify::minify_query;
+pub use self::owned::OwnedDocument;
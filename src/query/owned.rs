@@ -0,0 +1,126 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::query::ast::Document;
+use crate::query::error::ParseError;
+use crate::query::grammar::parse_query;
+
+/// Parsed query bundled together with its source text
+///
+/// All names in the document borrow from the source buffer, so parsing
+/// doesn't allocate per identifier (like `Document<&str>`). But the buffer
+/// is owned by the structure itself, so it can be stored in long-lived
+/// caches and sent across threads. Cloning only bumps a reference count of
+/// the source and copies the tree.
+#[derive(Clone, PartialEq)]
+pub struct OwnedDocument {
+    // Borrows from `source`, so it must be declared (and dropped) first.
+    // The `'static` lifetime is a lie: it is never exposed outside, see
+    // `document()` which narrows it down to the lifetime of `self`.
+    document: Document<'static, &'static str>,
+    source: Arc<str>,
+}
+
+impl OwnedDocument {
+    /// Parses query and keeps the source buffer along with the AST
+    pub fn parse<S: Into<Arc<str>>>(source: S) -> Result<OwnedDocument, ParseError> {
+        let source = source.into();
+        // This is safe because string data behind `Arc` is never mutated
+        // and never moves, and it's kept alive by the `source` field for
+        // as long as the document exists.
+        let text = unsafe { &*(&*source as *const str) };
+        let document = parse_query::<&'static str>(text)?;
+        Ok(OwnedDocument { document, source })
+    }
+
+    /// Returns a zero-copy view of the document
+    pub fn document<'a>(&'a self) -> &'a Document<'a, &'a str> {
+        // Only narrows lifetimes down to the lifetime of the buffer owner
+        let ptr: *const Document<'static, &'static str> = &self.document;
+        unsafe { &*ptr.cast::<Document<'a, &'a str>>() }
+    }
+
+    /// Returns source text of the document
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns shared buffer with the source text of the document
+    pub fn shared_source(&self) -> &Arc<str> {
+        &self.source
+    }
+}
+
+impl fmt::Debug for OwnedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnedDocument")
+            .field("document", self.document())
+            .finish()
+    }
+}
+
+impl fmt::Display for OwnedDocument {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.document(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::sync::Arc;
+    use std::thread;
+
+    use super::OwnedDocument;
+    use crate::query::{parse_query, Definition, OperationDefinition};
+
+    fn is_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn send_sync() {
+        is_send_sync::<OwnedDocument>();
+    }
+
+    #[test]
+    fn borrows_from_source() {
+        let doc = OwnedDocument::parse("query Hello { field }").unwrap();
+        let name = match doc.document().definitions[0] {
+            Definition::Operation(OperationDefinition::Query(ref q)) => q.name.unwrap(),
+            _ => unreachable!(),
+        };
+        assert_eq!(name, "Hello");
+        let range = doc.source().as_bytes().as_ptr_range();
+        assert!(range.contains(&name.as_ptr()));
+    }
+
+    #[test]
+    fn same_as_borrowed() {
+        let source = "query Hello($x: Int = 1) { a(x: $x) { b } ...frag }";
+        let doc = OwnedDocument::parse(source).unwrap();
+        assert_eq!(doc.document(), &parse_query::<&str>(source).unwrap());
+        assert_eq!(
+            doc.to_string(),
+            parse_query::<&str>(source).unwrap().to_string()
+        );
+    }
+
+    #[test]
+    fn clone_shares_source() {
+        let doc = OwnedDocument::parse(String::from("{ a }")).unwrap();
+        let copy = doc.clone();
+        assert!(Arc::ptr_eq(doc.shared_source(), copy.shared_source()));
+        drop(doc);
+        assert_eq!(copy.to_string(), "{\n  a\n}\n");
+    }
+
+    #[test]
+    fn move_across_threads() {
+        let doc = OwnedDocument::parse("{ a b }").unwrap();
+        let printed = thread::spawn(move || doc.to_string()).join().unwrap();
+        assert_eq!(printed, "{\n  a\n  b\n}\n");
+    }
+
+    #[test]
+    fn error() {
+        assert!(OwnedDocument::parse("{ a").is_err());
+    }
+}
@@ -2,7 +2,7 @@ use std::str::FromStr;
 
 use thiserror::Error;
 
-pub use crate::common::{Directive, Text, Type, Value};
+pub use crate::common::{Directive, StringValue, Text, Type, Value};
 use crate::position::Pos;
 
 #[derive(Debug, Clone, Default, PartialEq)]
@@ -12,19 +12,13 @@ pub struct Document<'a, T: Text<'a>> {
 
 impl<'a> Document<'a, String> {
     pub fn into_static(self) -> Document<'static, String> {
-        // To support both reference and owned values in the AST,
-        // all string data is represented with the ::common::Str<'a, T: Text<'a>>
-        // wrapper type.
-        // This type must carry the liftetime of the schema string,
-        // and is stored in a PhantomData value on the Str type.
-        // When using owned String types, the actual lifetime of
-        // the Ast nodes is 'static, since no references are kept,
-        // but the nodes will still carry the input lifetime.
-        // To continue working with Document<String> in a owned fasion
-        // the lifetime needs to be transmuted to 'static.
-        //
-        // This is safe because no references are present.
-        // Just the PhantomData lifetime reference is transmuted away.
+        // The AST is generic over the string type and carries the lifetime
+        // of the source. The only reference the lifetime can be used for
+        // besides `T::Value` is a string value allocated elsewhere
+        // (`Unescaped::Borrowed`), which is only created for
+        // `StringValue<'a, &'a str>` by `move_value`. So with `String`
+        // texts nothing is borrowed and the lifetime can be transmuted to
+        // 'static.
         unsafe { std::mem::transmute::<_, Document<'static, String>>(self) }
     }
 }
@@ -69,7 +63,7 @@ pub enum TypeExtension<'a, T: Text<'a>> {
 #[derive(Debug, Clone, PartialEq)]
 pub struct ScalarType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
 }
@@ -111,7 +105,7 @@ where
 #[derive(Debug, Clone, PartialEq)]
 pub struct ObjectType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
     pub directives: Vec<Directive<'a, T>>,
@@ -161,27 +155,59 @@ where
 #[derive(Debug, Clone, PartialEq)]
 pub struct Field<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub arguments: Vec<InputValue<'a, T>>,
     pub field_type: Type<'a, T>,
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> Field<'a, T>
+where
+    T: Text<'a>,
+{
+    pub fn new(name: T::Value, field_type: Type<'a, T>) -> Self {
+        Self {
+            position: Pos::default(),
+            description: None,
+            name,
+            arguments: vec![],
+            field_type,
+            directives: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct InputValue<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub value_type: Type<'a, T>,
     pub default_value: Option<Value<'a, T>>,
     pub directives: Vec<Directive<'a, T>>,
 }
 
+impl<'a, T> InputValue<'a, T>
+where
+    T: Text<'a>,
+{
+    pub fn new(name: T::Value, value_type: Type<'a, T>) -> Self {
+        Self {
+            position: Pos::default(),
+            description: None,
+            name,
+            value_type,
+            default_value: None,
+            directives: vec![],
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq)]
 pub struct InterfaceType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub implements_interfaces: Vec<T::Value>,
     pub directives: Vec<Directive<'a, T>>,
@@ -231,7 +257,7 @@ where
 #[derive(Debug, Clone, PartialEq)]
 pub struct UnionType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub types: Vec<T::Value>,
@@ -277,7 +303,7 @@ where
 #[derive(Debug, Clone, PartialEq)]
 pub struct EnumType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub values: Vec<EnumValue<'a, T>>,
@@ -301,7 +327,7 @@ where
 #[derive(Debug, Clone, PartialEq)]
 pub struct EnumValue<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
 }
@@ -345,7 +371,7 @@ where
 #[derive(Debug, Clone, PartialEq)]
 pub struct InputObjectType<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub directives: Vec<Directive<'a, T>>,
     pub fields: Vec<InputValue<'a, T>>,
@@ -417,7 +443,7 @@ pub enum DirectiveLocation {
 #[derive(Debug, Clone, PartialEq)]
 pub struct DirectiveDefinition<'a, T: Text<'a>> {
     pub position: Pos,
-    pub description: Option<String>,
+    pub description: Option<StringValue<'a, T>>,
     pub name: T::Value,
     pub arguments: Vec<InputValue<'a, T>>,
     pub repeatable: bool,
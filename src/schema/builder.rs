@@ -0,0 +1,428 @@
+//! Builders for constructing schema ASTs in code
+//!
+//! Every node is created with a default position, so the result is meant to
+//! be formatted rather than mapped back to a source:
+//!
+//! ```rust
+//! use graphql_parser::schema::builder::*;
+//! use graphql_parser::schema::Document;
+//!
+//! let doc: Document<String> = document()
+//!     .definition(
+//!         object("User")
+//!             .implements("Node")
+//!             .field("id", non_null(named("ID")))
+//!             .add_field(field("friends", list(named("User"))).argument("first", named("Int"))),
+//!     );
+//! assert_eq!(doc.to_string(), "\
+//! type User implements Node {
+//!   id: ID!
+//!   friends(first: Int): [User]
+//! }
+//! ");
+//! ```
+pub use crate::builder::{directive, list, named, non_null};
+use crate::common::{Directive, StringValue, Text, Type, Value};
+use crate::position::Pos;
+use crate::schema::ast::*;
+
+/// Returns an empty document
+pub fn document<'a, T: Text<'a>>() -> Document<'a, T> {
+    Document {
+        definitions: vec![],
+    }
+}
+
+/// Returns a schema definition without root operation types
+pub fn schema<'a, T: Text<'a>>() -> SchemaDefinition<'a, T> {
+    SchemaDefinition {
+        position: Pos::default(),
+        directives: vec![],
+        query: None,
+        mutation: None,
+        subscription: None,
+    }
+}
+
+/// Returns a scalar type
+pub fn scalar<'a, T: Text<'a>>(name: impl Into<T::Value>) -> ScalarType<'a, T> {
+    ScalarType::new(name.into())
+}
+
+/// Returns an object type without fields
+pub fn object<'a, T: Text<'a>>(name: impl Into<T::Value>) -> ObjectType<'a, T> {
+    ObjectType::new(name.into())
+}
+
+/// Returns an interface type without fields
+pub fn interface<'a, T: Text<'a>>(name: impl Into<T::Value>) -> InterfaceType<'a, T> {
+    InterfaceType::new(name.into())
+}
+
+/// Returns a union type without members
+pub fn union_type<'a, T: Text<'a>>(name: impl Into<T::Value>) -> UnionType<'a, T> {
+    UnionType::new(name.into())
+}
+
+/// Returns an enum type without values
+pub fn enum_type<'a, T: Text<'a>>(name: impl Into<T::Value>) -> EnumType<'a, T> {
+    EnumType::new(name.into())
+}
+
+/// Returns a value for `EnumType::add_value`
+pub fn enum_value<'a, T: Text<'a>>(name: impl Into<T::Value>) -> EnumValue<'a, T> {
+    EnumValue::new(name.into())
+}
+
+/// Returns an input object type without fields
+pub fn input_object<'a, T: Text<'a>>(name: impl Into<T::Value>) -> InputObjectType<'a, T> {
+    InputObjectType::new(name.into())
+}
+
+/// Returns a field definition for `add_field`
+pub fn field<'a, T: Text<'a>>(name: impl Into<T::Value>, field_type: Type<'a, T>) -> Field<'a, T> {
+    Field::new(name.into(), field_type)
+}
+
+/// Returns an argument or an input field for `add_argument` and `add_field`
+pub fn input_value<'a, T: Text<'a>>(
+    name: impl Into<T::Value>,
+    value_type: Type<'a, T>,
+) -> InputValue<'a, T> {
+    InputValue::new(name.into(), value_type)
+}
+
+/// Returns a directive definition without locations
+pub fn directive_definition<'a, T: Text<'a>>(
+    name: impl Into<T::Value>,
+) -> DirectiveDefinition<'a, T> {
+    DirectiveDefinition::new(name.into())
+}
+
+impl<'a, T: Text<'a>> Document<'a, T> {
+    /// Appends a definition
+    pub fn definition(mut self, definition: impl Into<Definition<'a, T>>) -> Self {
+        self.definitions.push(definition.into());
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> SchemaDefinition<'a, T> {
+    /// Sets the query root type
+    pub fn query(mut self, name: impl Into<T::Value>) -> Self {
+        self.query = Some(name.into());
+        self
+    }
+
+    /// Sets the mutation root type
+    pub fn mutation(mut self, name: impl Into<T::Value>) -> Self {
+        self.mutation = Some(name.into());
+        self
+    }
+
+    /// Sets the subscription root type
+    pub fn subscription(mut self, name: impl Into<T::Value>) -> Self {
+        self.subscription = Some(name.into());
+        self
+    }
+}
+
+macro_rules! impl_description {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Sets the description
+                pub fn description(mut self, description: impl Into<StringValue<'a, T>>) -> Self {
+                    self.description = Some(description.into());
+                    self
+                }
+            }
+        )+
+    };
+}
+
+macro_rules! impl_directive {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Appends a directive
+                pub fn directive(mut self, directive: Directive<'a, T>) -> Self {
+                    self.directives.push(directive);
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_description!(
+    ScalarType,
+    ObjectType,
+    InterfaceType,
+    UnionType,
+    EnumType,
+    EnumValue,
+    InputObjectType,
+    Field,
+    InputValue,
+    DirectiveDefinition
+);
+
+impl_directive!(
+    SchemaDefinition,
+    ScalarType,
+    ObjectType,
+    InterfaceType,
+    UnionType,
+    EnumType,
+    EnumValue,
+    InputObjectType,
+    Field,
+    InputValue
+);
+
+macro_rules! impl_fields {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Appends an implemented interface
+                pub fn implements(mut self, name: impl Into<T::Value>) -> Self {
+                    self.implements_interfaces.push(name.into());
+                    self
+                }
+
+                /// Appends a field without arguments
+                pub fn field(self, name: impl Into<T::Value>, field_type: Type<'a, T>) -> Self {
+                    self.add_field(field(name, field_type))
+                }
+
+                /// Appends a field built with [`field`]
+                pub fn add_field(mut self, field: Field<'a, T>) -> Self {
+                    self.fields.push(field);
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_fields!(ObjectType, InterfaceType);
+
+impl<'a, T: Text<'a>> UnionType<'a, T> {
+    /// Appends a member type
+    pub fn member(mut self, name: impl Into<T::Value>) -> Self {
+        self.types.push(name.into());
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> EnumType<'a, T> {
+    /// Appends a value
+    pub fn value(self, name: impl Into<T::Value>) -> Self {
+        self.add_value(enum_value(name))
+    }
+
+    /// Appends a value built with [`enum_value`]
+    pub fn add_value(mut self, value: EnumValue<'a, T>) -> Self {
+        self.values.push(value);
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> InputObjectType<'a, T> {
+    /// Appends an input field
+    pub fn field(self, name: impl Into<T::Value>, value_type: Type<'a, T>) -> Self {
+        self.add_field(input_value(name, value_type))
+    }
+
+    /// Appends an input field built with [`input_value`]
+    pub fn add_field(mut self, field: InputValue<'a, T>) -> Self {
+        self.fields.push(field);
+        self
+    }
+}
+
+macro_rules! impl_arguments {
+    ($($typ: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> $typ<'a, T> {
+                /// Appends an argument
+                pub fn argument(self, name: impl Into<T::Value>, value_type: Type<'a, T>) -> Self {
+                    self.add_argument(input_value(name, value_type))
+                }
+
+                /// Appends an argument built with [`input_value`]
+                pub fn add_argument(mut self, argument: InputValue<'a, T>) -> Self {
+                    self.arguments.push(argument);
+                    self
+                }
+            }
+        )+
+    };
+}
+
+impl_arguments!(Field, DirectiveDefinition);
+
+impl<'a, T: Text<'a>> InputValue<'a, T> {
+    /// Sets the default value
+    pub fn default_value(mut self, value: Value<'a, T>) -> Self {
+        self.default_value = Some(value);
+        self
+    }
+}
+
+impl<'a, T: Text<'a>> DirectiveDefinition<'a, T> {
+    /// Appends a location where the directive may be used
+    pub fn location(mut self, location: DirectiveLocation) -> Self {
+        self.locations.push(location);
+        self
+    }
+
+    /// Allows using the directive more than once at a location
+    pub fn repeatable(mut self) -> Self {
+        self.repeatable = true;
+        self
+    }
+}
+
+macro_rules! impl_into_definition {
+    ($($typ: ident => $outer: ident :: $variant: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> From<$typ<'a, T>> for $outer<'a, T> {
+                fn from(value: $typ<'a, T>) -> Self {
+                    $outer::$variant(value)
+                }
+            }
+        )+
+    };
+}
+
+impl_into_definition!(
+    SchemaDefinition => Definition::SchemaDefinition,
+    TypeDefinition => Definition::TypeDefinition,
+    TypeExtension => Definition::TypeExtension,
+    DirectiveDefinition => Definition::DirectiveDefinition,
+    ScalarType => TypeDefinition::Scalar,
+    ObjectType => TypeDefinition::Object,
+    InterfaceType => TypeDefinition::Interface,
+    UnionType => TypeDefinition::Union,
+    EnumType => TypeDefinition::Enum,
+    InputObjectType => TypeDefinition::InputObject,
+    ScalarTypeExtension => TypeExtension::Scalar,
+    ObjectTypeExtension => TypeExtension::Object,
+    InterfaceTypeExtension => TypeExtension::Interface,
+    UnionTypeExtension => TypeExtension::Union,
+    EnumTypeExtension => TypeExtension::Enum,
+    InputObjectTypeExtension => TypeExtension::InputObject
+);
+
+macro_rules! impl_type_into_definition {
+    ($($typ: ident => $outer: ident),+) => {
+        $(
+            impl<'a, T: Text<'a>> From<$typ<'a, T>> for Definition<'a, T> {
+                fn from(value: $typ<'a, T>) -> Self {
+                    Definition::from($outer::from(value))
+                }
+            }
+        )+
+    };
+}
+
+impl_type_into_definition!(
+    ScalarType => TypeDefinition,
+    ObjectType => TypeDefinition,
+    InterfaceType => TypeDefinition,
+    UnionType => TypeDefinition,
+    EnumType => TypeDefinition,
+    InputObjectType => TypeDefinition,
+    ScalarTypeExtension => TypeExtension,
+    ObjectTypeExtension => TypeExtension,
+    InterfaceTypeExtension => TypeExtension,
+    UnionTypeExtension => TypeExtension,
+    EnumTypeExtension => TypeExtension,
+    InputObjectTypeExtension => TypeExtension
+);
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::schema::parse_schema;
+
+    #[test]
+    fn object_type() {
+        let doc: Document<String> = document().definition(
+            object("User")
+                .description("A user")
+                .field("id", non_null(named("ID")))
+                .directive(directive("key").argument("fields", Value::String("id".into()))),
+        );
+        assert_eq!(
+            doc.to_string(),
+            "\"A user\"\ntype User @key(fields: \"id\") {\n  id: ID!\n}\n"
+        );
+    }
+
+    #[test]
+    fn borrowed() {
+        let doc: Document<&str> = document()
+            .definition(schema().query("Query"))
+            .definition(object("Query").field("users", non_null(list(named("User")))));
+        assert_eq!(
+            doc.to_string(),
+            "schema {\n  query: Query\n}\n\ntype Query {\n  users: [User]!\n}\n"
+        );
+    }
+
+    #[test]
+    fn round_trip() {
+        let source = "\
+scalar Date
+
+interface Node {
+  id: ID!
+}
+
+union Item = Post | Comment
+
+enum Role {
+  ADMIN
+  \"Default\" USER @deprecated
+}
+
+input Filter {
+  role: Role = USER
+  since: Date
+}
+
+directive @auth(role: Role!) repeatable on OBJECT | FIELD_DEFINITION
+";
+        let built: Document<String> = document()
+            .definition(scalar("Date"))
+            .definition(interface("Node").field("id", non_null(named("ID"))))
+            .definition(union_type("Item").member("Post").member("Comment"))
+            .definition(
+                enum_type("Role").value("ADMIN").add_value(
+                    enum_value("USER")
+                        .description("Default")
+                        .directive(directive("deprecated")),
+                ),
+            )
+            .definition(
+                input_object("Filter")
+                    .add_field(
+                        input_value("role", named("Role"))
+                            .default_value(Value::Enum("USER".into())),
+                    )
+                    .field("since", named("Date")),
+            )
+            .definition(
+                directive_definition("auth")
+                    .argument("role", non_null(named("Role")))
+                    .repeatable()
+                    .location(DirectiveLocation::Object)
+                    .location(DirectiveLocation::FieldDefinition),
+            );
+        assert_eq!(built.to_string(), source);
+        assert_eq!(parse_schema::<String>(source).unwrap().to_string(), source);
+    }
+}
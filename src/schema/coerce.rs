@@ -0,0 +1,714 @@
+//! Input coercion of value literals
+//!
+//! Implements the "Input Coercion" sections of the spec for argument
+//! values, variable default values and default values of the schema itself.
+use std::collections::{BTreeMap, HashMap};
+use std::convert::TryFrom;
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::common::{Number, StringValue, Text, Type, Value};
+use crate::position::Pos;
+use crate::schema::ast::*;
+
+/// A value that can't be coerced to the expected type
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidValue {
+    /// Position of the value, as passed to the coercion, or the position
+    /// of the input value definition for invalid default values
+    pub position: Pos,
+    /// Path to the invalid part of the value, like `filter.tags[1]`,
+    /// empty if the whole value is invalid
+    pub path: String,
+    /// Human readable description of the problem
+    pub message: String,
+}
+
+impl fmt::Display for InvalidValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.path.is_empty() {
+            write!(f, "{}: {}", self.position, self.message)
+        } else {
+            write!(f, "{}: {}: {}", self.position, self.path, self.message)
+        }
+    }
+}
+
+/// Error coercing an input value
+#[derive(Error, Debug)]
+#[error("input coercion error: {}", display_errors(errors))]
+pub struct CoercionError {
+    errors: Vec<InvalidValue>,
+}
+
+impl CoercionError {
+    /// All the invalid values found, in order of appearance
+    pub fn errors(&self) -> &[InvalidValue] {
+        &self.errors
+    }
+}
+
+fn display_errors(errors: &[InvalidValue]) -> String {
+    errors
+        .iter()
+        .map(|e| e.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Coerces `value` to `value_type` as defined in `schema`
+///
+/// This is a shortcut for `InputTypes::new(schema).coerce(..)`, use
+/// `InputTypes` directly to coerce many values against the same schema.
+pub fn coerce_value<'a, T: Text<'a>>(
+    schema: &Document<'a, T>,
+    value: &Value<'a, T>,
+    value_type: &Type<'a, T>,
+    position: Pos,
+) -> Result<Value<'a, T>, CoercionError> {
+    InputTypes::new(schema).coerce(value, value_type, position)
+}
+
+/// Checks that default values of arguments and input fields in `schema`
+/// are valid for their types
+pub fn check_default_values<'a, T: Text<'a>>(
+    schema: &Document<'a, T>,
+) -> Result<(), CoercionError> {
+    InputTypes::new(schema).check_default_values()
+}
+
+enum Kind<'d, 'a, T: Text<'a>> {
+    Scalar,
+    Enum(Vec<&'d EnumValue<'a, T>>),
+    InputObject {
+        fields: Vec<&'d InputValue<'a, T>>,
+        one_of: bool,
+    },
+    Output(&'static str),
+}
+
+/// Input types of a schema, with extensions applied
+pub struct InputTypes<'d, 'a, T: Text<'a>> {
+    schema: &'d Document<'a, T>,
+    types: HashMap<&'d str, Kind<'d, 'a, T>>,
+}
+
+impl<'d, 'a, T: Text<'a>> fmt::Debug for InputTypes<'d, 'a, T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut names = self.types.keys().collect::<Vec<_>>();
+        names.sort();
+        f.debug_struct("InputTypes").field("types", &names).finish()
+    }
+}
+
+fn has_one_of<'a, T: Text<'a>>(directives: &[Directive<'a, T>]) -> bool {
+    directives.iter().any(|d| d.name.as_ref() == "oneOf")
+}
+
+impl<'d, 'a, T: Text<'a>> InputTypes<'d, 'a, T> {
+    pub fn new(schema: &'d Document<'a, T>) -> Self {
+        let mut types = HashMap::new();
+        for definition in &schema.definitions {
+            if let Definition::TypeDefinition(definition) = definition {
+                let (name, kind) = match definition {
+                    TypeDefinition::Scalar(t) => (&t.name, Kind::Scalar),
+                    TypeDefinition::Enum(t) => (&t.name, Kind::Enum(t.values.iter().collect())),
+                    TypeDefinition::InputObject(t) => (
+                        &t.name,
+                        Kind::InputObject {
+                            fields: t.fields.iter().collect(),
+                            one_of: has_one_of(&t.directives),
+                        },
+                    ),
+                    TypeDefinition::Object(t) => (&t.name, Kind::Output("an object")),
+                    TypeDefinition::Interface(t) => (&t.name, Kind::Output("an interface")),
+                    TypeDefinition::Union(t) => (&t.name, Kind::Output("a union")),
+                };
+                types.insert(name.as_ref(), kind);
+            }
+        }
+        for definition in &schema.definitions {
+            match definition {
+                Definition::TypeExtension(TypeExtension::Enum(ext)) => {
+                    if let Some(Kind::Enum(values)) = types.get_mut(ext.name.as_ref()) {
+                        values.extend(&ext.values);
+                    }
+                }
+                Definition::TypeExtension(TypeExtension::InputObject(ext)) => {
+                    if let Some(Kind::InputObject { fields, one_of }) =
+                        types.get_mut(ext.name.as_ref())
+                    {
+                        fields.extend(&ext.fields);
+                        *one_of |= has_one_of(&ext.directives);
+                    }
+                }
+                _ => {}
+            }
+        }
+        InputTypes { schema, types }
+    }
+
+    /// Coerces a value literal to `value_type`
+    ///
+    /// Returns the coerced value: single values are wrapped into lists,
+    /// defaults are added for missing input object fields, integers are
+    /// converted to `Float` and `ID` strings. Variables are kept as is,
+    /// their types are checked against the variable definitions separately.
+    ///
+    /// All the errors are reported at `position`, except errors in default
+    /// values, which are reported at their definitions.
+    pub fn coerce(
+        &self,
+        value: &Value<'a, T>,
+        value_type: &Type<'a, T>,
+        position: Pos,
+    ) -> Result<Value<'a, T>, CoercionError> {
+        let mut coercion = Coercion {
+            types: self,
+            position,
+            path: Vec::new(),
+            defaults: Vec::new(),
+            errors: Vec::new(),
+        };
+        match coercion.coerce(value, value_type) {
+            Some(value) if coercion.errors.is_empty() => Ok(value),
+            _ => Err(CoercionError {
+                errors: coercion.errors,
+            }),
+        }
+    }
+
+    /// Checks that all default values of the schema are valid
+    ///
+    /// Errors are reported at the arguments and input fields having invalid
+    /// defaults, with paths like `Type.field(argument:)`, `Input.field` or
+    /// `@directive(argument:)`.
+    pub fn check_default_values(&self) -> Result<(), CoercionError> {
+        let mut errors = Vec::new();
+        let mut check = |path: String, input: &InputValue<'a, T>| {
+            if let Some(default) = &input.default_value {
+                let mut coercion = Coercion {
+                    types: self,
+                    position: input.position,
+                    path: vec![Segment::Root(path)],
+                    // Input field defaults can't refer to themselves
+                    defaults: vec![input],
+                    errors: Vec::new(),
+                };
+                coercion.coerce(default, &input.value_type);
+                errors.extend(coercion.errors);
+            }
+        };
+        for definition in &self.schema.definitions {
+            match definition {
+                Definition::TypeDefinition(TypeDefinition::Object(t)) => {
+                    check_fields(&t.name, &t.fields, &mut check)
+                }
+                Definition::TypeDefinition(TypeDefinition::Interface(t)) => {
+                    check_fields(&t.name, &t.fields, &mut check)
+                }
+                Definition::TypeDefinition(TypeDefinition::InputObject(t)) => {
+                    for field in &t.fields {
+                        check(
+                            format!("{}.{}", t.name.as_ref(), field.name.as_ref()),
+                            field,
+                        );
+                    }
+                }
+                Definition::TypeExtension(TypeExtension::Object(t)) => {
+                    check_fields(&t.name, &t.fields, &mut check)
+                }
+                Definition::TypeExtension(TypeExtension::Interface(t)) => {
+                    check_fields(&t.name, &t.fields, &mut check)
+                }
+                Definition::TypeExtension(TypeExtension::InputObject(t)) => {
+                    for field in &t.fields {
+                        check(
+                            format!("{}.{}", t.name.as_ref(), field.name.as_ref()),
+                            field,
+                        );
+                    }
+                }
+                Definition::DirectiveDefinition(d) => {
+                    for argument in &d.arguments {
+                        check(
+                            format!("@{}({}:)", d.name.as_ref(), argument.name.as_ref()),
+                            argument,
+                        );
+                    }
+                }
+                _ => {}
+            }
+        }
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(CoercionError { errors })
+        }
+    }
+}
+
+fn check_fields<'a, T: Text<'a>>(
+    type_name: &T::Value,
+    fields: &[Field<'a, T>],
+    check: &mut impl FnMut(String, &InputValue<'a, T>),
+) {
+    for field in fields {
+        for argument in &field.arguments {
+            check(
+                format!(
+                    "{}.{}({}:)",
+                    type_name.as_ref(),
+                    field.name.as_ref(),
+                    argument.name.as_ref()
+                ),
+                argument,
+            );
+        }
+    }
+}
+
+enum Segment<'v> {
+    Root(String),
+    Field(&'v str),
+    Index(usize),
+}
+
+struct Coercion<'t, 'd, 'a, T: Text<'a>> {
+    types: &'t InputTypes<'d, 'a, T>,
+    position: Pos,
+    path: Vec<Segment<'t>>,
+    /// Input fields which default values are being expanded
+    defaults: Vec<&'d InputValue<'a, T>>,
+    errors: Vec<InvalidValue>,
+}
+
+impl<'t, 'd: 't, 'a: 'd, T: Text<'a>> Coercion<'t, 'd, 'a, T> {
+    fn error(&mut self, message: String) -> Option<Value<'a, T>> {
+        let mut path = String::new();
+        for segment in &self.path {
+            match segment {
+                Segment::Root(root) => path.push_str(root),
+                Segment::Field(name) if path.is_empty() => path.push_str(name),
+                Segment::Field(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                Segment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+        self.errors.push(InvalidValue {
+            position: self.position,
+            path,
+            message,
+        });
+        None
+    }
+
+    /// Returns `None` if the value is invalid, the error is recorded then
+    fn coerce(
+        &mut self,
+        value: &'t Value<'a, T>,
+        value_type: &Type<'a, T>,
+    ) -> Option<Value<'a, T>> {
+        if let Value::Variable(name) = value {
+            return Some(Value::Variable(name.clone()));
+        }
+        match value_type {
+            Type::NonNullType(inner) => match value {
+                Value::Null => self.error(format!(
+                    "Expected value of non-null type `{}`, found null",
+                    value_type
+                )),
+                _ => self.coerce(value, inner),
+            },
+            _ if matches!(value, Value::Null) => Some(Value::Null),
+            Type::ListType(inner) => match value {
+                Value::List(items) => {
+                    let mut result = Vec::with_capacity(items.len());
+                    for (index, item) in items.iter().enumerate() {
+                        self.path.push(Segment::Index(index));
+                        let item = self.coerce(item, inner);
+                        self.path.pop();
+                        result.extend(item);
+                    }
+                    if result.len() == items.len() {
+                        Some(Value::List(result))
+                    } else {
+                        None
+                    }
+                }
+                _ => self
+                    .coerce(value, inner)
+                    .map(|item| Value::List(vec![item])),
+            },
+            Type::NamedType(name) => self.coerce_named(value, name.as_ref()),
+        }
+    }
+
+    fn coerce_named(&mut self, value: &'t Value<'a, T>, type_name: &str) -> Option<Value<'a, T>> {
+        match (type_name, value) {
+            ("Int", Value::Int(number)) => match number.as_i64() {
+                Some(n) if i32::try_from(n).is_ok() => Some(Value::Int(number.clone())),
+                _ => self.error(format!(
+                    "Int cannot represent non 32-bit signed integer value: {}",
+                    value
+                )),
+            },
+            ("Int", _) => self.error(format!("Int cannot represent non-integer value: {}", value)),
+            ("Float", Value::Int(number)) => Some(Value::Float(number.0 as f64)),
+            ("Float", Value::Float(n)) => Some(Value::Float(*n)),
+            ("Float", _) => self.error(format!(
+                "Float cannot represent non numeric value: {}",
+                value
+            )),
+            ("String", Value::String(s)) | ("ID", Value::String(s)) => {
+                Some(Value::String(copy_string(s)))
+            }
+            ("String", _) => self.error(format!(
+                "String cannot represent a non string value: {}",
+                value
+            )),
+            ("ID", Value::Int(Number(n))) => Some(Value::String(StringValue::new(n.to_string()))),
+            ("ID", _) => self.error(format!(
+                "ID cannot represent a non-string and non-integer value: {}",
+                value
+            )),
+            ("Boolean", Value::Boolean(b)) => Some(Value::Boolean(*b)),
+            ("Boolean", _) => self.error(format!(
+                "Boolean cannot represent a non boolean value: {}",
+                value
+            )),
+            _ => {
+                let types = self.types;
+                match types.types.get(type_name) {
+                    Some(Kind::Scalar) => Some(copy_value(value)),
+                    Some(Kind::Enum(values)) => self.coerce_enum(value, type_name, values),
+                    Some(Kind::InputObject { fields, one_of }) => {
+                        self.coerce_input_object(value, type_name, fields, *one_of)
+                    }
+                    Some(Kind::Output(kind)) => {
+                        self.error(format!("`{}` is {}, not an input type", type_name, kind))
+                    }
+                    None => self.error(format!("Unknown type `{}`", type_name)),
+                }
+            }
+        }
+    }
+
+    fn coerce_enum(
+        &mut self,
+        value: &Value<'a, T>,
+        type_name: &str,
+        values: &[&EnumValue<'a, T>],
+    ) -> Option<Value<'a, T>> {
+        match value {
+            Value::Enum(name) if values.iter().any(|v| v.name == *name) => {
+                Some(Value::Enum(name.clone()))
+            }
+            Value::Enum(name) => self.error(format!(
+                "Value `{}` does not exist in `{}` enum",
+                name.as_ref(),
+                type_name
+            )),
+            _ => self.error(format!(
+                "Enum `{}` cannot represent non-enum value: {}",
+                type_name, value
+            )),
+        }
+    }
+
+    fn coerce_input_object(
+        &mut self,
+        value: &'t Value<'a, T>,
+        type_name: &str,
+        fields: &[&'d InputValue<'a, T>],
+        one_of: bool,
+    ) -> Option<Value<'a, T>> {
+        let given = match value {
+            Value::Object(given) => given,
+            _ => {
+                return self.error(format!(
+                    "Expected value of type `{}`, found {}",
+                    type_name, value
+                ))
+            }
+        };
+        let errors = self.errors.len();
+        for name in given.keys() {
+            if !fields.iter().any(|field| field.name == *name) {
+                self.path.push(Segment::Field(name.as_ref()));
+                self.error(format!(
+                    "Field `{}` is not defined by type `{}`",
+                    name.as_ref(),
+                    type_name
+                ));
+                self.path.pop();
+            }
+        }
+        let mut result = BTreeMap::new();
+        for field in fields {
+            let coerced = match (given.get(field.name.as_ref()), &field.default_value) {
+                (Some(value), _) => {
+                    self.path.push(Segment::Field(field.name.as_ref()));
+                    let coerced = self.coerce(value, &field.value_type);
+                    self.path.pop();
+                    coerced
+                }
+                (None, Some(default)) => {
+                    // Errors in defaults point to their definitions
+                    let position = std::mem::replace(&mut self.position, field.position);
+                    self.path.push(Segment::Field(field.name.as_ref()));
+                    let coerced = if self.defaults.iter().any(|d| std::ptr::eq(*d, *field)) {
+                        self.error(format!(
+                            "Circular default value of field `{}.{}`",
+                            type_name,
+                            field.name.as_ref()
+                        ))
+                    } else {
+                        self.defaults.push(field);
+                        let coerced = self.coerce(default, &field.value_type);
+                        self.defaults.pop();
+                        coerced
+                    };
+                    self.path.pop();
+                    self.position = position;
+                    coerced
+                }
+                (None, None) => {
+                    if let Type::NonNullType(_) = field.value_type {
+                        self.error(format!(
+                            "Field `{}.{}` of required type `{}` was not provided",
+                            type_name,
+                            field.name.as_ref(),
+                            field.value_type
+                        ));
+                    }
+                    continue;
+                }
+            };
+            if let Some(coerced) = coerced {
+                result.insert(field.name.clone(), coerced);
+            }
+        }
+        if one_of {
+            match given.iter().collect::<Vec<_>>()[..] {
+                [(name, Value::Null)] => {
+                    self.error(format!(
+                        "Field `{}.{}` must be non-null",
+                        type_name,
+                        name.as_ref()
+                    ));
+                }
+                [_] => {}
+                _ => {
+                    self.error(format!(
+                        "OneOf input object `{}` must specify exactly one key",
+                        type_name
+                    ));
+                }
+            }
+        }
+        if self.errors.len() == errors {
+            Some(Value::Object(result))
+        } else {
+            None
+        }
+    }
+}
+
+fn copy_string<'a, T: Text<'a>>(value: &StringValue<'a, T>) -> StringValue<'a, T> {
+    if value.is_block() {
+        StringValue::block(value.as_str())
+    } else {
+        StringValue::new(value.as_str())
+    }
+}
+
+fn copy_value<'a, T: Text<'a>>(value: &Value<'a, T>) -> Value<'a, T> {
+    match value {
+        Value::Variable(name) => Value::Variable(name.clone()),
+        Value::Int(number) => Value::Int(number.clone()),
+        Value::Float(number) => Value::Float(*number),
+        Value::String(value) => Value::String(copy_string(value)),
+        Value::Boolean(value) => Value::Boolean(*value),
+        Value::Null => Value::Null,
+        Value::Enum(name) => Value::Enum(name.clone()),
+        Value::List(items) => Value::List(items.iter().map(copy_value).collect()),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), copy_value(value)))
+                .collect(),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{check_default_values, InputTypes};
+    use crate::position::Pos;
+    use crate::schema::{parse_schema, Document};
+    use crate::{parse_type, parse_value};
+
+    const SCHEMA: &str = "\
+scalar Date
+enum Role { ADMIN USER }
+extend enum Role { GUEST }
+input Filter {
+  role: Role = USER
+  ids: [ID!]
+  since: Date
+  limit: Int!
+}
+input Key @oneOf { id: ID name: String }
+type Query { users(filter: Filter): [ID] }
+";
+
+    fn schema() -> Document<'static, &'static str> {
+        parse_schema(SCHEMA).unwrap()
+    }
+
+    fn coerce(
+        types: &InputTypes<'_, 'static, &'static str>,
+        value: &'static str,
+        value_type: &'static str,
+    ) -> Result<String, Vec<String>> {
+        let value = parse_value(value).unwrap();
+        let value_type = parse_type(value_type).unwrap();
+        types
+            .coerce(&value, &value_type, Pos { line: 1, column: 2 })
+            .map(|v| v.to_string())
+            .map_err(|e| e.errors().iter().map(|e| e.to_string()).collect())
+    }
+
+    #[test]
+    fn scalars() {
+        let schema = schema();
+        let types = InputTypes::new(&schema);
+        assert_eq!(coerce(&types, "1", "Float").unwrap(), "1.0");
+        assert_eq!(coerce(&types, "1", "ID").unwrap(), "\"1\"");
+        assert_eq!(coerce(&types, "{a: [1]}", "Date").unwrap(), "{a: [1]}");
+        assert_eq!(coerce(&types, "null", "Int").unwrap(), "null");
+        assert_eq!(coerce(&types, "$x", "Int!").unwrap(), "$x");
+        assert_eq!(
+            coerce(&types, "3000000000", "Int").unwrap_err(),
+            ["1:2: Int cannot represent non 32-bit signed integer value: 3000000000"]
+        );
+        assert_eq!(
+            coerce(&types, "\"1\"", "Int").unwrap_err(),
+            ["1:2: Int cannot represent non-integer value: \"1\""]
+        );
+        assert_eq!(
+            coerce(&types, "null", "Boolean!").unwrap_err(),
+            ["1:2: Expected value of non-null type `Boolean!`, found null"]
+        );
+        assert_eq!(
+            coerce(&types, "1", "Query").unwrap_err(),
+            ["1:2: `Query` is an object, not an input type"]
+        );
+    }
+
+    #[test]
+    fn lists_and_enums() {
+        let schema = schema();
+        let types = InputTypes::new(&schema);
+        assert_eq!(coerce(&types, "GUEST", "[Role]").unwrap(), "[GUEST]");
+        assert_eq!(coerce(&types, "[[1], 2]", "[[Int]]").unwrap(), "[[1], [2]]");
+        assert_eq!(
+            coerce(&types, "[ADMIN, \"USER\", OWNER]", "[Role]").unwrap_err(),
+            [
+                "1:2: [1]: Enum `Role` cannot represent non-enum value: \"USER\"",
+                "1:2: [2]: Value `OWNER` does not exist in `Role` enum",
+            ]
+        );
+    }
+
+    #[test]
+    fn input_objects() {
+        let schema = schema();
+        let types = InputTypes::new(&schema);
+        assert_eq!(
+            coerce(&types, "{limit: 10, ids: 1}", "Filter").unwrap(),
+            "{ids: [\"1\"], limit: 10, role: USER}"
+        );
+        assert_eq!(
+            coerce(&types, "{ids: [null], extra: 1}", "Filter").unwrap_err(),
+            [
+                "1:2: extra: Field `extra` is not defined by type `Filter`",
+                "1:2: ids[0]: Expected value of non-null type `ID!`, found null",
+                "1:2: Field `Filter.limit` of required type `Int!` was not provided",
+            ]
+        );
+        assert_eq!(
+            coerce(&types, "[{limit: 1, role: 1}]", "[Filter]").unwrap_err(),
+            ["1:2: [0].role: Enum `Role` cannot represent non-enum value: 1"]
+        );
+    }
+
+    #[test]
+    fn one_of() {
+        let schema = schema();
+        let types = InputTypes::new(&schema);
+        assert_eq!(coerce(&types, "{id: 1}", "Key").unwrap(), "{id: \"1\"}");
+        assert_eq!(
+            coerce(&types, "{id: 1, name: \"x\"}", "Key").unwrap_err(),
+            ["1:2: OneOf input object `Key` must specify exactly one key"]
+        );
+        assert_eq!(
+            coerce(&types, "{name: null}", "Key").unwrap_err(),
+            ["1:2: Field `Key.name` must be non-null"]
+        );
+    }
+
+    #[test]
+    fn circular_default() {
+        let schema =
+            parse_schema::<&str>("input A { x: A = {} }\ntype Query { f(a: A = {}): Int }")
+                .unwrap();
+        let err = check_default_values(&schema).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input coercion error: \
+             1:11: A.x.x: Circular default value of field `A.x`\n\
+             1:11: Query.f(a:).x.x: Circular default value of field `A.x`"
+        );
+        let types = InputTypes::new(&schema);
+        assert_eq!(
+            coerce(&types, "{}", "A").unwrap_err(),
+            ["1:11: x.x: Circular default value of field `A.x`"]
+        );
+        assert_eq!(
+            coerce(&types, "{x: {x: null}}", "A").unwrap(),
+            "{x: {x: null}}"
+        );
+    }
+
+    #[test]
+    fn default_values() {
+        let schema = parse_schema::<&str>(
+            "\
+enum Role { ADMIN }
+input Filter {
+  role: Role = \"ADMIN\"
+}
+type Query {
+  users(first: Int = 1.5, filter: Filter = {}): [Int]
+}
+directive @limit(max: Int = null, of: [Role!] = [ADMIN, null]) on FIELD
+",
+        )
+        .unwrap();
+        let err = check_default_values(&schema).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "input coercion error: \
+             3:3: Filter.role: Enum `Role` cannot represent non-enum value: \"ADMIN\"\n\
+             6:9: Query.users(first:): Int cannot represent non-integer value: 1.5\n\
+             3:3: Query.users(filter:).role: \
+             Enum `Role` cannot represent non-enum value: \"ADMIN\"\n\
+             8:35: @limit(of:)[1]: Expected value of non-null type `Role!`, found null"
+        );
+    }
+}
@@ -0,0 +1,1197 @@
+//! Comparison of two schemas
+//!
+//! Changes are classified the same way as `findBreakingChanges` and
+//! `findDangerousChanges` of graphql-js do it.
+use std::collections::HashMap;
+
+use crate::common::{Text, Type};
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::semantic::{FieldOrder, SemanticEq};
+
+/// How a change affects existing clients
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Criticality {
+    /// Queries that were valid may become invalid or get different
+    /// results
+    Breaking,
+    /// Queries stay valid, but clients may not be prepared for new
+    /// values, like an enum value they don't know of
+    Dangerous,
+    /// Change doesn't affect existing clients
+    Safe,
+}
+
+/// Kind of the change
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ChangeKind {
+    TypeAdded,
+    TypeRemoved,
+    TypeKindChanged,
+    TypeDescriptionChanged,
+    FieldAdded,
+    FieldRemoved,
+    FieldTypeChanged,
+    FieldDescriptionChanged,
+    ArgumentAdded,
+    ArgumentRemoved,
+    ArgumentTypeChanged,
+    ArgumentDefaultChanged,
+    ArgumentDescriptionChanged,
+    InputFieldAdded,
+    InputFieldRemoved,
+    InputFieldTypeChanged,
+    InputFieldDefaultChanged,
+    InputFieldDescriptionChanged,
+    EnumValueAdded,
+    EnumValueRemoved,
+    EnumValueDescriptionChanged,
+    UnionMemberAdded,
+    UnionMemberRemoved,
+    InterfaceAdded,
+    InterfaceRemoved,
+    DirectiveAdded,
+    DirectiveRemoved,
+    DirectiveDescriptionChanged,
+    DirectiveRepeatableAdded,
+    DirectiveRepeatableRemoved,
+    DirectiveLocationAdded,
+    DirectiveLocationRemoved,
+    DirectiveArgumentAdded,
+    DirectiveArgumentRemoved,
+    DirectiveArgumentTypeChanged,
+    DirectiveArgumentDefaultChanged,
+    DirectiveArgumentDescriptionChanged,
+    SchemaRootAdded,
+    SchemaRootRemoved,
+    SchemaRootChanged,
+}
+
+/// A difference between two schemas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Change {
+    pub kind: ChangeKind,
+    pub criticality: Criticality,
+    /// Path of the changed element, like `Type.field(argument:)`,
+    /// `Enum.VALUE` or `@directive`
+    pub path: String,
+    /// Human readable description of the change
+    pub message: String,
+    /// Position of the element in the old schema, if it's there
+    pub old_position: Option<Pos>,
+    /// Position of the element in the new schema, if it's there
+    pub new_position: Option<Pos>,
+}
+
+/// Compares two schemas and returns all the changes made in `new` one
+///
+/// Type extensions are merged into the types they extend, so moving a field
+/// into an extension isn't a change. Changes are ordered as the elements
+/// appear in the old schema, followed by the added ones.
+pub fn diff<'a, T: Text<'a>>(old: &Document<'a, T>, new: &Document<'a, T>) -> Vec<Change> {
+    let mut changes = Changes(Vec::new());
+    let old_types = Types::collect(old);
+    let new_types = Types::collect(new);
+    for (name, old_type) in &old_types.list {
+        match new_types.get(name) {
+            Some(new_type) => diff_type(name, old_type, new_type, &mut changes),
+            None => changes.push(
+                ChangeKind::TypeRemoved,
+                Criticality::Breaking,
+                name.to_string(),
+                format!("Type `{}` was removed", name),
+                Some(old_type.position),
+                None,
+            ),
+        }
+    }
+    for (name, new_type) in &new_types.list {
+        if old_types.get(name).is_none() {
+            changes.push(
+                ChangeKind::TypeAdded,
+                Criticality::Safe,
+                name.to_string(),
+                format!("Type `{}` was added", name),
+                None,
+                Some(new_type.position),
+            );
+        }
+    }
+    diff_schema(old, new, &old_types, &new_types, &mut changes);
+    diff_directives(old, new, &mut changes);
+    changes.0
+}
+
+struct Changes(Vec<Change>);
+
+impl Changes {
+    fn push(
+        &mut self,
+        kind: ChangeKind,
+        criticality: Criticality,
+        path: String,
+        message: String,
+        old_position: Option<Pos>,
+        new_position: Option<Pos>,
+    ) {
+        self.0.push(Change {
+            kind,
+            criticality,
+            path,
+            message,
+            old_position,
+            new_position,
+        });
+    }
+}
+
+/// Type with all of its extensions merged
+struct TypeView<'d, 'a, T: Text<'a>> {
+    kind: &'static str,
+    position: Pos,
+    description: Option<&'d StringValue<'a, T>>,
+    fields: Vec<&'d Field<'a, T>>,
+    interfaces: Vec<&'d T::Value>,
+    members: Vec<&'d T::Value>,
+    values: Vec<&'d EnumValue<'a, T>>,
+    inputs: Vec<&'d InputValue<'a, T>>,
+}
+
+impl<'d, 'a, T: Text<'a>> TypeView<'d, 'a, T> {
+    fn new(kind: &'static str, position: Pos) -> Self {
+        TypeView {
+            kind,
+            position,
+            description: None,
+            fields: Vec::new(),
+            interfaces: Vec::new(),
+            members: Vec::new(),
+            values: Vec::new(),
+            inputs: Vec::new(),
+        }
+    }
+}
+
+struct Types<'d, 'a, T: Text<'a>> {
+    list: Vec<(&'d str, TypeView<'d, 'a, T>)>,
+    index: HashMap<&'d str, usize>,
+}
+
+impl<'d, 'a, T: Text<'a>> Types<'d, 'a, T> {
+    fn collect(document: &'d Document<'a, T>) -> Self {
+        let mut types = Types {
+            list: Vec::new(),
+            index: HashMap::new(),
+        };
+        // Definitions go first, so that extensions can't change the kind
+        for def in &document.definitions {
+            if let Definition::TypeDefinition(ref t) = *def {
+                types.add_definition(t);
+            }
+        }
+        for def in &document.definitions {
+            if let Definition::TypeExtension(ref e) = *def {
+                types.add_extension(e);
+            }
+        }
+        types
+    }
+
+    fn get(&self, name: &str) -> Option<&TypeView<'d, 'a, T>> {
+        self.index.get(name).map(|&idx| &self.list[idx].1)
+    }
+
+    /// Returns the type to merge the definition into, `None` if a type of
+    /// the other kind is already there
+    fn entry(
+        &mut self,
+        name: &'d str,
+        kind: &'static str,
+        position: Pos,
+    ) -> Option<&mut TypeView<'d, 'a, T>> {
+        let idx = match self.index.get(name) {
+            Some(&idx) => idx,
+            None => {
+                self.index.insert(name, self.list.len());
+                self.list.push((name, TypeView::new(kind, position)));
+                self.list.len() - 1
+            }
+        };
+        let view = &mut self.list[idx].1;
+        if view.kind == kind {
+            Some(view)
+        } else {
+            None
+        }
+    }
+
+    fn add_definition(&mut self, definition: &'d TypeDefinition<'a, T>) {
+        match *definition {
+            TypeDefinition::Scalar(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "scalar", t.position) {
+                    view.description = view.description.or(t.description.as_ref());
+                }
+            }
+            TypeDefinition::Object(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "object", t.position) {
+                    view.description = view.description.or(t.description.as_ref());
+                    view.interfaces.extend(&t.implements_interfaces);
+                    view.fields.extend(&t.fields);
+                }
+            }
+            TypeDefinition::Interface(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "interface", t.position) {
+                    view.description = view.description.or(t.description.as_ref());
+                    view.interfaces.extend(&t.implements_interfaces);
+                    view.fields.extend(&t.fields);
+                }
+            }
+            TypeDefinition::Union(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "union", t.position) {
+                    view.description = view.description.or(t.description.as_ref());
+                    view.members.extend(&t.types);
+                }
+            }
+            TypeDefinition::Enum(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "enum", t.position) {
+                    view.description = view.description.or(t.description.as_ref());
+                    view.values.extend(&t.values);
+                }
+            }
+            TypeDefinition::InputObject(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "input object", t.position) {
+                    view.description = view.description.or(t.description.as_ref());
+                    view.inputs.extend(&t.fields);
+                }
+            }
+        }
+    }
+
+    fn add_extension(&mut self, extension: &'d TypeExtension<'a, T>) {
+        match *extension {
+            TypeExtension::Scalar(ref t) => {
+                self.entry(t.name.as_ref(), "scalar", t.position);
+            }
+            TypeExtension::Object(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "object", t.position) {
+                    view.interfaces.extend(&t.implements_interfaces);
+                    view.fields.extend(&t.fields);
+                }
+            }
+            TypeExtension::Interface(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "interface", t.position) {
+                    view.interfaces.extend(&t.implements_interfaces);
+                    view.fields.extend(&t.fields);
+                }
+            }
+            TypeExtension::Union(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "union", t.position) {
+                    view.members.extend(&t.types);
+                }
+            }
+            TypeExtension::Enum(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "enum", t.position) {
+                    view.values.extend(&t.values);
+                }
+            }
+            TypeExtension::InputObject(ref t) => {
+                if let Some(view) = self.entry(t.name.as_ref(), "input object", t.position) {
+                    view.inputs.extend(&t.fields);
+                }
+            }
+        }
+    }
+}
+
+fn find<'x, I, F>(items: &[&'x I], name: &str, key: F) -> Option<&'x I>
+where
+    F: Fn(&I) -> &str,
+{
+    items.iter().copied().find(|item| key(item) == name)
+}
+
+fn description_changed<'a, T: Text<'a>>(
+    old: Option<&StringValue<'a, T>>,
+    new: Option<&StringValue<'a, T>>,
+) -> bool {
+    old.map(|d| d.as_str()) != new.map(|d| d.as_str())
+}
+
+fn diff_type<'a, T: Text<'a>>(
+    name: &str,
+    old: &TypeView<'_, 'a, T>,
+    new: &TypeView<'_, 'a, T>,
+    changes: &mut Changes,
+) {
+    let positions = (Some(old.position), Some(new.position));
+    if old.kind != new.kind {
+        changes.push(
+            ChangeKind::TypeKindChanged,
+            Criticality::Breaking,
+            name.to_string(),
+            format!(
+                "Type `{}` changed from {} to {}",
+                name,
+                article(old.kind),
+                article(new.kind)
+            ),
+            positions.0,
+            positions.1,
+        );
+        return;
+    }
+    if description_changed(old.description, new.description) {
+        changes.push(
+            ChangeKind::TypeDescriptionChanged,
+            Criticality::Safe,
+            name.to_string(),
+            format!("Description of type `{}` changed", name),
+            positions.0,
+            positions.1,
+        );
+    }
+    diff_fields(name, &old.fields, &new.fields, changes);
+    diff_names(
+        name,
+        &old.interfaces,
+        &new.interfaces,
+        positions,
+        changes,
+        |type_name, interface, added| {
+            if added {
+                (
+                    ChangeKind::InterfaceAdded,
+                    Criticality::Dangerous,
+                    format!("`{}` now implements interface `{}`", type_name, interface),
+                )
+            } else {
+                (
+                    ChangeKind::InterfaceRemoved,
+                    Criticality::Breaking,
+                    format!(
+                        "`{}` no longer implements interface `{}`",
+                        type_name, interface
+                    ),
+                )
+            }
+        },
+    );
+    diff_names(
+        name,
+        &old.members,
+        &new.members,
+        positions,
+        changes,
+        |union, member, added| {
+            if added {
+                (
+                    ChangeKind::UnionMemberAdded,
+                    Criticality::Dangerous,
+                    format!("`{}` was added to union `{}`", member, union),
+                )
+            } else {
+                (
+                    ChangeKind::UnionMemberRemoved,
+                    Criticality::Breaking,
+                    format!("`{}` was removed from union `{}`", member, union),
+                )
+            }
+        },
+    );
+    diff_enum_values(name, &old.values, &new.values, changes);
+    diff_input_fields(name, &old.inputs, &new.inputs, changes);
+}
+
+fn article(kind: &str) -> String {
+    match kind {
+        "object" | "interface" | "enum" | "input object" => format!("an {}", kind),
+        _ => format!("a {}", kind),
+    }
+}
+
+/// Compares lists of names, like implemented interfaces and union members
+fn diff_names<V, F>(
+    type_name: &str,
+    old: &[&V],
+    new: &[&V],
+    positions: (Option<Pos>, Option<Pos>),
+    changes: &mut Changes,
+    describe: F,
+) where
+    V: AsRef<str> + ?Sized,
+    F: Fn(&str, &str, bool) -> (ChangeKind, Criticality, String),
+{
+    for name in old {
+        let name = (*name).as_ref();
+        if !new.iter().any(|n| (*n).as_ref() == name) {
+            let (kind, criticality, message) = describe(type_name, name, false);
+            let path = format!("{}.{}", type_name, name);
+            changes.push(kind, criticality, path, message, positions.0, positions.1);
+        }
+    }
+    for name in new {
+        let name = (*name).as_ref();
+        if !old.iter().any(|n| (*n).as_ref() == name) {
+            let (kind, criticality, message) = describe(type_name, name, true);
+            let path = format!("{}.{}", type_name, name);
+            changes.push(kind, criticality, path, message, positions.0, positions.1);
+        }
+    }
+}
+
+fn diff_fields<'a, T: Text<'a>>(
+    type_name: &str,
+    old: &[&Field<'a, T>],
+    new: &[&Field<'a, T>],
+    changes: &mut Changes,
+) {
+    for old_field in old {
+        let name = old_field.name.as_ref();
+        let path = format!("{}.{}", type_name, name);
+        let new_field = match find(new, name, |f| f.name.as_ref()) {
+            Some(field) => field,
+            None => {
+                changes.push(
+                    ChangeKind::FieldRemoved,
+                    Criticality::Breaking,
+                    path.clone(),
+                    format!("Field `{}` was removed", path),
+                    Some(old_field.position),
+                    None,
+                );
+                continue;
+            }
+        };
+        let positions = (Some(old_field.position), Some(new_field.position));
+        if !is_safe_output_change(&old_field.field_type, &new_field.field_type) {
+            changes.push(
+                ChangeKind::FieldTypeChanged,
+                Criticality::Breaking,
+                path.clone(),
+                format!(
+                    "Field `{}` changed type from `{}` to `{}`",
+                    path, old_field.field_type, new_field.field_type
+                ),
+                positions.0,
+                positions.1,
+            );
+        } else if old_field.field_type.to_string() != new_field.field_type.to_string() {
+            changes.push(
+                ChangeKind::FieldTypeChanged,
+                Criticality::Safe,
+                path.clone(),
+                format!(
+                    "Field `{}` changed type from `{}` to `{}`",
+                    path, old_field.field_type, new_field.field_type
+                ),
+                positions.0,
+                positions.1,
+            );
+        }
+        if description_changed(
+            old_field.description.as_ref(),
+            new_field.description.as_ref(),
+        ) {
+            changes.push(
+                ChangeKind::FieldDescriptionChanged,
+                Criticality::Safe,
+                path.clone(),
+                format!("Description of field `{}` changed", path),
+                positions.0,
+                positions.1,
+            );
+        }
+        let old_args: Vec<_> = old_field.arguments.iter().collect();
+        let new_args: Vec<_> = new_field.arguments.iter().collect();
+        diff_arguments(&path, &old_args, &new_args, false, changes);
+    }
+    for new_field in new {
+        let name = new_field.name.as_ref();
+        if find(old, name, |f| f.name.as_ref()).is_none() {
+            let path = format!("{}.{}", type_name, name);
+            changes.push(
+                ChangeKind::FieldAdded,
+                Criticality::Safe,
+                path.clone(),
+                format!("Field `{}` was added", path),
+                None,
+                Some(new_field.position),
+            );
+        }
+    }
+}
+
+/// Compares arguments of a field or of a directive
+fn diff_arguments<'a, T: Text<'a>>(
+    parent: &str,
+    old: &[&InputValue<'a, T>],
+    new: &[&InputValue<'a, T>],
+    directive: bool,
+    changes: &mut Changes,
+) {
+    let (removed, added, type_changed, default_changed, description_kind) = if directive {
+        (
+            ChangeKind::DirectiveArgumentRemoved,
+            ChangeKind::DirectiveArgumentAdded,
+            ChangeKind::DirectiveArgumentTypeChanged,
+            ChangeKind::DirectiveArgumentDefaultChanged,
+            ChangeKind::DirectiveArgumentDescriptionChanged,
+        )
+    } else {
+        (
+            ChangeKind::ArgumentRemoved,
+            ChangeKind::ArgumentAdded,
+            ChangeKind::ArgumentTypeChanged,
+            ChangeKind::ArgumentDefaultChanged,
+            ChangeKind::ArgumentDescriptionChanged,
+        )
+    };
+    for old_arg in old {
+        let name = old_arg.name.as_ref();
+        let path = format!("{}({}:)", parent, name);
+        match find(new, name, |a| a.name.as_ref()) {
+            Some(new_arg) => {
+                diff_input_value(
+                    &path,
+                    "Argument",
+                    old_arg,
+                    new_arg,
+                    type_changed,
+                    default_changed,
+                    changes,
+                );
+                if description_changed(old_arg.description.as_ref(), new_arg.description.as_ref()) {
+                    changes.push(
+                        description_kind,
+                        Criticality::Safe,
+                        path.clone(),
+                        format!("Description of argument `{}` changed", path),
+                        Some(old_arg.position),
+                        Some(new_arg.position),
+                    );
+                }
+            }
+            None => changes.push(
+                removed,
+                Criticality::Breaking,
+                path.clone(),
+                format!("Argument `{}` was removed", path),
+                Some(old_arg.position),
+                None,
+            ),
+        }
+    }
+    for new_arg in new {
+        let name = new_arg.name.as_ref();
+        if find(old, name, |a| a.name.as_ref()).is_none() {
+            let path = format!("{}({}:)", parent, name);
+            let (criticality, message) = if is_required(new_arg) {
+                (
+                    Criticality::Breaking,
+                    format!("Required argument `{}` was added", path),
+                )
+            } else {
+                (
+                    Criticality::Dangerous,
+                    format!("Optional argument `{}` was added", path),
+                )
+            };
+            changes.push(
+                added,
+                criticality,
+                path,
+                message,
+                None,
+                Some(new_arg.position),
+            );
+        }
+    }
+}
+
+fn diff_input_fields<'a, T: Text<'a>>(
+    type_name: &str,
+    old: &[&InputValue<'a, T>],
+    new: &[&InputValue<'a, T>],
+    changes: &mut Changes,
+) {
+    for old_field in old {
+        let name = old_field.name.as_ref();
+        let path = format!("{}.{}", type_name, name);
+        match find(new, name, |f| f.name.as_ref()) {
+            Some(new_field) => {
+                diff_input_value(
+                    &path,
+                    "Input field",
+                    old_field,
+                    new_field,
+                    ChangeKind::InputFieldTypeChanged,
+                    ChangeKind::InputFieldDefaultChanged,
+                    changes,
+                );
+                if description_changed(
+                    old_field.description.as_ref(),
+                    new_field.description.as_ref(),
+                ) {
+                    changes.push(
+                        ChangeKind::InputFieldDescriptionChanged,
+                        Criticality::Safe,
+                        path.clone(),
+                        format!("Description of input field `{}` changed", path),
+                        Some(old_field.position),
+                        Some(new_field.position),
+                    );
+                }
+            }
+            None => changes.push(
+                ChangeKind::InputFieldRemoved,
+                Criticality::Breaking,
+                path.clone(),
+                format!("Input field `{}` was removed", path),
+                Some(old_field.position),
+                None,
+            ),
+        }
+    }
+    for new_field in new {
+        let name = new_field.name.as_ref();
+        if find(old, name, |f| f.name.as_ref()).is_none() {
+            let path = format!("{}.{}", type_name, name);
+            let (criticality, message) = if is_required(new_field) {
+                (
+                    Criticality::Breaking,
+                    format!("Required input field `{}` was added", path),
+                )
+            } else {
+                (
+                    Criticality::Dangerous,
+                    format!("Optional input field `{}` was added", path),
+                )
+            };
+            changes.push(
+                ChangeKind::InputFieldAdded,
+                criticality,
+                path,
+                message,
+                None,
+                Some(new_field.position),
+            );
+        }
+    }
+}
+
+/// Compares type and default value of an argument or an input field
+fn diff_input_value<'a, T: Text<'a>>(
+    path: &str,
+    what: &str,
+    old: &InputValue<'a, T>,
+    new: &InputValue<'a, T>,
+    type_changed: ChangeKind,
+    default_changed: ChangeKind,
+    changes: &mut Changes,
+) {
+    let positions = (Some(old.position), Some(new.position));
+    let old_type = old.value_type.to_string();
+    let new_type = new.value_type.to_string();
+    if old_type != new_type {
+        let criticality = if is_safe_input_change(&old.value_type, &new.value_type) {
+            Criticality::Safe
+        } else {
+            Criticality::Breaking
+        };
+        changes.push(
+            type_changed,
+            criticality,
+            path.to_string(),
+            format!(
+                "{} `{}` changed type from `{}` to `{}`",
+                what, path, old_type, new_type
+            ),
+            positions.0,
+            positions.1,
+        );
+    }
+    // Printed literals keep their original quoting, so compare the values
+    if !old
+        .default_value
+        .semantic_eq(&new.default_value, FieldOrder::Significant)
+    {
+        let old_default = old.default_value.as_ref().map(|value| value.to_string());
+        let new_default = new.default_value.as_ref().map(|value| value.to_string());
+        let message = match (&old_default, &new_default) {
+            (Some(old_value), Some(new_value)) => format!(
+                "{} `{}` changed default value from `{}` to `{}`",
+                what, path, old_value, new_value
+            ),
+            (None, Some(value)) => {
+                format!("{} `{}` got default value `{}`", what, path, value)
+            }
+            (_, None) => format!("{} `{}` lost its default value", what, path),
+        };
+        changes.push(
+            default_changed,
+            Criticality::Dangerous,
+            path.to_string(),
+            message,
+            positions.0,
+            positions.1,
+        );
+    }
+}
+
+fn diff_enum_values<'a, T: Text<'a>>(
+    type_name: &str,
+    old: &[&EnumValue<'a, T>],
+    new: &[&EnumValue<'a, T>],
+    changes: &mut Changes,
+) {
+    for old_value in old {
+        let name = old_value.name.as_ref();
+        let path = format!("{}.{}", type_name, name);
+        match find(new, name, |v| v.name.as_ref()) {
+            Some(new_value) => {
+                if description_changed(
+                    old_value.description.as_ref(),
+                    new_value.description.as_ref(),
+                ) {
+                    changes.push(
+                        ChangeKind::EnumValueDescriptionChanged,
+                        Criticality::Safe,
+                        path.clone(),
+                        format!("Description of enum value `{}` changed", path),
+                        Some(old_value.position),
+                        Some(new_value.position),
+                    );
+                }
+            }
+            None => changes.push(
+                ChangeKind::EnumValueRemoved,
+                Criticality::Breaking,
+                path.clone(),
+                format!("Enum value `{}` was removed", path),
+                Some(old_value.position),
+                None,
+            ),
+        }
+    }
+    for new_value in new {
+        let name = new_value.name.as_ref();
+        if find(old, name, |v| v.name.as_ref()).is_none() {
+            let path = format!("{}.{}", type_name, name);
+            changes.push(
+                ChangeKind::EnumValueAdded,
+                Criticality::Dangerous,
+                path.clone(),
+                format!("Enum value `{}` was added", path),
+                None,
+                Some(new_value.position),
+            );
+        }
+    }
+}
+
+fn schema_definition<'d, 'a, T: Text<'a>>(
+    document: &'d Document<'a, T>,
+) -> Option<&'d SchemaDefinition<'a, T>> {
+    document.definitions.iter().find_map(|def| match *def {
+        Definition::SchemaDefinition(ref schema) => Some(schema),
+        _ => None,
+    })
+}
+
+fn diff_schema<'d, 'a, T: Text<'a>>(
+    old: &Document<'a, T>,
+    new: &Document<'a, T>,
+    old_types: &Types<'d, 'a, T>,
+    new_types: &Types<'d, 'a, T>,
+    changes: &mut Changes,
+) {
+    let old_schema = schema_definition(old);
+    let new_schema = schema_definition(new);
+    let roots = [
+        ("query", "Query"),
+        ("mutation", "Mutation"),
+        ("subscription", "Subscription"),
+    ];
+    for &(operation, default) in &roots {
+        // Without schema definition, types with default names are used
+        let root = |schema: Option<&SchemaDefinition<'a, T>>, types: &Types<'d, 'a, T>| match schema
+        {
+            Some(schema) => match operation {
+                "query" => schema.query.as_ref().map(|name| name.as_ref().to_string()),
+                "mutation" => schema
+                    .mutation
+                    .as_ref()
+                    .map(|name| name.as_ref().to_string()),
+                _ => schema
+                    .subscription
+                    .as_ref()
+                    .map(|name| name.as_ref().to_string()),
+            },
+            None => types.get(default).map(|_| default.to_string()),
+        };
+        let old_root = root(old_schema, old_types);
+        let new_root = root(new_schema, new_types);
+        let positions = (
+            old_schema.map(|schema| schema.position),
+            new_schema.map(|schema| schema.position),
+        );
+        let path = format!("schema.{}", operation);
+        match (old_root, new_root) {
+            (Some(old_root), Some(new_root)) if old_root != new_root => changes.push(
+                ChangeKind::SchemaRootChanged,
+                Criticality::Breaking,
+                path,
+                format!(
+                    "Root {} type changed from `{}` to `{}`",
+                    operation, old_root, new_root
+                ),
+                positions.0,
+                positions.1,
+            ),
+            (Some(old_root), None) => changes.push(
+                ChangeKind::SchemaRootRemoved,
+                Criticality::Breaking,
+                path,
+                format!("Root {} type `{}` was removed", operation, old_root),
+                positions.0,
+                positions.1,
+            ),
+            (None, Some(new_root)) => changes.push(
+                ChangeKind::SchemaRootAdded,
+                Criticality::Safe,
+                path,
+                format!("Root {} type `{}` was added", operation, new_root),
+                positions.0,
+                positions.1,
+            ),
+            _ => {}
+        }
+    }
+}
+
+fn directives<'d, 'a, T: Text<'a>>(
+    document: &'d Document<'a, T>,
+) -> Vec<&'d DirectiveDefinition<'a, T>> {
+    document
+        .definitions
+        .iter()
+        .filter_map(|def| match *def {
+            Definition::DirectiveDefinition(ref d) => Some(d),
+            _ => None,
+        })
+        .collect()
+}
+
+fn diff_directives<'a, T: Text<'a>>(
+    old: &Document<'a, T>,
+    new: &Document<'a, T>,
+    changes: &mut Changes,
+) {
+    let old_directives = directives(old);
+    let new_directives = directives(new);
+    for old_dir in &old_directives {
+        let name = old_dir.name.as_ref();
+        let path = format!("@{}", name);
+        let new_dir = match find(&new_directives, name, |d| d.name.as_ref()) {
+            Some(dir) => dir,
+            None => {
+                changes.push(
+                    ChangeKind::DirectiveRemoved,
+                    Criticality::Breaking,
+                    path.clone(),
+                    format!("Directive `{}` was removed", path),
+                    Some(old_dir.position),
+                    None,
+                );
+                continue;
+            }
+        };
+        let positions = (Some(old_dir.position), Some(new_dir.position));
+        if description_changed(old_dir.description.as_ref(), new_dir.description.as_ref()) {
+            changes.push(
+                ChangeKind::DirectiveDescriptionChanged,
+                Criticality::Safe,
+                path.clone(),
+                format!("Description of directive `{}` changed", path),
+                positions.0,
+                positions.1,
+            );
+        }
+        match (old_dir.repeatable, new_dir.repeatable) {
+            (true, false) => changes.push(
+                ChangeKind::DirectiveRepeatableRemoved,
+                Criticality::Breaking,
+                path.clone(),
+                format!("Directive `{}` is no longer repeatable", path),
+                positions.0,
+                positions.1,
+            ),
+            (false, true) => changes.push(
+                ChangeKind::DirectiveRepeatableAdded,
+                Criticality::Safe,
+                path.clone(),
+                format!("Directive `{}` became repeatable", path),
+                positions.0,
+                positions.1,
+            ),
+            _ => {}
+        }
+        for location in &old_dir.locations {
+            if !new_dir.locations.contains(location) {
+                changes.push(
+                    ChangeKind::DirectiveLocationRemoved,
+                    Criticality::Breaking,
+                    path.clone(),
+                    format!(
+                        "Location `{}` was removed from directive `{}`",
+                        location.as_str(),
+                        path
+                    ),
+                    positions.0,
+                    positions.1,
+                );
+            }
+        }
+        for location in &new_dir.locations {
+            if !old_dir.locations.contains(location) {
+                changes.push(
+                    ChangeKind::DirectiveLocationAdded,
+                    Criticality::Safe,
+                    path.clone(),
+                    format!(
+                        "Location `{}` was added to directive `{}`",
+                        location.as_str(),
+                        path
+                    ),
+                    positions.0,
+                    positions.1,
+                );
+            }
+        }
+        let old_args: Vec<_> = old_dir.arguments.iter().collect();
+        let new_args: Vec<_> = new_dir.arguments.iter().collect();
+        diff_arguments(&path, &old_args, &new_args, true, changes);
+    }
+    for new_dir in &new_directives {
+        let name = new_dir.name.as_ref();
+        if find(&old_directives, name, |d| d.name.as_ref()).is_none() {
+            let path = format!("@{}", name);
+            changes.push(
+                ChangeKind::DirectiveAdded,
+                Criticality::Safe,
+                path.clone(),
+                format!("Directive `{}` was added", path),
+                None,
+                Some(new_dir.position),
+            );
+        }
+    }
+}
+
+fn is_required<'a, T: Text<'a>>(value: &InputValue<'a, T>) -> bool {
+    matches!(value.value_type, Type::NonNullType(_)) && value.default_value.is_none()
+}
+
+/// Returns `true` if clients reading the field of `old` type can read the
+/// field of `new` type
+fn is_safe_output_change<'a, T: Text<'a>>(old: &Type<'a, T>, new: &Type<'a, T>) -> bool {
+    match (old, new) {
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        (Type::ListType(old), Type::ListType(new)) => is_safe_output_change(old, new),
+        (Type::NonNullType(old), Type::NonNullType(new)) => is_safe_output_change(old, new),
+        (Type::NonNullType(_), _) => false,
+        (_, Type::NonNullType(new)) => is_safe_output_change(old, new),
+        _ => false,
+    }
+}
+
+/// Returns `true` if values clients passed as `old` type are valid for
+/// `new` type
+fn is_safe_input_change<'a, T: Text<'a>>(old: &Type<'a, T>, new: &Type<'a, T>) -> bool {
+    match (old, new) {
+        (Type::NamedType(old), Type::NamedType(new)) => old == new,
+        (Type::ListType(old), Type::ListType(new)) => is_safe_input_change(old, new),
+        (Type::NonNullType(old), Type::NonNullType(new)) => is_safe_input_change(old, new),
+        (Type::NonNullType(old), _) => is_safe_input_change(old, new),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{diff, ChangeKind, Criticality};
+    use crate::position::Pos;
+    use crate::schema::parse_schema;
+
+    fn changes(old: &str, new: &str) -> Vec<(ChangeKind, Criticality, String)> {
+        let old = parse_schema::<String>(old).unwrap();
+        let new = parse_schema::<String>(new).unwrap();
+        diff(&old, &new)
+            .into_iter()
+            .map(|c| (c.kind, c.criticality, c.path))
+            .collect()
+    }
+
+    #[test]
+    fn same() {
+        let schema = "type Query { a(x: Int = 1): [String!] } enum E { A B }";
+        assert_eq!(changes(schema, schema), vec![]);
+    }
+
+    #[test]
+    fn extensions_are_merged() {
+        assert_eq!(
+            changes(
+                "type Query { a: Int b: Int }",
+                "type Query { a: Int } extend type Query { b: Int }"
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn types() {
+        use self::ChangeKind::*;
+        use self::Criticality::*;
+        assert_eq!(
+            changes(
+                "type Query { a: Int } type A { a: Int } scalar B",
+                "type Query { a: Int } interface A { a: Int } scalar C"
+            ),
+            vec![
+                (TypeKindChanged, Breaking, "A".into()),
+                (TypeRemoved, Breaking, "B".into()),
+                (TypeAdded, Safe, "C".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn fields() {
+        use self::ChangeKind::*;
+        use self::Criticality::*;
+        assert_eq!(
+            changes(
+                "type Query { a: Int b: Int c: Int! d: [Int] e: Int }",
+                "type Query { a: Int! b: String c: Int d: [Int!]! f: Int }"
+            ),
+            vec![
+                (FieldTypeChanged, Safe, "Query.a".into()),
+                (FieldTypeChanged, Breaking, "Query.b".into()),
+                (FieldTypeChanged, Breaking, "Query.c".into()),
+                (FieldTypeChanged, Safe, "Query.d".into()),
+                (FieldRemoved, Breaking, "Query.e".into()),
+                (FieldAdded, Safe, "Query.f".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn arguments() {
+        use self::ChangeKind::*;
+        use self::Criticality::*;
+        assert_eq!(
+            changes(
+                "type Query { a(x: Int, y: Int!, z: Int = 1, w: Int): Int }",
+                "type Query { a(x: Int!, y: Int, z: Int = 2, r: Int!, o: Int): Int }"
+            ),
+            vec![
+                (ArgumentTypeChanged, Breaking, "Query.a(x:)".into()),
+                (ArgumentTypeChanged, Safe, "Query.a(y:)".into()),
+                (ArgumentDefaultChanged, Dangerous, "Query.a(z:)".into()),
+                (ArgumentRemoved, Breaking, "Query.a(w:)".into()),
+                (ArgumentAdded, Breaking, "Query.a(r:)".into()),
+                (ArgumentAdded, Dangerous, "Query.a(o:)".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn enums_unions_interfaces_and_inputs() {
+        use self::ChangeKind::*;
+        use self::Criticality::*;
+        assert_eq!(
+            changes(
+                "enum E { A B } union U = X | Y type X implements I { a: Int } \
+                 input In { a: Int }",
+                "enum E { A C } union U = X | Z type X { a: Int } \
+                 input In { a: Int b: Int! c: Int! = 1 }"
+            ),
+            vec![
+                (EnumValueRemoved, Breaking, "E.B".into()),
+                (EnumValueAdded, Dangerous, "E.C".into()),
+                (UnionMemberRemoved, Breaking, "U.Y".into()),
+                (UnionMemberAdded, Dangerous, "U.Z".into()),
+                (InterfaceRemoved, Breaking, "X.I".into()),
+                (InputFieldAdded, Breaking, "In.b".into()),
+                (InputFieldAdded, Dangerous, "In.c".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn directives_and_schema() {
+        use self::ChangeKind::*;
+        use self::Criticality::*;
+        assert_eq!(
+            changes(
+                "schema { query: Q } directive @a(x: Int) repeatable on FIELD | QUERY \
+                 directive @b on FIELD",
+                "schema { query: R mutation: M } directive @a(y: Int!) on FIELD | MUTATION"
+            ),
+            vec![
+                (SchemaRootChanged, Breaking, "schema.query".into()),
+                (SchemaRootAdded, Safe, "schema.mutation".into()),
+                (DirectiveRepeatableRemoved, Breaking, "@a".into()),
+                (DirectiveLocationRemoved, Breaking, "@a".into()),
+                (DirectiveLocationAdded, Safe, "@a".into()),
+                (DirectiveArgumentRemoved, Breaking, "@a(x:)".into()),
+                (DirectiveArgumentAdded, Breaking, "@a(y:)".into()),
+                (DirectiveRemoved, Breaking, "@b".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn default_values_written_differently() {
+        // Borrowed strings keep their literals, which print differently
+        let old =
+            parse_schema::<&str>(r#"type Query { a(x: String = "A", y: [String] = ["B"]): Int }"#)
+                .unwrap();
+        let new = parse_schema::<&str>(
+            r#"type Query { a(x: String = "\u0041", y: [String] = ["""B"""]): Int }"#,
+        )
+        .unwrap();
+        assert_eq!(diff(&old, &new), vec![]);
+    }
+
+    #[test]
+    fn argument_descriptions() {
+        use self::ChangeKind::*;
+        use self::Criticality::*;
+        assert_eq!(
+            changes(
+                r#"type Query { a("x" x: Int): Int } directive @d("y" y: Int) on FIELD"#,
+                r#"type Query { a("z" x: Int): Int } directive @d(y: Int) on FIELD"#
+            ),
+            vec![
+                (ArgumentDescriptionChanged, Safe, "Query.a(x:)".into()),
+                (DirectiveArgumentDescriptionChanged, Safe, "@d(y:)".into()),
+            ]
+        );
+    }
+
+    #[test]
+    fn positions_and_messages() {
+        let old = parse_schema::<String>("type Query {\n  a: Int\n}").unwrap();
+        let new = parse_schema::<String>("type Query {\n\n  a: String\n}").unwrap();
+        let changes = diff(&old, &new);
+        assert_eq!(changes.len(), 1);
+        assert_eq!(
+            changes[0].message,
+            "Field `Query.a` changed type from `Int` to `String`"
+        );
+        assert_eq!(changes[0].old_position, Some(Pos { line: 2, column: 3 }));
+        assert_eq!(changes[0].new_position, Some(Pos { line: 3, column: 3 }));
+    }
+}
@@ -11,11 +11,24 @@ pub type InternalError<'a> = Errors<Token<'a>, Token<'a>, Pos>;
 /// This structure is opaque for forward compatibility. We are exploring a
 /// way to improve both error message and API.
 #[derive(Error, Debug)]
-#[error("schema parse error: {}", _0)]
-pub struct ParseError(String);
+#[error("schema parse error: {}", message)]
+pub struct ParseError {
+    message: String,
+    position: Pos,
+}
+
+impl ParseError {
+    /// Position in the source where the error occurred
+    pub fn position(&self) -> Pos {
+        self.position
+    }
+}
 
 impl<'a> From<InternalError<'a>> for ParseError {
     fn from(e: InternalError<'a>) -> ParseError {
-        ParseError(format!("{}", e))
+        ParseError {
+            message: format!("{}", e),
+            position: e.position,
+        }
     }
 }
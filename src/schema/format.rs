@@ -1,9 +1,15 @@
+use std::collections::{HashMap, HashSet};
 use std::fmt;
+use std::io;
+use std::ops::Range;
 
 use crate::common::Text;
-use crate::format::{format_directives, Displayable, Formatter, Style};
+use crate::format::{self, format_directives, ordered, overlaps, Displayable, Formatter, Style};
+use crate::format::{TextEdit, Tokens};
+use crate::position::Pos;
 
 use crate::schema::ast::*;
+use crate::schema::{parse_schema, ParseError};
 
 impl<'a, T> Document<'a, T>
 where
@@ -11,24 +17,139 @@ where
 {
     /// Format a document according to style
     pub fn format(&self, style: &Style) -> String {
-        let mut formatter = Formatter::new(style);
-        self.display(&mut formatter);
-        formatter.into_string()
+        format::format(self, style)
+    }
+
+    /// Format a document according to style writing it to `out`
+    ///
+    /// Unlike `format` it doesn't keep the whole output in memory.
+    pub fn format_to<W: fmt::Write>(&self, style: &Style, out: &mut W) -> fmt::Result {
+        format::format_to(self, style, out)
+    }
+
+    /// Format a document according to style writing it to `out`
+    ///
+    /// Output is buffered, so `out` doesn't need to be.
+    pub fn write_to<W: io::Write>(&self, style: &Style, out: &mut W) -> io::Result<()> {
+        format::write_to(self, style, out)
     }
 }
 
-fn to_string<T: Displayable>(v: &T) -> String {
-    let style = Style::default();
-    let mut formatter = Formatter::new(&style);
-    v.display(&mut formatter);
-    formatter.into_string()
+fn description<'a, T: Text<'a>>(description: &Option<StringValue<'a, T>>, f: &mut Formatter) {
+    if let Some(ref descr) = *description {
+        if f.descriptions() {
+            f.indent();
+            f.write_string(descr);
+            f.endline();
+        }
+    }
 }
 
-fn description(description: &Option<String>, f: &mut Formatter) {
+/// Writes description on the same line as the definition
+fn inline_description<'a, T: Text<'a>>(
+    description: &Option<StringValue<'a, T>>,
+    f: &mut Formatter,
+) {
     if let Some(ref descr) = *description {
-        f.indent();
-        f.write_quoted(descr.as_ref());
-        f.endline();
+        if f.descriptions() {
+            f.write_string(descr);
+            f.write(" ");
+        }
+    }
+}
+
+/// Reformats the definitions that overlap byte `range` of the source
+///
+/// Nothing else in the source is changed, except that adjacent definitions
+/// in the range are separated by a single blank line. Definitions
+/// containing comments are left intact, as comments would be lost, use
+/// `skipped_schema_definitions` to find them.
+///
+/// Returns the edits ordered by their position. Use `line_range` to get
+/// a range of lines.
+pub fn format_schema_range(
+    source: &str,
+    range: Range<usize>,
+    style: &Style,
+) -> Result<Vec<TextEdit>, ParseError> {
+    range_edits(source, range, style).map(|(edits, _)| edits)
+}
+
+/// Returns positions of the definitions overlapping byte `range` that
+/// `format_schema_range` leaves intact, because they contain comments
+pub fn skipped_schema_definitions(
+    source: &str,
+    range: Range<usize>,
+) -> Result<Vec<Pos>, ParseError> {
+    range_edits(source, range, &Style::default()).map(|(_, skipped)| skipped)
+}
+
+fn range_edits(
+    source: &str,
+    range: Range<usize>,
+    style: &Style,
+) -> Result<(Vec<TextEdit>, Vec<Pos>), ParseError> {
+    let document = parse_schema::<&str>(source)?;
+    let tokens = Tokens::new(source);
+    let starts: Vec<usize> = document
+        .definitions
+        .iter()
+        .map(|def| {
+            let (position, preceded) = definition_start(def);
+            tokens.find(position) - preceded as usize
+        })
+        .collect();
+    let mut edits = Vec::new();
+    let mut skipped = Vec::new();
+    let mut preceded = false;
+    for (idx, def) in document.definitions.iter().enumerate() {
+        let first = starts[idx];
+        let last = starts.get(idx + 1).copied().unwrap_or(tokens.len()) - 1;
+        if !overlaps(&range, &tokens.bytes(first, last)) {
+            continue;
+        }
+        // Definitions in the range are adjacent
+        if preceded {
+            edits.extend(tokens.separate(first - 1));
+        }
+        preceded = true;
+        if tokens.has_comments(first, last) {
+            skipped.push(tokens.position(first));
+        }
+        edits.extend(tokens.edit(
+            first,
+            last,
+            &format::format_indented(def, style, &tokens, 0),
+        ));
+    }
+    Ok((edits, skipped))
+}
+
+/// Position of the keyword of the definition and whether it's preceded
+/// by a description or the `extend` keyword
+fn definition_start<'a, T>(definition: &Definition<'a, T>) -> (Pos, bool)
+where
+    T: Text<'a>,
+{
+    match *definition {
+        Definition::SchemaDefinition(ref s) => (s.position, false),
+        Definition::TypeDefinition(ref t) => match *t {
+            TypeDefinition::Scalar(ref t) => (t.position, t.description.is_some()),
+            TypeDefinition::Object(ref t) => (t.position, t.description.is_some()),
+            TypeDefinition::Interface(ref t) => (t.position, t.description.is_some()),
+            TypeDefinition::Union(ref t) => (t.position, t.description.is_some()),
+            TypeDefinition::Enum(ref t) => (t.position, t.description.is_some()),
+            TypeDefinition::InputObject(ref t) => (t.position, t.description.is_some()),
+        },
+        Definition::TypeExtension(ref e) => match *e {
+            TypeExtension::Scalar(ref e) => (e.position, true),
+            TypeExtension::Object(ref e) => (e.position, true),
+            TypeExtension::Interface(ref e) => (e.position, true),
+            TypeExtension::Union(ref e) => (e.position, true),
+            TypeExtension::Enum(ref e) => (e.position, true),
+            TypeExtension::InputObject(ref e) => (e.position, true),
+        },
+        Definition::DirectiveDefinition(ref d) => (d.position, d.description.is_some()),
     }
 }
 
@@ -37,17 +158,100 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
-        for item in &self.definitions {
+        for item in definition_order(&self.definitions, f) {
             item.display(f);
         }
     }
 }
 
+fn definition_order<'x, 'a, T>(
+    definitions: &'x [Definition<'a, T>],
+    f: &Formatter,
+) -> Vec<&'x Definition<'a, T>>
+where
+    T: Text<'a>,
+{
+    let group = f.group_extensions();
+    if f.sort_definitions() {
+        return ordered(definitions, true, |def| {
+            let (kind, name) = definition_key(def);
+            let extension = matches!(*def, Definition::TypeExtension(_));
+            (extension && !group, kind, name, extension)
+        });
+    }
+    if !group {
+        return definitions.iter().collect();
+    }
+    let mut defined = HashSet::new();
+    let mut extensions = HashMap::new();
+    for def in definitions {
+        match *def {
+            Definition::TypeDefinition(_) => {
+                defined.insert(definition_key(def).1);
+            }
+            Definition::TypeExtension(_) => extensions
+                .entry(definition_key(def).1)
+                .or_insert_with(Vec::new)
+                .push(def),
+            _ => {}
+        }
+    }
+    let mut items = Vec::with_capacity(definitions.len());
+    for def in definitions {
+        match *def {
+            // Extensions of types that aren't defined are kept in place
+            Definition::TypeExtension(_) => {
+                if !defined.contains(definition_key(def).1) {
+                    items.push(def);
+                }
+            }
+            Definition::TypeDefinition(_) => {
+                items.push(def);
+                if let Some(exts) = extensions.remove(definition_key(def).1) {
+                    items.extend(exts);
+                }
+            }
+            _ => items.push(def),
+        }
+    }
+    items
+}
+
+/// Kind of the definition, in the order definitions are sorted, and name
+fn definition_key<'x, 'a, T>(definition: &'x Definition<'a, T>) -> (u8, &'x str)
+where
+    T: Text<'a>,
+{
+    let (kind, name) = match *definition {
+        Definition::SchemaDefinition(_) => return (0, ""),
+        Definition::TypeDefinition(TypeDefinition::Scalar(ref t)) => (1, &t.name),
+        Definition::TypeExtension(TypeExtension::Scalar(ref t)) => (1, &t.name),
+        Definition::TypeDefinition(TypeDefinition::Object(ref t)) => (2, &t.name),
+        Definition::TypeExtension(TypeExtension::Object(ref t)) => (2, &t.name),
+        Definition::TypeDefinition(TypeDefinition::Interface(ref t)) => (3, &t.name),
+        Definition::TypeExtension(TypeExtension::Interface(ref t)) => (3, &t.name),
+        Definition::TypeDefinition(TypeDefinition::Union(ref t)) => (4, &t.name),
+        Definition::TypeExtension(TypeExtension::Union(ref t)) => (4, &t.name),
+        Definition::TypeDefinition(TypeDefinition::Enum(ref t)) => (5, &t.name),
+        Definition::TypeExtension(TypeExtension::Enum(ref t)) => (5, &t.name),
+        Definition::TypeDefinition(TypeDefinition::InputObject(ref t)) => (6, &t.name),
+        Definition::TypeExtension(TypeExtension::InputObject(ref t)) => (6, &t.name),
+        Definition::DirectiveDefinition(ref d) => (7, &d.name),
+    };
+    (kind, name.as_ref())
+}
+
 impl<'a, T> Displayable for Definition<'a, T>
 where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
+        if let Definition::TypeExtension(ref e) = *self {
+            // Extension that only adds directives is invalid without them
+            if !f.directives() && only_directives(e) {
+                return;
+            }
+        }
         f.margin();
         match *self {
             Definition::SchemaDefinition(ref s) => s.display(f),
@@ -58,6 +262,22 @@ where
     }
 }
 
+fn only_directives<'a, T>(extension: &TypeExtension<'a, T>) -> bool
+where
+    T: Text<'a>,
+{
+    match *extension {
+        TypeExtension::Scalar(_) => true,
+        TypeExtension::Object(ref o) => o.implements_interfaces.is_empty() && o.fields.is_empty(),
+        TypeExtension::Interface(ref i) => {
+            i.implements_interfaces.is_empty() && i.fields.is_empty()
+        }
+        TypeExtension::Union(ref u) => u.types.is_empty(),
+        TypeExtension::Enum(ref e) => e.values.is_empty(),
+        TypeExtension::InputObject(ref i) => i.fields.is_empty(),
+    }
+}
+
 impl<'a, T> Displayable for SchemaDefinition<'a, T>
 where
     T: Text<'a>,
@@ -133,6 +353,71 @@ where
     }
 }
 
+fn blank_line_before(end: Option<usize>, start: Pos, sorted: bool, f: &Formatter) -> bool {
+    match end {
+        Some(end) => !sorted && f.blank_line_between(end, start, false),
+        None => false,
+    }
+}
+
+/// Line where the item ends in the source, given it starts with an
+/// optional description at `position`
+///
+/// Only positions where nodes start are recorded, so the description is
+/// assumed to be followed by a line break.
+fn description_end<'a, T>(position: Pos, description: &Option<StringValue<'a, T>>) -> usize
+where
+    T: Text<'a>,
+{
+    match *description {
+        Some(ref descr) => {
+            // Block strings usually have quotes on lines of their own
+            let lines = match descr.raw() {
+                Some(raw) => raw.matches('\n').count(),
+                None if descr.is_block() => descr.matches('\n').count() + 2,
+                None => 0,
+            };
+            position.line + lines + 1
+        }
+        None => position.line,
+    }
+}
+
+fn directives_end<'a, T>(line: usize, directives: &[Directive<'a, T>]) -> usize
+where
+    T: Text<'a>,
+{
+    directives
+        .iter()
+        .map(|dir| dir.position.line)
+        .fold(line, usize::max)
+}
+
+fn input_value_end<'a, T>(value: &InputValue<'a, T>) -> usize
+where
+    T: Text<'a>,
+{
+    directives_end(
+        description_end(value.position, &value.description),
+        &value.directives,
+    )
+}
+
+/// Multiline list of arguments is assumed to be closed on its own line
+fn field_end<'a, T>(field: &Field<'a, T>) -> usize
+where
+    T: Text<'a>,
+{
+    let mut end = description_end(field.position, &field.description);
+    if let Some(arg) = field.arguments.last() {
+        let arguments_end = input_value_end(arg);
+        if arguments_end > end {
+            end = arguments_end + 1;
+        }
+    }
+    directives_end(end, &field.directives)
+}
+
 fn format_fields<'a, T>(fields: &[Field<'a, T>], f: &mut Formatter)
 where
     T: Text<'a>,
@@ -140,7 +425,12 @@ where
     if !fields.is_empty() {
         f.write(" ");
         f.start_block();
-        for fld in fields {
+        let mut end = None;
+        for fld in ordered(fields, f.sort_fields(), |fld| fld.name.as_ref()) {
+            if blank_line_before(end, fld.position, f.sort_fields(), f) {
+                f.endline();
+            }
+            end = Some(field_end(fld));
             fld.display(f);
         }
         f.end_block();
@@ -159,12 +449,7 @@ where
         f.write("type ");
         f.write(self.name.as_ref());
         if !self.implements_interfaces.is_empty() {
-            f.write(" implements ");
-            f.write(self.implements_interfaces[0].as_ref());
-            for name in &self.implements_interfaces[1..] {
-                f.write(" & ");
-                f.write(name.as_ref());
-            }
+            f.write_separated(" implements ", " & ", &self.implements_interfaces);
         }
         format_directives(&self.directives, f);
         format_fields(&self.fields, f);
@@ -180,12 +465,7 @@ where
         f.write("extend type ");
         f.write(self.name.as_ref());
         if !self.implements_interfaces.is_empty() {
-            f.write(" implements ");
-            f.write(self.implements_interfaces[0].as_ref());
-            for name in &self.implements_interfaces[1..] {
-                f.write(" & ");
-                f.write(name.as_ref());
-            }
+            f.write_separated(" implements ", " & ", &self.implements_interfaces);
         }
         format_directives(&self.directives, f);
         format_fields(&self.fields, f);
@@ -197,10 +477,7 @@ where
     T: Text<'a>,
 {
     fn display(&self, f: &mut Formatter) {
-        if let Some(ref descr) = self.description {
-            f.write_quoted(descr.as_ref());
-            f.write(" ");
-        }
+        inline_description(&self.description, f);
         f.write(self.name.as_ref());
         f.write(": ");
         self.value_type.display(f);
@@ -217,13 +494,22 @@ where
     T: Text<'a>,
 {
     if !arguments.is_empty() {
-        f.write("(");
-        arguments[0].display(f);
-        for arg in &arguments[1..] {
-            f.write(", ");
+        let sorted = f.sort_arguments();
+        let blank = arguments.windows(2).any(|pair| {
+            blank_line_before(Some(input_value_end(&pair[0])), pair[1].position, sorted, f)
+        });
+        f.start_sequence('(', blank);
+        let mut end = None;
+        for arg in ordered(arguments, sorted, |arg| arg.name.as_ref()) {
+            if blank_line_before(end, arg.position, sorted, f) {
+                f.sequence_item_after_blank();
+            } else {
+                f.sequence_item();
+            }
+            end = Some(input_value_end(arg));
             arg.display(f);
         }
-        f.write(")");
+        f.end_sequence(')');
     }
 }
 
@@ -253,12 +539,7 @@ where
         f.write("interface ");
         f.write(self.name.as_ref());
         if !self.implements_interfaces.is_empty() {
-            f.write(" implements ");
-            f.write(self.implements_interfaces[0].as_ref());
-            for name in &self.implements_interfaces[1..] {
-                f.write(" & ");
-                f.write(name.as_ref());
-            }
+            f.write_separated(" implements ", " & ", &self.implements_interfaces);
         }
         format_directives(&self.directives, f);
         format_fields(&self.fields, f);
@@ -274,12 +555,7 @@ where
         f.write("extend interface ");
         f.write(self.name.as_ref());
         if !self.implements_interfaces.is_empty() {
-            f.write(" implements ");
-            f.write(self.implements_interfaces[0].as_ref());
-            for name in &self.implements_interfaces[1..] {
-                f.write(" & ");
-                f.write(name.as_ref());
-            }
+            f.write_separated(" implements ", " & ", &self.implements_interfaces);
         }
         format_directives(&self.directives, f);
         format_fields(&self.fields, f);
@@ -339,23 +615,7 @@ where
         f.write("enum ");
         f.write(self.name.as_ref());
         format_directives(&self.directives, f);
-        if !self.values.is_empty() {
-            f.write(" ");
-            f.start_block();
-            for val in &self.values {
-                f.indent();
-                if let Some(ref descr) = val.description {
-                    f.write_quoted(descr.as_ref());
-                    f.write(" ");
-                }
-                f.write(val.name.as_ref());
-                format_directives(&val.directives, f);
-                f.endline();
-            }
-            f.end_block();
-        } else {
-            f.endline();
-        }
+        format_enum_values(&self.values, f);
     }
 }
 
@@ -368,23 +628,35 @@ where
         f.write("extend enum ");
         f.write(self.name.as_ref());
         format_directives(&self.directives, f);
-        if !self.values.is_empty() {
-            f.write(" ");
-            f.start_block();
-            for val in &self.values {
-                f.indent();
-                if let Some(ref descr) = val.description {
-                    f.write_quoted(descr.as_ref());
-                    f.write(" ");
-                }
-                f.write(val.name.as_ref());
-                format_directives(&val.directives, f);
+        format_enum_values(&self.values, f);
+    }
+}
+
+fn format_enum_values<'a, T>(values: &[EnumValue<'a, T>], f: &mut Formatter)
+where
+    T: Text<'a>,
+{
+    if !values.is_empty() {
+        f.write(" ");
+        f.start_block();
+        let mut end = None;
+        for val in ordered(values, f.sort_fields(), |val| val.name.as_ref()) {
+            if blank_line_before(end, val.position, f.sort_fields(), f) {
                 f.endline();
             }
-            f.end_block();
-        } else {
+            end = Some(directives_end(
+                description_end(val.position, &val.description),
+                &val.directives,
+            ));
+            f.indent();
+            inline_description(&val.description, f);
+            f.write(val.name.as_ref());
+            format_directives(&val.directives, f);
             f.endline();
         }
+        f.end_block();
+    } else {
+        f.endline();
     }
 }
 
@@ -395,7 +667,12 @@ where
     if !fields.is_empty() {
         f.write(" ");
         f.start_block();
-        for fld in fields {
+        let mut end = None;
+        for fld in ordered(fields, f.sort_fields(), |fld| fld.name.as_ref()) {
+            if blank_line_before(end, fld.position, f.sort_fields(), f) {
+                f.endline();
+            }
+            end = Some(input_value_end(fld));
             f.indent();
             fld.display(f);
             f.endline();
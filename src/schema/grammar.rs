@@ -1,689 +1,739 @@
+use std::iter::FusedIterator;
+use std::marker::PhantomData;
+
 use combine::easy::{Error, Errors};
 use combine::error::StreamError;
-use combine::sep_by1;
-use combine::{choice, eof, many, many1, optional, position};
-use combine::{parser, Parser, StdParseResult};
+use combine::Positioned;
+
+use crate::position::Pos;
 
-use crate::common::{default_value, directives, parse_type, string, Text};
-use crate::helpers::{ident, kind, name, punct};
+use crate::common::{default_value, directives, is_string, parse_type, string, Text};
+use crate::helpers::ParseResult;
 use crate::schema::ast::*;
 use crate::schema::error::ParseError;
-use crate::tokenizer::{Kind as T, Token, TokenStream};
+use crate::tokenizer::{Kind as T, TokenStream};
 
-pub fn schema<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<SchemaDefinition<'a, S>, TokenStream<'a>>
+pub fn schema<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<'a, SchemaDefinition<'a, S>>
 where
     S: Text<'a>,
 {
-    (
-        position().skip(ident("schema")),
-        parser(directives),
-        punct("{")
-            .with(many((kind(T::Name).skip(punct(":")), name::<'a, S>())))
-            .skip(punct("}")),
-    )
-        .flat_map(
-            |(position, directives, operations): (_, _, Vec<(Token, _)>)| {
-                let mut query = None;
-                let mut mutation = None;
-                let mut subscription = None;
-                let mut err = Errors::empty(position);
-                for (oper, type_name) in operations {
-                    match oper.value {
-                        "query" if query.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `query` operation",
-                            ));
-                        }
-                        "query" => {
-                            query = Some(type_name);
-                        }
-                        "mutation" if mutation.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `mutation` operation",
-                            ));
-                        }
-                        "mutation" => {
-                            mutation = Some(type_name);
-                        }
-                        "subscription" if subscription.is_some() => {
-                            err.add_error(Error::unexpected_static_message(
-                                "duplicate `subscription` operation",
-                            ));
-                        }
-                        "subscription" => {
-                            subscription = Some(type_name);
-                        }
-                        _ => {
-                            err.add_error(Error::unexpected_token(oper));
-                            err.add_error(Error::expected_static_message("query"));
-                            err.add_error(Error::expected_static_message("mutation"));
-                            err.add_error(Error::expected_static_message("subscription"));
-                        }
-                    }
-                }
-                if !err.errors.is_empty() {
-                    return Err(err);
-                }
-                Ok(SchemaDefinition {
-                    position,
-                    directives,
-                    query,
-                    mutation,
-                    subscription,
-                })
-            },
-        )
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn scalar_type<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<ScalarType<'a, T>, TokenStream<'a>>
+    let position = input.position();
+    input.ident("schema")?;
+    let directives = directives(input)?;
+    input.punct("{")?;
+    let mut operations = Vec::new();
+    while input.is_name() {
+        let oper = input.bump();
+        input.punct(":")?;
+        operations.push((oper, input.name::<S>()?));
+    }
+    input.punct_or("}", &["Name", ":", "}"])?;
+
+    let mut query = None;
+    let mut mutation = None;
+    let mut subscription = None;
+    let mut err = Errors::empty(position);
+    for (oper, type_name) in operations {
+        match oper.value {
+            "query" if query.is_some() => {
+                err.add_error(Error::unexpected_static_message(
+                    "duplicate `query` operation",
+                ));
+            }
+            "query" => {
+                query = Some(type_name);
+            }
+            "mutation" if mutation.is_some() => {
+                err.add_error(Error::unexpected_static_message(
+                    "duplicate `mutation` operation",
+                ));
+            }
+            "mutation" => {
+                mutation = Some(type_name);
+            }
+            "subscription" if subscription.is_some() => {
+                err.add_error(Error::unexpected_static_message(
+                    "duplicate `subscription` operation",
+                ));
+            }
+            "subscription" => {
+                subscription = Some(type_name);
+            }
+            _ => {
+                err.add_error(Error::unexpected_token(oper));
+                err.add_error(Error::expected_static_message("query"));
+                err.add_error(Error::expected_static_message("mutation"));
+                err.add_error(Error::expected_static_message("subscription"));
+            }
+        }
+    }
+    if !err.errors.is_empty() {
+        return Err(err);
+    }
+    Ok(SchemaDefinition {
+        position,
+        directives,
+        query,
+        mutation,
+        subscription,
+    })
+}
+
+pub fn scalar_type<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, ScalarType<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("scalar").with(name::<'a, T>()),
-        parser(directives),
-    )
-        .map(|(position, name, directives)| ScalarType {
-            position,
-            description: None,
-            name,
-            directives,
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("scalar")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    Ok(ScalarType {
+        position,
+        description: None, // is filled in described_definition
+        name,
+        directives,
+    })
+}
+
+/// Builds an error for type extension without any content
+fn empty_extension<'a>(position: Pos, message: &'static str) -> ParseResult<'a, ()> {
+    let mut e = Errors::empty(position);
+    e.add_error(Error::expected_static_message(message));
+    Err(e)
 }
 
 pub fn scalar_type_extension<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<ScalarTypeExtension<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, ScalarTypeExtension<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("scalar").with(name::<'a, T>()),
-        parser(directives),
-    )
-        .flat_map(|(position, name, directives)| {
-            if directives.is_empty() {
-                let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Scalar type extension should contain at least \
-                 one directive.",
-                ));
-                return Err(e);
-            }
-            Ok(ScalarTypeExtension {
-                position,
-                name,
-                directives,
-            })
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("scalar")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    if directives.is_empty() {
+        empty_extension(
+            position,
+            "Scalar type extension should contain at least \
+             one directive.",
+        )?;
+    }
+    Ok(ScalarTypeExtension {
+        position,
+        name,
+        directives,
+    })
 }
 
-pub fn implements_interfaces<'a, X>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<X::Value>, TokenStream<'a>>
+pub fn implements_interfaces<'a, X>(input: &mut TokenStream<'a>) -> ParseResult<'a, Vec<X::Value>>
 where
     X: Text<'a>,
 {
-    optional(
-        ident("implements")
-            .skip(optional(punct("&")))
-            .with(sep_by1(name::<'a, X>(), punct("&"))),
-    )
-    .map(|opt| opt.unwrap_or_default())
-    .parse_stream(input)
-    .into_result()
+    let mut interfaces = Vec::new();
+    if input.eat_ident("implements") {
+        input.eat_punct("&");
+        interfaces.push(input.name::<X>()?);
+        while input.eat_punct("&") {
+            interfaces.push(input.name::<X>()?);
+        }
+    }
+    Ok(interfaces)
+}
+
+/// Parses optional description
+fn description<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Option<StringValue<'a, T>>>
+where
+    T: Text<'a>,
+{
+    if is_string(input) {
+        string(input).map(Some)
+    } else {
+        Ok(None)
+    }
 }
 
-pub fn input_value<'a, X>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<InputValue<'a, X>, TokenStream<'a>>
+pub fn input_value<'a, X>(input: &mut TokenStream<'a>) -> ParseResult<'a, InputValue<'a, X>>
 where
     X: Text<'a>,
 {
-    (
-        position(),
-        optional(parser(string)),
-        name::<'a, X>(),
-        punct(":").with(parser(parse_type)),
-        optional(punct("=").with(parser(default_value))),
-        parser(directives),
-    )
-        .map(
-            |(position, description, name, value_type, default_value, directives)| InputValue {
-                position,
-                description,
-                name,
-                value_type,
-                default_value,
-                directives,
-            },
-        )
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    let description = description(input)?;
+    let name = item_name::<X>(input, &description)?;
+    input.punct(":")?;
+    let value_type = parse_type(input)?;
+    let default_value = if input.eat_punct("=") {
+        Some(default_value(input)?)
+    } else {
+        None
+    };
+    let directives = directives(input)?;
+    Ok(InputValue {
+        position,
+        description,
+        name,
+        value_type,
+        default_value,
+        directives,
+    })
+}
+
+/// Parses the name of an item following its optional description
+fn item_name<'a, T>(
+    input: &mut TokenStream<'a>,
+    description: &Option<StringValue<'a, T>>,
+) -> ParseResult<'a, T::Value>
+where
+    T: Text<'a>,
+{
+    if description.is_none() && !input.is_name() {
+        return Err(input.unexpected_choice(&["Name"]));
+    }
+    input.name::<T>()
+}
+
+/// Returns `true` if the next token can start a definition of a field,
+/// an input value or an enum value
+fn is_item_start(input: &mut TokenStream) -> bool {
+    input.is_name() || is_string(input)
+}
+
+/// Parses a list of input values enclosed in `open` and `close`
+fn input_values<'a, T>(
+    input: &mut TokenStream<'a>,
+    open: &'static str,
+    close: &'static str,
+) -> ParseResult<'a, Vec<InputValue<'a, T>>>
+where
+    T: Text<'a>,
+{
+    let mut values = Vec::new();
+    if input.eat_punct(open) {
+        values.push(input_value(input)?);
+        while is_item_start(input) {
+            values.push(input_value(input)?);
+        }
+        input.punct(close)?;
+    }
+    Ok(values)
 }
 
 pub fn arguments_definition<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<InputValue<'a, T>>, TokenStream<'a>>
+) -> ParseResult<'a, Vec<InputValue<'a, T>>>
 where
     T: Text<'a>,
 {
-    optional(punct("(").with(many1(parser(input_value))).skip(punct(")")))
-        .map(|v| v.unwrap_or_default())
-        .parse_stream(input)
-        .into_result()
+    input_values(input, "(", ")")
 }
 
-pub fn field<'a, S>(input: &mut TokenStream<'a>) -> StdParseResult<Field<'a, S>, TokenStream<'a>>
+pub fn field<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<'a, Field<'a, S>>
 where
     S: Text<'a>,
 {
-    (
-        position(),
-        optional(parser(string)),
-        name::<'a, S>(),
-        parser(arguments_definition),
-        punct(":").with(parser(parse_type)),
-        parser(directives),
-    )
-        .map(
-            |(position, description, name, arguments, field_type, directives)| Field {
-                position,
-                description,
-                name,
-                arguments,
-                field_type,
-                directives,
-            },
-        )
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn fields<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<Field<'a, S>>, TokenStream<'a>>
+    let position = input.position();
+    let description = description(input)?;
+    let name = item_name::<S>(input, &description)?;
+    let arguments = arguments_definition(input)?;
+    input.punct(":")?;
+    let field_type = parse_type(input)?;
+    let directives = directives(input)?;
+    Ok(Field {
+        position,
+        description,
+        name,
+        arguments,
+        field_type,
+        directives,
+    })
+}
+
+pub fn fields<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<'a, Vec<Field<'a, S>>>
 where
     S: Text<'a>,
 {
-    optional(punct("{").with(many1(parser(field))).skip(punct("}")))
-        .map(|v| v.unwrap_or_default())
-        .parse_stream(input)
-        .into_result()
+    let mut fields = Vec::new();
+    if input.eat_punct("{") {
+        fields.push(field(input)?);
+        while is_item_start(input) {
+            fields.push(field(input)?);
+        }
+        input.punct("}")?;
+    }
+    Ok(fields)
 }
 
-pub fn object_type<'a, S>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<ObjectType<'a, S>, TokenStream<'a>>
+pub fn object_type<'a, S>(input: &mut TokenStream<'a>) -> ParseResult<'a, ObjectType<'a, S>>
 where
     S: Text<'a>,
 {
-    (
-        position(),
-        ident("type").with(name::<'a, S>()),
-        parser(implements_interfaces::<S>),
-        parser(directives),
-        parser(fields),
-    )
-        .map(|(position, name, interfaces, directives, fields)| {
-            ObjectType {
-                position,
-                name,
-                directives,
-                fields,
-                implements_interfaces: interfaces,
-                description: None, // is filled in described_definition
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("type")?;
+    let name = input.name::<S>()?;
+    let implements_interfaces = implements_interfaces::<S>(input)?;
+    let directives = directives(input)?;
+    let fields = fields(input)?;
+    Ok(ObjectType {
+        position,
+        name,
+        directives,
+        fields,
+        implements_interfaces,
+        description: None, // is filled in described_definition
+    })
 }
 
 pub fn object_type_extension<'a, S>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<ObjectTypeExtension<'a, S>, TokenStream<'a>>
+) -> ParseResult<'a, ObjectTypeExtension<'a, S>>
 where
     S: Text<'a>,
 {
-    (
-        position(),
-        ident("type").with(name::<'a, S>()),
-        parser(implements_interfaces::<S>),
-        parser(directives),
-        parser(fields),
-    )
-        .flat_map(|(position, name, interfaces, directives, fields)| {
-            if interfaces.is_empty() && directives.is_empty() && fields.is_empty() {
-                let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Object type extension should contain at least \
-                     one interface, directive or field.",
-                ));
-                return Err(e);
-            }
-            Ok(ObjectTypeExtension {
-                position,
-                name,
-                directives,
-                fields,
-                implements_interfaces: interfaces,
-            })
-        })
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn interface_type<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<InterfaceType<'a, T>, TokenStream<'a>>
+    let position = input.position();
+    input.ident("type")?;
+    let name = input.name::<S>()?;
+    let implements_interfaces = implements_interfaces::<S>(input)?;
+    let directives = directives(input)?;
+    let fields = fields(input)?;
+    if implements_interfaces.is_empty() && directives.is_empty() && fields.is_empty() {
+        empty_extension(
+            position,
+            "Object type extension should contain at least \
+             one interface, directive or field.",
+        )?;
+    }
+    Ok(ObjectTypeExtension {
+        position,
+        name,
+        directives,
+        fields,
+        implements_interfaces,
+    })
+}
+
+pub fn interface_type<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, InterfaceType<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("interface").with(name::<'a, T>()),
-        parser(implements_interfaces::<T>),
-        parser(directives),
-        parser(fields),
-    )
-        .map(|(position, name, interfaces, directives, fields)| {
-            InterfaceType {
-                position,
-                name,
-                implements_interfaces: interfaces,
-                directives,
-                fields,
-                description: None, // is filled in described_definition
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("interface")?;
+    let name = input.name::<T>()?;
+    let implements_interfaces = implements_interfaces::<T>(input)?;
+    let directives = directives(input)?;
+    let fields = fields(input)?;
+    Ok(InterfaceType {
+        position,
+        name,
+        implements_interfaces,
+        directives,
+        fields,
+        description: None, // is filled in described_definition
+    })
 }
 
 pub fn interface_type_extension<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<InterfaceTypeExtension<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, InterfaceTypeExtension<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("interface").with(name::<'a, T>()),
-        parser(implements_interfaces::<T>),
-        parser(directives),
-        parser(fields),
-    )
-        .flat_map(|(position, name, interfaces, directives, fields)| {
-            if directives.is_empty() && fields.is_empty() {
-                let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Interface type extension should contain at least \
-                     one directive or field.",
-                ));
-                return Err(e);
-            }
-            Ok(InterfaceTypeExtension {
-                position,
-                name,
-                implements_interfaces: interfaces,
-                directives,
-                fields,
-            })
-        })
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn union_members<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<T::Value>, TokenStream<'a>>
+    let position = input.position();
+    input.ident("interface")?;
+    let name = input.name::<T>()?;
+    let implements_interfaces = implements_interfaces::<T>(input)?;
+    let directives = directives(input)?;
+    let fields = fields(input)?;
+    if directives.is_empty() && fields.is_empty() {
+        empty_extension(
+            position,
+            "Interface type extension should contain at least \
+             one directive or field.",
+        )?;
+    }
+    Ok(InterfaceTypeExtension {
+        position,
+        name,
+        implements_interfaces,
+        directives,
+        fields,
+    })
+}
+
+pub fn union_members<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Vec<T::Value>>
 where
     T: Text<'a>,
 {
-    optional(punct("|"))
-        .with(sep_by1(name::<'a, T>(), punct("|")))
-        .parse_stream(input)
-        .into_result()
+    let mut members = Vec::new();
+    if input.eat_punct("|") {
+        members.push(input.name::<T>()?);
+    } else {
+        if !input.is_name() {
+            return Err(input.unexpected_choice(&["|", "Name"]));
+        }
+        members.push(input.name::<T>()?);
+    }
+    while input.eat_punct("|") {
+        members.push(input.name::<T>()?);
+    }
+    Ok(members)
 }
 
-pub fn union_type<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<UnionType<'a, T>, TokenStream<'a>>
+pub fn union_type<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, UnionType<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("union").with(name::<'a, T>()),
-        parser(directives),
-        optional(punct("=").with(parser(union_members::<T>))),
-    )
-        .map(|(position, name, directives, types)| {
-            UnionType {
-                position,
-                name,
-                directives,
-                types: types.unwrap_or_else(Vec::new),
-                description: None, // is filled in described_definition
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("union")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    let types = if input.eat_punct("=") {
+        union_members::<T>(input)?
+    } else {
+        Vec::new()
+    };
+    Ok(UnionType {
+        position,
+        name,
+        directives,
+        types,
+        description: None, // is filled in described_definition
+    })
 }
 
 pub fn union_type_extension<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<UnionTypeExtension<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, UnionTypeExtension<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("union").with(name::<'a, T>()),
-        parser(directives),
-        optional(punct("=").with(parser(union_members::<T>))),
-    )
-        .flat_map(|(position, name, directives, types)| {
-            if directives.is_empty() && types.is_none() {
-                let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Union type extension should contain at least \
-                 one directive or type.",
-                ));
-                return Err(e);
-            }
-            Ok(UnionTypeExtension {
-                position,
-                name,
-                directives,
-                types: types.unwrap_or_else(Vec::new),
-            })
-        })
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn enum_values<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<EnumValue<'a, T>>, TokenStream<'a>>
+    let position = input.position();
+    input.ident("union")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    let types = if input.eat_punct("=") {
+        Some(union_members::<T>(input)?)
+    } else {
+        None
+    };
+    if directives.is_empty() && types.is_none() {
+        empty_extension(
+            position,
+            "Union type extension should contain at least \
+             one directive or type.",
+        )?;
+    }
+    Ok(UnionTypeExtension {
+        position,
+        name,
+        directives,
+        types: types.unwrap_or_default(),
+    })
+}
+
+pub fn enum_values<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Vec<EnumValue<'a, T>>>
 where
     T: Text<'a>,
 {
-    punct("{")
-        .with(many1(
-            (
-                position(),
-                optional(parser(string)),
-                name::<'a, T>(),
-                parser(directives),
-            )
-                .map(|(position, description, name, directives)| EnumValue {
-                    position,
-                    description,
-                    name,
-                    directives,
-                }),
-        ))
-        .skip(punct("}"))
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn enum_type<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<EnumType<'a, T>, TokenStream<'a>>
+    input.punct("{")?;
+    let mut values = Vec::new();
+    loop {
+        let position = input.position();
+        let description = description(input)?;
+        let name = input.name::<T>()?;
+        let directives = directives(input)?;
+        values.push(EnumValue {
+            position,
+            description,
+            name,
+            directives,
+        });
+        if !is_item_start(input) {
+            break;
+        }
+    }
+    input.punct_or("}", &["Name", "}"])?;
+    Ok(values)
+}
+
+pub fn enum_type<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, EnumType<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("enum").with(name::<'a, T>()),
-        parser(directives),
-        optional(parser(enum_values)),
-    )
-        .map(|(position, name, directives, values)| {
-            EnumType {
-                position,
-                name,
-                directives,
-                values: values.unwrap_or_else(Vec::new),
-                description: None, // is filled in described_definition
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("enum")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    let values = if input.is_punct("{") {
+        enum_values(input)?
+    } else {
+        Vec::new()
+    };
+    Ok(EnumType {
+        position,
+        name,
+        directives,
+        values,
+        description: None, // is filled in described_definition
+    })
 }
 
 pub fn enum_type_extension<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<EnumTypeExtension<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, EnumTypeExtension<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("enum").with(name::<'a, T>()),
-        parser(directives),
-        optional(parser(enum_values)),
-    )
-        .flat_map(|(position, name, directives, values)| {
-            if directives.is_empty() && values.is_none() {
-                let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Enum type extension should contain at least \
-                 one directive or value.",
-                ));
-                return Err(e);
-            }
-            Ok(EnumTypeExtension {
-                position,
-                name,
-                directives,
-                values: values.unwrap_or_else(Vec::new),
-            })
-        })
-        .parse_stream(input)
-        .into_result()
-}
-
-pub fn input_fields<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<InputValue<'a, T>>, TokenStream<'a>>
+    let position = input.position();
+    input.ident("enum")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    let values = if input.is_punct("{") {
+        Some(enum_values(input)?)
+    } else {
+        None
+    };
+    if directives.is_empty() && values.is_none() {
+        empty_extension(
+            position,
+            "Enum type extension should contain at least \
+             one directive or value.",
+        )?;
+    }
+    Ok(EnumTypeExtension {
+        position,
+        name,
+        directives,
+        values: values.unwrap_or_default(),
+    })
+}
+
+pub fn input_fields<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Vec<InputValue<'a, T>>>
 where
     T: Text<'a>,
 {
-    optional(punct("{").with(many1(parser(input_value))).skip(punct("}")))
-        .map(|v| v.unwrap_or_default())
-        .parse_stream(input)
-        .into_result()
+    input_values(input, "{", "}")
 }
 
 pub fn input_object_type<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<InputObjectType<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, InputObjectType<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("input").with(name::<'a, T>()),
-        parser(directives),
-        parser(input_fields),
-    )
-        .map(|(position, name, directives, fields)| {
-            InputObjectType {
-                position,
-                name,
-                directives,
-                fields,
-                description: None, // is filled in described_definition
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("input")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    let fields = input_fields(input)?;
+    Ok(InputObjectType {
+        position,
+        name,
+        directives,
+        fields,
+        description: None, // is filled in described_definition
+    })
 }
 
 pub fn input_object_type_extension<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<InputObjectTypeExtension<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, InputObjectTypeExtension<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("input").with(name::<'a, T>()),
-        parser(directives),
-        parser(input_fields),
-    )
-        .flat_map(|(position, name, directives, fields)| {
-            if directives.is_empty() && fields.is_empty() {
-                let mut e = Errors::empty(position);
-                e.add_error(Error::expected_static_message(
-                    "Input object type extension should contain at least \
-                     one directive or field.",
-                ));
-                return Err(e);
-            }
-            Ok(InputObjectTypeExtension {
-                position,
-                name,
-                directives,
-                fields,
-            })
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("input")?;
+    let name = input.name::<T>()?;
+    let directives = directives(input)?;
+    let fields = input_fields(input)?;
+    if directives.is_empty() && fields.is_empty() {
+        empty_extension(
+            position,
+            "Input object type extension should contain at least \
+             one directive or field.",
+        )?;
+    }
+    Ok(InputObjectTypeExtension {
+        position,
+        name,
+        directives,
+        fields,
+    })
+}
+
+fn directive_location<'a>(input: &mut TokenStream<'a>) -> ParseResult<'a, DirectiveLocation> {
+    let position = input.position();
+    let token = input.kind(T::Name, "Name")?;
+    token
+        .value
+        .parse::<DirectiveLocation>()
+        .map_err(|e| input.error_at(position, Error::Other(e.into())))
 }
 
 pub fn directive_locations<'a>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<Vec<DirectiveLocation>, TokenStream<'a>> {
-    optional(optional(punct("|")).with(sep_by1(
-        kind(T::Name).and_then(|tok| tok.value.parse::<DirectiveLocation>()),
-        punct("|"),
-    )))
-    .map(|opt| opt.unwrap_or_default())
-    .parse_stream(input)
-    .into_result()
+) -> ParseResult<'a, Vec<DirectiveLocation>> {
+    let mut locations = Vec::new();
+    if input.eat_punct("|") || input.is_name() {
+        locations.push(directive_location(input)?);
+        while input.eat_punct("|") {
+            locations.push(directive_location(input)?);
+        }
+    }
+    Ok(locations)
 }
 
 pub fn directive_definition<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<DirectiveDefinition<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, DirectiveDefinition<'a, T>>
 where
     T: Text<'a>,
 {
-    (
-        position(),
-        ident("directive").and(punct("@")).with(name::<'a, T>()),
-        parser(arguments_definition),
-        optional(ident("repeatable")),
-        ident("on").with(parser(directive_locations)),
-    )
-        .map(|(position, name, arguments, repeatable, locations)| {
-            DirectiveDefinition {
-                position,
-                name,
-                arguments,
-                locations,
-                repeatable: repeatable.is_some(),
-                description: None, // is filled in described_definition
-            }
-        })
-        .parse_stream(input)
-        .into_result()
+    let position = input.position();
+    input.ident("directive")?;
+    input.punct("@")?;
+    let name = input.name::<T>()?;
+    let arguments = arguments_definition(input)?;
+    let repeatable = input.eat_ident("repeatable");
+    if !input.is_ident("on") {
+        let expected: &[_] = if repeatable {
+            &["on"]
+        } else {
+            &["repeatable", "on"]
+        };
+        return Err(input.unexpected(expected));
+    }
+    input.bump();
+    let locations = directive_locations(input)?;
+    Ok(DirectiveDefinition {
+        position,
+        name,
+        arguments,
+        locations,
+        repeatable,
+        description: None, // is filled in described_definition
+    })
 }
 
 pub fn described_definition<'a, T>(
     input: &mut TokenStream<'a>,
-) -> StdParseResult<Definition<'a, T>, TokenStream<'a>>
+) -> ParseResult<'a, Definition<'a, T>>
 where
     T: Text<'a>,
 {
     use self::TypeDefinition::*;
-    (
-        optional(parser(string)),
-        choice((
-            choice((
-                parser(scalar_type).map(Scalar),
-                parser(object_type).map(Object),
-                parser(interface_type).map(Interface),
-                parser(union_type).map(Union),
-                parser(enum_type).map(Enum),
-                parser(input_object_type).map(InputObject),
-            ))
-            .map(Definition::TypeDefinition),
-            parser(directive_definition).map(Definition::DirectiveDefinition),
-        )),
-    )
-        // We can't set description inside type definition parser, because
-        // that means parser will need to backtrace, and that in turn
-        // means that error reporting is bad (along with performance)
-        .map(|(descr, mut def)| {
-            use crate::schema::ast::Definition::TypeDefinition as T;
-            use crate::schema::ast::Definition::*;
-            use crate::schema::ast::TypeDefinition::*;
-            match def {
-                T(Scalar(ref mut s)) => s.description = descr,
-                T(Object(ref mut o)) => o.description = descr,
-                T(Interface(ref mut i)) => i.description = descr,
-                T(Union(ref mut u)) => u.description = descr,
-                T(Enum(ref mut e)) => e.description = descr,
-                T(InputObject(ref mut o)) => o.description = descr,
-                DirectiveDefinition(ref mut d) => d.description = descr,
-                SchemaDefinition(_) => unreachable!(),
-                TypeExtension(_) => unreachable!(),
-            }
-            def
-        })
-        .parse_stream(input)
-        .into_result()
+    let descr = description(input)?;
+    // We can't set description inside type definition parser, because
+    // the description is parsed before we know which definition it is
+    let def = if input.is_ident("scalar") {
+        let mut s = scalar_type(input)?;
+        s.description = descr;
+        Definition::TypeDefinition(Scalar(s))
+    } else if input.is_ident("type") {
+        let mut o = object_type(input)?;
+        o.description = descr;
+        Definition::TypeDefinition(Object(o))
+    } else if input.is_ident("interface") {
+        let mut i = interface_type(input)?;
+        i.description = descr;
+        Definition::TypeDefinition(Interface(i))
+    } else if input.is_ident("union") {
+        let mut u = union_type(input)?;
+        u.description = descr;
+        Definition::TypeDefinition(Union(u))
+    } else if input.is_ident("enum") {
+        let mut e = enum_type(input)?;
+        e.description = descr;
+        Definition::TypeDefinition(Enum(e))
+    } else if input.is_ident("input") {
+        let mut i = input_object_type(input)?;
+        i.description = descr;
+        Definition::TypeDefinition(InputObject(i))
+    } else if input.is_ident("directive") {
+        let mut d = directive_definition(input)?;
+        d.description = descr;
+        Definition::DirectiveDefinition(d)
+    } else if descr.is_some() {
+        return Err(input.unexpected_choice(&[
+            "scalar",
+            "type",
+            "interface",
+            "union",
+            "enum",
+            "input",
+            "directive",
+        ]));
+    } else {
+        return Err(input.unexpected_choice(&[
+            "schema",
+            "extend",
+            "scalar",
+            "type",
+            "interface",
+            "union",
+            "enum",
+            "input",
+            "directive",
+        ]));
+    };
+    Ok(def)
 }
 
-pub fn type_extension<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<TypeExtension<'a, T>, TokenStream<'a>>
+pub fn type_extension<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, TypeExtension<'a, T>>
 where
     T: Text<'a>,
 {
-    ident("extend")
-        .with(choice((
-            parser(scalar_type_extension).map(TypeExtension::Scalar),
-            parser(object_type_extension).map(TypeExtension::Object),
-            parser(interface_type_extension).map(TypeExtension::Interface),
-            parser(union_type_extension).map(TypeExtension::Union),
-            parser(enum_type_extension).map(TypeExtension::Enum),
-            parser(input_object_type_extension).map(TypeExtension::InputObject),
-        )))
-        .parse_stream(input)
-        .into_result()
+    input.ident("extend")?;
+    if input.is_ident("scalar") {
+        scalar_type_extension(input).map(TypeExtension::Scalar)
+    } else if input.is_ident("type") {
+        object_type_extension(input).map(TypeExtension::Object)
+    } else if input.is_ident("interface") {
+        interface_type_extension(input).map(TypeExtension::Interface)
+    } else if input.is_ident("union") {
+        union_type_extension(input).map(TypeExtension::Union)
+    } else if input.is_ident("enum") {
+        enum_type_extension(input).map(TypeExtension::Enum)
+    } else if input.is_ident("input") {
+        input_object_type_extension(input).map(TypeExtension::InputObject)
+    } else {
+        Err(input.unexpected_choice(&["scalar", "type", "interface", "union", "enum", "input"]))
+    }
 }
 
-pub fn definition<'a, T>(
-    input: &mut TokenStream<'a>,
-) -> StdParseResult<Definition<'a, T>, TokenStream<'a>>
+/// Returns `true` if the next token starts a type system definition
+pub fn is_definition_start(input: &mut TokenStream) -> bool {
+    match input.peek() {
+        Ok(Some(token)) => match token.kind {
+            T::Name => matches!(
+                token.value,
+                "schema"
+                    | "extend"
+                    | "scalar"
+                    | "type"
+                    | "interface"
+                    | "union"
+                    | "enum"
+                    | "input"
+                    | "directive"
+            ),
+            T::StringValue | T::BlockString => true,
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+pub fn definition<'a, T>(input: &mut TokenStream<'a>) -> ParseResult<'a, Definition<'a, T>>
 where
     T: Text<'a>,
 {
-    choice((
-        parser(schema).map(Definition::SchemaDefinition),
-        parser(type_extension).map(Definition::TypeExtension),
-        parser(described_definition),
-    ))
-    .parse_stream(input)
-    .into_result()
+    if input.is_ident("schema") {
+        schema(input).map(Definition::SchemaDefinition)
+    } else if input.is_ident("extend") {
+        type_extension(input).map(Definition::TypeExtension)
+    } else {
+        described_definition(input)
+    }
 }
 
 /// Parses a piece of schema language and returns an AST
@@ -692,14 +742,83 @@ where
     T: Text<'a>,
 {
     let mut tokens = TokenStream::new(s);
-    let (doc, _) = many1(parser(definition))
-        .map(|d| Document { definitions: d })
-        .skip(eof())
-        .parse_stream(&mut tokens)
-        .into_result()
-        .map_err(|e| e.into_inner().error)?;
+    let mut definitions = vec![definition(&mut tokens)?];
+    while is_definition_start(&mut tokens) {
+        definitions.push(definition(&mut tokens)?);
+    }
+    tokens.eof()?;
+    Ok(Document { definitions })
+}
+
+/// Parses a single type system definition and returns an AST as well as
+/// the remainder of the input which is unparsed
+pub fn consume_definition<'a, T>(s: &'a str) -> Result<(Definition<'a, T>, &'a str), ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let def = definition(&mut tokens)?;
+
+    Ok((def, &s[tokens.offset()..]))
+}
+
+/// Returns an iterator parsing definitions one at a time
+///
+/// Unlike `parse_schema` it doesn't keep the whole document in memory.
+/// An error ends the iteration, definitions before it are returned as is.
+pub fn parse_definitions<'a, T>(s: &'a str) -> Definitions<'a, T>
+where
+    T: Text<'a>,
+{
+    Definitions {
+        tokens: TokenStream::new(s),
+        first: true,
+        done: false,
+        marker: PhantomData,
+    }
+}
+
+/// Iterator over type system definitions, created by `parse_definitions`
+#[derive(Debug)]
+pub struct Definitions<'a, T: Text<'a>> {
+    tokens: TokenStream<'a>,
+    first: bool,
+    done: bool,
+    marker: PhantomData<T>,
+}
 
-    Ok(doc)
+impl<'a, T: Text<'a>> Iterator for Definitions<'a, T> {
+    type Item = Result<Definition<'a, T>, ParseError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        // Document must have at least one definition
+        if !self.first && !is_definition_start(&mut self.tokens) {
+            self.done = true;
+            return self.tokens.eof().err().map(|e| Err(e.into()));
+        }
+        self.first = false;
+        let result = definition(&mut self.tokens);
+        self.done = result.is_err();
+        Some(result.map_err(Into::into))
+    }
+}
+
+impl<'a, T: Text<'a>> FusedIterator for Definitions<'a, T> {}
+
+/// Parses a single field definition, like `user(id: ID!): User @auth`
+///
+/// The field may have a description.
+pub fn parse_field_definition<'a, T>(s: &'a str) -> Result<Field<'a, T>, ParseError>
+where
+    T: Text<'a>,
+{
+    let mut tokens = TokenStream::new(s);
+    let definition = field(&mut tokens)?;
+    tokens.eof()?;
+    Ok(definition)
 }
 
 #[cfg(test)]
@@ -708,7 +827,7 @@ mod test {
     use crate::position::Pos;
     use crate::schema::grammar::*;
 
-    fn ast(s: &str) -> Document<String> {
+    fn ast(s: &str) -> Document<'_, String> {
         parse_schema::<String>(s).unwrap().to_owned()
     }
 
@@ -727,4 +846,64 @@ mod test {
             }
         );
     }
+
+    #[test]
+    fn borrowed_description() {
+        let source = "\"\"\"\n  Some type\n\"\"\"\ntype A { \"field\" x: Int }";
+        let doc = parse_schema::<&str>(source).unwrap();
+        let obj = match doc.definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Object(ref o)) => o,
+            _ => unreachable!(),
+        };
+        let descr = obj.description.as_ref().unwrap();
+        assert_eq!(descr, "Some type");
+        assert!(descr.is_block());
+        assert!(descr.is_borrowed());
+        assert_eq!(descr.raw(), Some("\"\"\"\n  Some type\n\"\"\""));
+        let descr = obj.fields[0].description.as_ref().unwrap();
+        assert_eq!(descr, "field");
+        assert!(!descr.is_block());
+    }
+
+    #[test]
+    fn field_definition() {
+        use super::parse_field_definition;
+
+        let field = parse_field_definition::<String>("\"User\" user(id: ID!): User @auth").unwrap();
+        assert_eq!(field.name, "user");
+        assert_eq!(field.description.as_ref().unwrap(), "User");
+        assert_eq!(field.arguments[0].name, "id");
+        assert_eq!(field.directives[0].name, "auth");
+        assert_eq!(field.to_string(), "\"User\"\nuser(id: ID!): User @auth\n");
+        assert!(parse_field_definition::<String>("a: Int b: Int").is_err());
+    }
+
+    #[test]
+    fn consume() {
+        use super::consume_definition;
+
+        let (def, remainder) =
+            consume_definition::<String>("type A { a: Int } query { a }").unwrap();
+        assert!(matches!(def, Definition::TypeDefinition(_)));
+        assert_eq!(remainder, "query { a }");
+    }
+
+    #[test]
+    fn definitions_iterator() {
+        use super::parse_definitions;
+
+        let source = "scalar A\n\"B\" type B { b: A }\nextend scalar A @x";
+        let defs = parse_definitions::<String>(source)
+            .collect::<Result<Vec<_>, _>>()
+            .unwrap();
+        assert_eq!(defs, parse_schema::<String>(source).unwrap().definitions);
+
+        let results: Vec<_> = parse_definitions::<String>("scalar A\ntype B {").collect();
+        assert_eq!(results.len(), 2);
+        assert!(results[0].is_ok());
+        assert_eq!(
+            results[1].as_ref().unwrap_err().position(),
+            Pos { line: 2, column: 9 }
+        );
+    }
 }
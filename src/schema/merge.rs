@@ -0,0 +1,575 @@
+//! Merging of schemas split into multiple documents
+use std::collections::HashMap;
+use std::fmt;
+
+use thiserror::Error;
+
+use crate::common::Text;
+use crate::position::Pos;
+use crate::schema::ast::*;
+use crate::semantic::{FieldOrder, SemanticEq};
+
+/// Conflicting definitions found when merging schemas
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Conflict {
+    /// Name of the conflicting element, like `Type`, `Type.field`,
+    /// `@directive` or `schema.query`
+    pub name: String,
+    /// Human readable description of the conflict
+    pub message: String,
+    /// Index of the document where the element is defined first
+    pub first_document: usize,
+    /// Position of the first definition
+    pub first_position: Pos,
+    /// Index of the document where the element is defined again
+    pub second_document: usize,
+    /// Position of the conflicting definition
+    pub second_position: Pos,
+}
+
+impl fmt::Display for Conflict {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} (document {} at {}, document {} at {})",
+            self.message,
+            self.first_document,
+            self.first_position,
+            self.second_document,
+            self.second_position
+        )
+    }
+}
+
+/// Error merging schemas
+#[derive(Error, Debug)]
+#[error("schema merge error: {}", display_conflicts(conflicts))]
+pub struct MergeError {
+    conflicts: Vec<Conflict>,
+}
+
+impl MergeError {
+    /// All the conflicts found, in order of appearance
+    pub fn conflicts(&self) -> &[Conflict] {
+        &self.conflicts
+    }
+}
+
+fn display_conflicts(conflicts: &[Conflict]) -> String {
+    conflicts
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Merges schema documents into a single one
+///
+/// Type extensions are folded into the types they extend, wherever the type
+/// is defined, and schema definitions are combined. Extensions of types not
+/// defined in any document are kept as is. Types and directives defined
+/// identically in several documents are written once, different
+/// definitions are reported as conflicts, along with fields defined twice
+/// and root operation types that don't match.
+///
+/// Definitions keep the order of their first appearance.
+pub fn merge<'a, T, I>(documents: I) -> Result<Document<'a, T>, MergeError>
+where
+    T: Text<'a>,
+    I: IntoIterator<Item = Document<'a, T>>,
+{
+    let mut merger = Merger {
+        definitions: Vec::new(),
+        schema: None,
+        types: HashMap::new(),
+        directives: HashMap::new(),
+        state: State {
+            origins: HashMap::new(),
+            conflicts: Vec::new(),
+        },
+    };
+    let mut extensions = Vec::new();
+    for (doc, document) in documents.into_iter().enumerate() {
+        for definition in document.definitions {
+            match definition {
+                Definition::SchemaDefinition(schema) => merger.add_schema(doc, schema),
+                Definition::TypeDefinition(t) => merger.add_type(doc, t),
+                // Extensions may come before the type they extend
+                Definition::TypeExtension(e) => extensions.push((doc, e)),
+                Definition::DirectiveDefinition(d) => merger.add_directive(doc, d),
+            }
+        }
+    }
+    for (doc, extension) in extensions {
+        merger.extend(doc, extension);
+    }
+    if merger.state.conflicts.is_empty() {
+        Ok(Document {
+            definitions: merger.definitions,
+        })
+    } else {
+        Err(MergeError {
+            conflicts: merger.state.conflicts,
+        })
+    }
+}
+
+struct Merger<'a, T: Text<'a>> {
+    definitions: Vec<Definition<'a, T>>,
+    schema: Option<usize>,
+    types: HashMap<String, usize>,
+    directives: HashMap<String, usize>,
+    state: State,
+}
+
+struct State {
+    /// Documents elements come from, used to report conflicts
+    origins: HashMap<String, usize>,
+    conflicts: Vec<Conflict>,
+}
+
+impl State {
+    fn conflict(&mut self, name: String, message: String, first: Pos, doc: usize, second: Pos) {
+        let first_document = self.origin(&name);
+        self.conflicts.push(Conflict {
+            name,
+            message,
+            first_document,
+            first_position: first,
+            second_document: doc,
+            second_position: second,
+        });
+    }
+
+    /// Returns the document the element comes from, for members of types
+    /// falls back to the document of the type
+    fn origin(&self, name: &str) -> usize {
+        let mut name = name;
+        loop {
+            if let Some(&doc) = self.origins.get(name) {
+                return doc;
+            }
+            match name.rfind('.') {
+                Some(idx) => name = &name[..idx],
+                None => return 0,
+            }
+        }
+    }
+}
+
+impl<'a, T: Text<'a>> Merger<'a, T> {
+    fn add_schema(&mut self, doc: usize, schema: SchemaDefinition<'a, T>) {
+        let idx = match self.schema {
+            Some(idx) => idx,
+            None => {
+                self.schema = Some(self.definitions.len());
+                self.state.origins.insert("schema".into(), doc);
+                self.definitions.push(Definition::SchemaDefinition(schema));
+                return;
+            }
+        };
+        let existing = match self.definitions[idx] {
+            Definition::SchemaDefinition(ref mut existing) => existing,
+            _ => unreachable!(),
+        };
+        let roots = vec![
+            ("query", &mut existing.query, schema.query),
+            ("mutation", &mut existing.mutation, schema.mutation),
+            (
+                "subscription",
+                &mut existing.subscription,
+                schema.subscription,
+            ),
+        ];
+        for (operation, root, new_root) in roots {
+            match (root.as_ref(), new_root) {
+                (Some(old), Some(new)) if old.as_ref() != new.as_ref() => self.state.conflict(
+                    format!("schema.{}", operation),
+                    format!(
+                        "Root {} type is both `{}` and `{}`",
+                        operation,
+                        old.as_ref(),
+                        new.as_ref()
+                    ),
+                    existing.position,
+                    doc,
+                    schema.position,
+                ),
+                (None, Some(new)) => {
+                    self.state
+                        .origins
+                        .insert(format!("schema.{}", operation), doc);
+                    *root = Some(new);
+                }
+                _ => {}
+            }
+        }
+        existing.directives.extend(schema.directives);
+    }
+
+    fn add_type(&mut self, doc: usize, definition: TypeDefinition<'a, T>) {
+        let name = type_name(&definition).to_string();
+        let idx = match self.types.get(&name) {
+            Some(&idx) => idx,
+            None => {
+                self.types.insert(name.clone(), self.definitions.len());
+                self.state.origins.insert(name, doc);
+                self.definitions
+                    .push(Definition::TypeDefinition(definition));
+                return;
+            }
+        };
+        let existing = match self.definitions[idx] {
+            Definition::TypeDefinition(ref existing) => existing,
+            _ => unreachable!(),
+        };
+        if !existing.semantic_eq(&definition, FieldOrder::Significant) {
+            let message = format!("Type `{}` is defined differently", name);
+            let first = type_position(existing);
+            let second = type_position(&definition);
+            self.state.conflict(name, message, first, doc, second);
+        }
+    }
+
+    fn add_directive(&mut self, doc: usize, definition: DirectiveDefinition<'a, T>) {
+        let name = format!("@{}", definition.name.as_ref());
+        let idx = match self.directives.get(&name) {
+            Some(&idx) => idx,
+            None => {
+                self.directives.insert(name.clone(), self.definitions.len());
+                self.state.origins.insert(name, doc);
+                self.definitions
+                    .push(Definition::DirectiveDefinition(definition));
+                return;
+            }
+        };
+        let existing = match self.definitions[idx] {
+            Definition::DirectiveDefinition(ref existing) => existing,
+            _ => unreachable!(),
+        };
+        if !existing.semantic_eq(&definition, FieldOrder::Significant) {
+            let message = format!("Directive `{}` is defined differently", name);
+            let first = existing.position;
+            self.state
+                .conflict(name, message, first, doc, definition.position);
+        }
+    }
+
+    fn extend(&mut self, doc: usize, extension: TypeExtension<'a, T>) {
+        let name = extension_name(&extension).to_string();
+        let idx = match self.types.get(&name) {
+            Some(&idx) => idx,
+            None => {
+                self.definitions.push(Definition::TypeExtension(extension));
+                return;
+            }
+        };
+        let state = &mut self.state;
+        let base = match self.definitions[idx] {
+            Definition::TypeDefinition(ref mut base) => base,
+            _ => unreachable!(),
+        };
+        match (base, extension) {
+            (TypeDefinition::Scalar(t), TypeExtension::Scalar(e)) => {
+                t.directives.extend(e.directives);
+            }
+            (TypeDefinition::Object(t), TypeExtension::Object(e)) => {
+                add_names(&mut t.implements_interfaces, e.implements_interfaces);
+                t.directives.extend(e.directives);
+                add_members(state, doc, &name, "Field", &mut t.fields, e.fields, |f| {
+                    (f.name.as_ref(), f.position)
+                });
+            }
+            (TypeDefinition::Interface(t), TypeExtension::Interface(e)) => {
+                add_names(&mut t.implements_interfaces, e.implements_interfaces);
+                t.directives.extend(e.directives);
+                add_members(state, doc, &name, "Field", &mut t.fields, e.fields, |f| {
+                    (f.name.as_ref(), f.position)
+                });
+            }
+            (TypeDefinition::Union(t), TypeExtension::Union(e)) => {
+                t.directives.extend(e.directives);
+                add_names(&mut t.types, e.types);
+            }
+            (TypeDefinition::Enum(t), TypeExtension::Enum(e)) => {
+                t.directives.extend(e.directives);
+                add_members(
+                    state,
+                    doc,
+                    &name,
+                    "Enum value",
+                    &mut t.values,
+                    e.values,
+                    |v| (v.name.as_ref(), v.position),
+                );
+            }
+            (TypeDefinition::InputObject(t), TypeExtension::InputObject(e)) => {
+                t.directives.extend(e.directives);
+                add_members(
+                    state,
+                    doc,
+                    &name,
+                    "Input field",
+                    &mut t.fields,
+                    e.fields,
+                    |f| (f.name.as_ref(), f.position),
+                );
+            }
+            (base, extension) => {
+                let message = format!(
+                    "Type `{}` is {} {}, but is extended as {} {}",
+                    name,
+                    article(type_kind(base)),
+                    type_kind(base),
+                    article(extension_kind(&extension)),
+                    extension_kind(&extension)
+                );
+                let first = type_position(base);
+                let second = extension_position(&extension);
+                state.conflict(name, message, first, doc, second);
+            }
+        }
+    }
+}
+
+/// Adds names which aren't in the list yet
+fn add_names<V: AsRef<str>>(names: &mut Vec<V>, new: Vec<V>) {
+    for name in new {
+        if !names.iter().any(|n| n.as_ref() == name.as_ref()) {
+            names.push(name);
+        }
+    }
+}
+
+/// Adds members of the extension, reporting the ones already defined
+fn add_members<M, F>(
+    state: &mut State,
+    doc: usize,
+    type_name: &str,
+    what: &str,
+    members: &mut Vec<M>,
+    new: Vec<M>,
+    key: F,
+) where
+    F: Fn(&M) -> (&str, Pos),
+{
+    for member in new {
+        let (name, position) = key(&member);
+        let path = format!("{}.{}", type_name, name);
+        match members.iter().find(|m| key(m).0 == name) {
+            Some(existing) => {
+                let message = format!("{} `{}` is defined twice", what, path);
+                let first = key(existing).1;
+                state.conflict(path, message, first, doc, position);
+            }
+            None => {
+                state.origins.insert(path, doc);
+                members.push(member);
+            }
+        }
+    }
+}
+
+fn article(kind: &str) -> &'static str {
+    match kind {
+        "object" | "interface" | "enum" | "input object" => "an",
+        _ => "a",
+    }
+}
+
+fn type_name<'x, 'a, T: Text<'a>>(definition: &'x TypeDefinition<'a, T>) -> &'x str {
+    match *definition {
+        TypeDefinition::Scalar(ref t) => t.name.as_ref(),
+        TypeDefinition::Object(ref t) => t.name.as_ref(),
+        TypeDefinition::Interface(ref t) => t.name.as_ref(),
+        TypeDefinition::Union(ref t) => t.name.as_ref(),
+        TypeDefinition::Enum(ref t) => t.name.as_ref(),
+        TypeDefinition::InputObject(ref t) => t.name.as_ref(),
+    }
+}
+
+fn type_position<'a, T: Text<'a>>(definition: &TypeDefinition<'a, T>) -> Pos {
+    match *definition {
+        TypeDefinition::Scalar(ref t) => t.position,
+        TypeDefinition::Object(ref t) => t.position,
+        TypeDefinition::Interface(ref t) => t.position,
+        TypeDefinition::Union(ref t) => t.position,
+        TypeDefinition::Enum(ref t) => t.position,
+        TypeDefinition::InputObject(ref t) => t.position,
+    }
+}
+
+fn type_kind<'a, T: Text<'a>>(definition: &TypeDefinition<'a, T>) -> &'static str {
+    match *definition {
+        TypeDefinition::Scalar(_) => "scalar",
+        TypeDefinition::Object(_) => "object",
+        TypeDefinition::Interface(_) => "interface",
+        TypeDefinition::Union(_) => "union",
+        TypeDefinition::Enum(_) => "enum",
+        TypeDefinition::InputObject(_) => "input object",
+    }
+}
+
+fn extension_name<'x, 'a, T: Text<'a>>(extension: &'x TypeExtension<'a, T>) -> &'x str {
+    match *extension {
+        TypeExtension::Scalar(ref t) => t.name.as_ref(),
+        TypeExtension::Object(ref t) => t.name.as_ref(),
+        TypeExtension::Interface(ref t) => t.name.as_ref(),
+        TypeExtension::Union(ref t) => t.name.as_ref(),
+        TypeExtension::Enum(ref t) => t.name.as_ref(),
+        TypeExtension::InputObject(ref t) => t.name.as_ref(),
+    }
+}
+
+fn extension_position<'a, T: Text<'a>>(extension: &TypeExtension<'a, T>) -> Pos {
+    match *extension {
+        TypeExtension::Scalar(ref t) => t.position,
+        TypeExtension::Object(ref t) => t.position,
+        TypeExtension::Interface(ref t) => t.position,
+        TypeExtension::Union(ref t) => t.position,
+        TypeExtension::Enum(ref t) => t.position,
+        TypeExtension::InputObject(ref t) => t.position,
+    }
+}
+
+fn extension_kind<'a, T: Text<'a>>(extension: &TypeExtension<'a, T>) -> &'static str {
+    match *extension {
+        TypeExtension::Scalar(_) => "scalar",
+        TypeExtension::Object(_) => "object",
+        TypeExtension::Interface(_) => "interface",
+        TypeExtension::Union(_) => "union",
+        TypeExtension::Enum(_) => "enum",
+        TypeExtension::InputObject(_) => "input object",
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::merge;
+    use crate::position::Pos;
+    use crate::schema::{parse_schema, Document};
+
+    fn parse(sources: &[&'static str]) -> Vec<Document<'static, &'static str>> {
+        sources.iter().map(|s| parse_schema(s).unwrap()).collect()
+    }
+
+    #[test]
+    fn extensions() {
+        let merged = merge(parse(&[
+            "extend type Query { b: Int } extend enum E @e { B } extend type Other { x: Int }",
+            "type Query implements A { a: Int } enum E { A } union U = X",
+            "extend type Query implements A & B @q extend union U = X | Y scalar S",
+        ]))
+        .unwrap();
+        assert_eq!(
+            merged.to_string(),
+            "type Query implements A & B @q {\n  a: Int\n  b: Int\n}\n\n\
+             enum E @e {\n  A\n  B\n}\n\nunion U = X | Y\n\nscalar S\n\n\
+             extend type Other {\n  x: Int\n}\n"
+        );
+    }
+
+    #[test]
+    fn schema_and_directives() {
+        let merged = merge(parse(&[
+            "schema { query: Query } directive @d(x: Int) on FIELD",
+            "schema @s { query: Query mutation: Mutation } directive @d(x: Int) on FIELD",
+            "type Query { a: Int }",
+        ]))
+        .unwrap();
+        assert_eq!(
+            merged.to_string(),
+            "schema @s {\n  query: Query\n  mutation: Mutation\n}\n\n\
+             directive @d(x: Int) on FIELD\n\ntype Query {\n  a: Int\n}\n"
+        );
+    }
+
+    #[test]
+    fn same_definitions_written_differently() {
+        let merged = merge(parse(&[
+            "\"desc\" type A { x: Int } directive @d(\"x\" x: String = \"a\") on FIELD",
+            "\"\"\"desc\"\"\"\ntype A {\n  x: Int\n}\n\
+             directive @d(\"\"\"\n  x\n\"\"\" x: String = \"\\u0061\") on FIELD",
+        ]))
+        .unwrap();
+        assert_eq!(
+            merged.to_string(),
+            "\"desc\"\ntype A {\n  x: Int\n}\n\n\
+             directive @d(\"x\" x: String = \"a\") on FIELD\n"
+        );
+    }
+
+    #[test]
+    fn conflicts() {
+        let err = merge(parse(&[
+            "schema { query: Query }\ntype A { a: Int }\ndirective @d on FIELD",
+            "extend type A { a: String }\n\nenum A { X }\n\nextend scalar A @a",
+            "schema { query: Root }\ndirective @d on FIELD | QUERY\ntype A { a: Int }",
+        ]))
+        .unwrap_err();
+        let conflicts = err
+            .conflicts()
+            .iter()
+            .map(|c| {
+                (
+                    c.message.as_str(),
+                    c.first_document,
+                    c.first_position,
+                    c.second_document,
+                    c.second_position,
+                )
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            conflicts,
+            vec![
+                (
+                    "Type `A` is defined differently",
+                    0,
+                    Pos { line: 2, column: 1 },
+                    1,
+                    Pos { line: 3, column: 1 },
+                ),
+                (
+                    "Root query type is both `Query` and `Root`",
+                    0,
+                    Pos { line: 1, column: 1 },
+                    2,
+                    Pos { line: 1, column: 1 },
+                ),
+                (
+                    "Directive `@d` is defined differently",
+                    0,
+                    Pos { line: 3, column: 1 },
+                    2,
+                    Pos { line: 2, column: 1 },
+                ),
+                (
+                    "Field `A.a` is defined twice",
+                    0,
+                    Pos {
+                        line: 2,
+                        column: 10
+                    },
+                    1,
+                    Pos {
+                        line: 1,
+                        column: 17
+                    },
+                ),
+                (
+                    "Type `A` is an object, but is extended as a scalar",
+                    0,
+                    Pos { line: 2, column: 1 },
+                    1,
+                    Pos { line: 5, column: 8 },
+                ),
+            ]
+        );
+        assert_eq!(
+            err.to_string().lines().next().unwrap(),
+            "schema merge error: Type `A` is defined differently \
+             (document 0 at 2:1, document 1 at 3:1)"
+        );
+    }
+}
@@ -1,10 +1,23 @@
 //! Schema definition language AST and utility
 //!
 mod ast;
+pub mod builder;
+mod coerce;
+mod diff;
 mod error;
 mod format;
-mod grammar;
+pub(crate) mod grammar;
+mod merge;
+mod owned;
 
 pub use self::ast::*;
+pub use self::coerce::{
+    check_default_values, coerce_value, CoercionError, InputTypes, InvalidValue,
+};
+pub use self::diff::{diff, Change, ChangeKind, Criticality};
 pub use self::error::ParseError;
-pub use self::grammar::parse_schema;
+pub use self::format::{format_schema_range, skipped_schema_definitions};
+pub use self::grammar::{consume_definition, parse_definitions, Definitions};
+pub use self::grammar::{parse_field_definition, parse_schema};
+pub use self::merge::{merge, Conflict, MergeError};
+pub use self::owned::OwnedSchema;
@@ -0,0 +1,107 @@
+use std::fmt;
+use std::sync::Arc;
+
+use crate::schema::ast::Document;
+use crate::schema::error::ParseError;
+use crate::schema::grammar::parse_schema;
+
+/// Parsed schema bundled together with its source text
+///
+/// All names in the document borrow from the source buffer, so parsing
+/// doesn't allocate per identifier (like `Document<&str>`). But the buffer
+/// is owned by the structure itself, so it can be stored in long-lived
+/// caches and sent across threads. Cloning only bumps a reference count of
+/// the source and copies the tree.
+#[derive(Clone, PartialEq)]
+pub struct OwnedSchema {
+    // Borrows from `source`, so it must be declared (and dropped) first.
+    // The `'static` lifetime is a lie: it is never exposed outside, see
+    // `document()` which narrows it down to the lifetime of `self`.
+    document: Document<'static, &'static str>,
+    source: Arc<str>,
+}
+
+impl OwnedSchema {
+    /// Parses schema and keeps the source buffer along with the AST
+    pub fn parse<S: Into<Arc<str>>>(source: S) -> Result<OwnedSchema, ParseError> {
+        let source = source.into();
+        // This is safe because string data behind `Arc` is never mutated
+        // and never moves, and it's kept alive by the `source` field for
+        // as long as the document exists.
+        let text = unsafe { &*(&*source as *const str) };
+        let document = parse_schema::<&'static str>(text)?;
+        Ok(OwnedSchema { document, source })
+    }
+
+    /// Returns a zero-copy view of the document
+    pub fn document<'a>(&'a self) -> &'a Document<'a, &'a str> {
+        // Only narrows lifetimes down to the lifetime of the buffer owner
+        let ptr: *const Document<'static, &'static str> = &self.document;
+        unsafe { &*ptr.cast::<Document<'a, &'a str>>() }
+    }
+
+    /// Returns source text of the document
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+
+    /// Returns shared buffer with the source text of the document
+    pub fn shared_source(&self) -> &Arc<str> {
+        &self.source
+    }
+}
+
+impl fmt::Debug for OwnedSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OwnedSchema")
+            .field("document", self.document())
+            .finish()
+    }
+}
+
+impl fmt::Display for OwnedSchema {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(self.document(), f)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use std::thread;
+
+    use super::OwnedSchema;
+    use crate::schema::{parse_schema, Definition, TypeDefinition};
+
+    fn is_send_sync<T: Send + Sync + 'static>() {}
+
+    #[test]
+    fn send_sync() {
+        is_send_sync::<OwnedSchema>();
+    }
+
+    #[test]
+    fn borrows_from_source() {
+        let schema = OwnedSchema::parse("type User { id: ID! }").unwrap();
+        let name = match schema.document().definitions[0] {
+            Definition::TypeDefinition(TypeDefinition::Object(ref o)) => o.name,
+            _ => unreachable!(),
+        };
+        assert_eq!(name, "User");
+        let range = schema.source().as_bytes().as_ptr_range();
+        assert!(range.contains(&name.as_ptr()));
+    }
+
+    #[test]
+    fn same_as_borrowed() {
+        let source = "schema { query: Query } type Query { users(first: Int): [User!]! }";
+        let schema = OwnedSchema::parse(source).unwrap();
+        assert_eq!(schema.document(), &parse_schema::<&str>(source).unwrap());
+    }
+
+    #[test]
+    fn move_across_threads() {
+        let schema = OwnedSchema::parse(String::from("scalar Date")).unwrap();
+        let printed = thread::spawn(move || schema.to_string()).join().unwrap();
+        assert_eq!(printed, "scalar Date\n");
+    }
+}
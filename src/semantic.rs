@@ -0,0 +1,682 @@
+//! Equality and hashing of AST nodes that ignores positions
+//!
+//! Derived `PartialEq` compares positions too, so the same definition parsed
+//! from two differently formatted sources compares unequal. `SemanticEq`
+//! compares what the node means instead:
+//!
+//! * positions and spans are ignored
+//! * strings and descriptions are compared by value, regardless of quoting
+//! * floats are compared bitwise, so values can be hashed
+//! * with `FieldOrder::Ignored` the order of fields, input fields, arguments
+//!   and selections doesn't matter either
+//!
+//! `Semantic` wraps a node to be used as a key of a `HashMap` or `HashSet`:
+//!
+//! ```rust
+//! use std::collections::HashSet;
+//! use graphql_parser::{parse_query, Semantic};
+//!
+//! let a = parse_query::<&str>("{ user(id: 1, name: \"x\") { id } }").unwrap();
+//! let b = parse_query::<&str>("{\n  user(name: \"x\", id: 1) {\n    id\n  }\n}").unwrap();
+//!
+//! let mut seen = HashSet::new();
+//! assert!(seen.insert(Semantic::ignore_order(&a)));
+//! assert!(!seen.insert(Semantic::ignore_order(&b)));
+//! assert!(Semantic::new(&a) != Semantic::new(&b));
+//! ```
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+use crate::common::{self, Number, StringValue, Text, Type, Value};
+use crate::{document, query, schema};
+
+/// Whether the order of fields, input fields, arguments and selections
+/// is significant when comparing nodes
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum FieldOrder {
+    Significant,
+    Ignored,
+}
+
+/// Position-insensitive equality and hashing
+///
+/// Nodes that are `semantic_eq` with the same `FieldOrder` produce the same
+/// `semantic_hash`.
+pub trait SemanticEq {
+    /// Returns `true` if nodes are equal ignoring their positions
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool;
+
+    /// Feeds the node into the hasher ignoring its positions
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H);
+}
+
+/// A reference to a node implementing `Eq` and `Hash` with `SemanticEq`
+#[derive(Debug)]
+pub struct Semantic<'n, N: ?Sized> {
+    node: &'n N,
+    order: FieldOrder,
+}
+
+impl<'n, N: SemanticEq + ?Sized> Semantic<'n, N> {
+    /// Compares nodes keeping the order of fields and arguments
+    pub fn new(node: &'n N) -> Self {
+        Semantic {
+            node,
+            order: FieldOrder::Significant,
+        }
+    }
+
+    /// Compares nodes ignoring the order of fields and arguments
+    pub fn ignore_order(node: &'n N) -> Self {
+        Semantic {
+            node,
+            order: FieldOrder::Ignored,
+        }
+    }
+
+    /// Returns the wrapped node
+    pub fn node(&self) -> &'n N {
+        self.node
+    }
+}
+
+impl<'n, N: ?Sized> Clone for Semantic<'n, N> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<'n, N: ?Sized> Copy for Semantic<'n, N> {}
+
+impl<'n, N: SemanticEq + ?Sized> PartialEq for Semantic<'n, N> {
+    fn eq(&self, other: &Self) -> bool {
+        self.order == other.order && self.node.semantic_eq(other.node, self.order)
+    }
+}
+
+impl<'n, N: SemanticEq + ?Sized> Eq for Semantic<'n, N> {}
+
+impl<'n, N: SemanticEq + ?Sized> Hash for Semantic<'n, N> {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.node.semantic_hash(self.order, state)
+    }
+}
+
+impl<N: SemanticEq> SemanticEq for Option<N> {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        match (self, other) {
+            (Some(a), Some(b)) => a.semantic_eq(b, order),
+            (None, None) => true,
+            _ => false,
+        }
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        self.is_some().hash(state);
+        if let Some(node) = self {
+            node.semantic_hash(order, state);
+        }
+    }
+}
+
+impl<N: SemanticEq> SemanticEq for [N] {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        self.len() == other.len() && self.iter().zip(other).all(|(a, b)| a.semantic_eq(b, order))
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        self.len().hash(state);
+        for node in self {
+            node.semantic_hash(order, state);
+        }
+    }
+}
+
+impl<N: SemanticEq> SemanticEq for Vec<N> {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        self[..].semantic_eq(&other[..], order)
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        self[..].semantic_hash(order, state)
+    }
+}
+
+impl<N: SemanticEq + ?Sized> SemanticEq for Box<N> {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        (**self).semantic_eq(other, order)
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        (**self).semantic_hash(order, state)
+    }
+}
+
+/// Helpers used by `impl_struct!` for each kind of field
+mod field {
+    use super::*;
+
+    /// Names and other strings
+    pub mod name {
+        use super::*;
+
+        pub fn eq<V: Eq>(a: &V, b: &V, _: FieldOrder) -> bool {
+            a == b
+        }
+
+        pub fn hash<V: AsRef<str>, H: Hasher>(value: &V, _: FieldOrder, state: &mut H) {
+            value.as_ref().hash(state);
+        }
+    }
+
+    /// Optional name, like the name of an operation
+    pub mod opt_name {
+        use super::*;
+
+        pub fn eq<V: Eq>(a: &Option<V>, b: &Option<V>, _: FieldOrder) -> bool {
+            a == b
+        }
+
+        pub fn hash<V: AsRef<str>, H: Hasher>(value: &Option<V>, _: FieldOrder, state: &mut H) {
+            value.as_ref().map(|v| v.as_ref()).hash(state);
+        }
+    }
+
+    /// List of names, like implemented interfaces
+    pub mod names {
+        use super::*;
+
+        pub fn eq<V: Eq>(a: &[V], b: &[V], _: FieldOrder) -> bool {
+            a == b
+        }
+
+        pub fn hash<V: AsRef<str>, H: Hasher>(values: &[V], _: FieldOrder, state: &mut H) {
+            values.len().hash(state);
+            for value in values {
+                value.as_ref().hash(state);
+            }
+        }
+    }
+
+    /// Values having a meaningful `Eq` and `Hash` already
+    pub mod plain {
+        use super::*;
+
+        pub fn eq<V: Eq>(a: &V, b: &V, _: FieldOrder) -> bool {
+            a == b
+        }
+
+        pub fn hash<V: Hash, H: Hasher>(value: &V, _: FieldOrder, state: &mut H) {
+            value.hash(state);
+        }
+    }
+
+    /// Nested nodes
+    pub mod node {
+        use super::*;
+
+        pub fn eq<N: SemanticEq + ?Sized>(a: &N, b: &N, order: FieldOrder) -> bool {
+            a.semantic_eq(b, order)
+        }
+
+        pub fn hash<N: SemanticEq + ?Sized, H: Hasher>(node: &N, order: FieldOrder, state: &mut H) {
+            node.semantic_hash(order, state);
+        }
+    }
+
+    /// Fields, arguments and selections, which order is ignored on request
+    pub mod fields {
+        use super::*;
+
+        pub fn eq<N: SemanticEq>(a: &[N], b: &[N], order: FieldOrder) -> bool {
+            match order {
+                FieldOrder::Significant => a.semantic_eq(b, order),
+                FieldOrder::Ignored => {
+                    // Lists are short, so quadratic matching is fine here
+                    let mut matched = vec![false; b.len()];
+                    a.len() == b.len()
+                        && a.iter().all(|x| {
+                            let found = b
+                                .iter()
+                                .zip(&mut matched)
+                                .find(|(y, matched)| !**matched && x.semantic_eq(y, order));
+                            match found {
+                                Some((_, matched)) => {
+                                    *matched = true;
+                                    true
+                                }
+                                None => false,
+                            }
+                        })
+                }
+            }
+        }
+
+        pub fn hash<N: SemanticEq, H: Hasher>(nodes: &[N], order: FieldOrder, state: &mut H) {
+            match order {
+                FieldOrder::Significant => nodes.semantic_hash(order, state),
+                FieldOrder::Ignored => {
+                    // Hash each item separately and sort hashes so that
+                    // the result doesn't depend on the order
+                    let mut hashes = nodes
+                        .iter()
+                        .map(|node| {
+                            let mut hasher = DefaultHasher::new();
+                            node.semantic_hash(order, &mut hasher);
+                            hasher.finish()
+                        })
+                        .collect::<Vec<_>>();
+                    hashes.sort_unstable();
+                    hashes.hash(state);
+                }
+            }
+        }
+    }
+}
+
+macro_rules! impl_struct {
+    ($module: ident :: $typ: ident { $($field: ident: $kind: ident),* $(,)? }) => {
+        impl<'a, T: Text<'a>> SemanticEq for $module::$typ<'a, T> {
+            fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+                true $(&& field::$kind::eq(&self.$field, &other.$field, order))*
+            }
+
+            fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+                $(field::$kind::hash(&self.$field, order, state);)*
+            }
+        }
+    };
+}
+
+macro_rules! impl_enum {
+    ($module: ident :: $typ: ident { $($variant: ident),* $(,)? }) => {
+        impl<'a, T: Text<'a>> SemanticEq for $module::$typ<'a, T> {
+            fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+                match (self, other) {
+                    $(
+                        ($module::$typ::$variant(a), $module::$typ::$variant(b)) => {
+                            a.semantic_eq(b, order)
+                        }
+                    )*
+                    _ => false,
+                }
+            }
+
+            fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+                std::mem::discriminant(self).hash(state);
+                match self {
+                    $($module::$typ::$variant(node) => node.semantic_hash(order, state),)*
+                }
+            }
+        }
+    };
+}
+
+impl<'a, T: Text<'a>> SemanticEq for StringValue<'a, T> {
+    fn semantic_eq(&self, other: &Self, _: FieldOrder) -> bool {
+        self.as_str() == other.as_str()
+    }
+
+    fn semantic_hash<H: Hasher>(&self, _: FieldOrder, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl<'a, T: Text<'a>> SemanticEq for Type<'a, T> {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        match (self, other) {
+            (Type::NamedType(a), Type::NamedType(b)) => a == b,
+            (Type::ListType(a), Type::ListType(b)) => a.semantic_eq(b, order),
+            (Type::NonNullType(a), Type::NonNullType(b)) => a.semantic_eq(b, order),
+            _ => false,
+        }
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Type::NamedType(name) => name.as_ref().hash(state),
+            Type::ListType(inner) | Type::NonNullType(inner) => inner.semantic_hash(order, state),
+        }
+    }
+}
+
+/// Returns bits of the float with both zeros being the same value
+fn float_bits(value: f64) -> u64 {
+    if value == 0.0 {
+        0
+    } else {
+        value.to_bits()
+    }
+}
+
+impl<'a, T: Text<'a>> SemanticEq for Value<'a, T> {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        match (self, other) {
+            (Value::Variable(a), Value::Variable(b)) => a == b,
+            (Value::Int(Number(a)), Value::Int(Number(b))) => a == b,
+            (Value::Float(a), Value::Float(b)) => float_bits(*a) == float_bits(*b),
+            (Value::String(a), Value::String(b)) => a.semantic_eq(b, order),
+            (Value::Boolean(a), Value::Boolean(b)) => a == b,
+            (Value::Null, Value::Null) => true,
+            (Value::Enum(a), Value::Enum(b)) => a == b,
+            (Value::List(a), Value::List(b)) => a.semantic_eq(b, order),
+            // Object keys are sorted, so the order never matters
+            (Value::Object(a), Value::Object(b)) => {
+                a.len() == b.len()
+                    && a.iter()
+                        .zip(b)
+                        .all(|((ka, va), (kb, vb))| ka == kb && va.semantic_eq(vb, order))
+            }
+            _ => false,
+        }
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        std::mem::discriminant(self).hash(state);
+        match self {
+            Value::Variable(name) | Value::Enum(name) => name.as_ref().hash(state),
+            Value::Int(Number(value)) => value.hash(state),
+            Value::Float(value) => float_bits(*value).hash(state),
+            Value::String(value) => value.semantic_hash(order, state),
+            Value::Boolean(value) => value.hash(state),
+            Value::Null => {}
+            Value::List(items) => items.semantic_hash(order, state),
+            Value::Object(fields) => {
+                fields.len().hash(state);
+                for (name, value) in fields {
+                    name.as_ref().hash(state);
+                    value.semantic_hash(order, state);
+                }
+            }
+        }
+    }
+}
+
+/// An argument of a field or a directive
+impl<'a, T: Text<'a>> SemanticEq for (T::Value, Value<'a, T>) {
+    fn semantic_eq(&self, other: &Self, order: FieldOrder) -> bool {
+        self.0 == other.0 && self.1.semantic_eq(&other.1, order)
+    }
+
+    fn semantic_hash<H: Hasher>(&self, order: FieldOrder, state: &mut H) {
+        self.0.as_ref().hash(state);
+        self.1.semantic_hash(order, state);
+    }
+}
+
+impl_struct!(common::Directive {
+    name: name,
+    arguments: fields,
+});
+
+impl_struct!(schema::Document { definitions: node });
+impl_enum!(schema::Definition {
+    SchemaDefinition,
+    TypeDefinition,
+    TypeExtension,
+    DirectiveDefinition,
+});
+impl_struct!(schema::SchemaDefinition {
+    directives: node,
+    query: opt_name,
+    mutation: opt_name,
+    subscription: opt_name,
+});
+impl_enum!(schema::TypeDefinition {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+});
+impl_enum!(schema::TypeExtension {
+    Scalar,
+    Object,
+    Interface,
+    Union,
+    Enum,
+    InputObject,
+});
+impl_struct!(schema::ScalarType {
+    description: node,
+    name: name,
+    directives: node,
+});
+impl_struct!(schema::ScalarTypeExtension {
+    name: name,
+    directives: node,
+});
+impl_struct!(schema::ObjectType {
+    description: node,
+    name: name,
+    implements_interfaces: names,
+    directives: node,
+    fields: fields,
+});
+impl_struct!(schema::ObjectTypeExtension {
+    name: name,
+    implements_interfaces: names,
+    directives: node,
+    fields: fields,
+});
+impl_struct!(schema::Field {
+    description: node,
+    name: name,
+    arguments: fields,
+    field_type: node,
+    directives: node,
+});
+impl_struct!(schema::InputValue {
+    description: node,
+    name: name,
+    value_type: node,
+    default_value: node,
+    directives: node,
+});
+impl_struct!(schema::InterfaceType {
+    description: node,
+    name: name,
+    implements_interfaces: names,
+    directives: node,
+    fields: fields,
+});
+impl_struct!(schema::InterfaceTypeExtension {
+    name: name,
+    implements_interfaces: names,
+    directives: node,
+    fields: fields,
+});
+impl_struct!(schema::UnionType {
+    description: node,
+    name: name,
+    directives: node,
+    types: names,
+});
+impl_struct!(schema::UnionTypeExtension {
+    name: name,
+    directives: node,
+    types: names,
+});
+impl_struct!(schema::EnumType {
+    description: node,
+    name: name,
+    directives: node,
+    values: node,
+});
+impl_struct!(schema::EnumValue {
+    description: node,
+    name: name,
+    directives: node,
+});
+impl_struct!(schema::EnumTypeExtension {
+    name: name,
+    directives: node,
+    values: node,
+});
+impl_struct!(schema::InputObjectType {
+    description: node,
+    name: name,
+    directives: node,
+    fields: fields,
+});
+impl_struct!(schema::InputObjectTypeExtension {
+    name: name,
+    directives: node,
+    fields: fields,
+});
+impl_struct!(schema::DirectiveDefinition {
+    description: node,
+    name: name,
+    arguments: fields,
+    repeatable: plain,
+    locations: plain,
+});
+
+impl_struct!(query::Document { definitions: node });
+impl_enum!(query::Definition {
+    Operation,
+    Fragment
+});
+impl_struct!(query::FragmentDefinition {
+    name: name,
+    type_condition: node,
+    directives: node,
+    selection_set: node,
+});
+impl_enum!(query::OperationDefinition {
+    SelectionSet,
+    Query,
+    Mutation,
+    Subscription,
+});
+impl_struct!(query::Query {
+    name: opt_name,
+    variable_definitions: node,
+    directives: node,
+    selection_set: node,
+});
+impl_struct!(query::Mutation {
+    name: opt_name,
+    variable_definitions: node,
+    directives: node,
+    selection_set: node,
+});
+impl_struct!(query::Subscription {
+    name: opt_name,
+    variable_definitions: node,
+    directives: node,
+    selection_set: node,
+});
+impl_struct!(query::SelectionSet { items: fields });
+impl_struct!(query::VariableDefinition {
+    name: name,
+    var_type: node,
+    default_value: node,
+});
+impl_enum!(query::Selection {
+    Field,
+    FragmentSpread,
+    InlineFragment,
+});
+impl_struct!(query::Field {
+    alias: opt_name,
+    name: name,
+    arguments: fields,
+    directives: node,
+    selection_set: node,
+});
+impl_struct!(query::FragmentSpread {
+    fragment_name: name,
+    directives: node,
+});
+
+impl<'a, T: Text<'a>> SemanticEq for query::TypeCondition<'a, T> {
+    fn semantic_eq(&self, other: &Self, _: FieldOrder) -> bool {
+        let (query::TypeCondition::On(a), query::TypeCondition::On(b)) = (self, other);
+        a == b
+    }
+
+    fn semantic_hash<H: Hasher>(&self, _: FieldOrder, state: &mut H) {
+        let query::TypeCondition::On(name) = self;
+        name.as_ref().hash(state);
+    }
+}
+
+impl_struct!(query::InlineFragment {
+    type_condition: node,
+    directives: node,
+    selection_set: node,
+});
+
+impl_struct!(document::Document { definitions: node });
+impl_enum!(document::Definition {
+    Executable,
+    TypeSystem,
+});
+
+#[cfg(test)]
+mod test {
+    use std::collections::hash_map::DefaultHasher;
+    use std::hash::{Hash, Hasher};
+
+    use super::{FieldOrder, Semantic, SemanticEq};
+    use crate::{parse_query, parse_schema, parse_value};
+
+    fn hash<N: SemanticEq>(node: Semantic<N>) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        node.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[test]
+    fn ignores_positions() {
+        let a = parse_schema::<String>("type A { a: Int @x(y: [1.5]) }").unwrap();
+        let b = parse_schema::<String>("\n\ntype A {\n  a: Int\n    @x(y: [1.5])\n}").unwrap();
+        assert_ne!(a, b);
+        assert!(a.semantic_eq(&b, FieldOrder::Significant));
+        assert_eq!(hash(Semantic::new(&a)), hash(Semantic::new(&b)));
+    }
+
+    #[test]
+    fn field_order() {
+        let a = parse_schema::<&str>("type A { a(x: Int, y: Int): Int b: Int }").unwrap();
+        let b = parse_schema::<&str>("type A { b: Int a(y: Int, x: Int): Int }").unwrap();
+        assert!(!a.semantic_eq(&b, FieldOrder::Significant));
+        assert!(a.semantic_eq(&b, FieldOrder::Ignored));
+        assert_eq!(
+            hash(Semantic::ignore_order(&a)),
+            hash(Semantic::ignore_order(&b))
+        );
+
+        // Other lists keep their order
+        let a = parse_schema::<&str>("enum E { A B }").unwrap();
+        let b = parse_schema::<&str>("enum E { B A }").unwrap();
+        assert!(!a.semantic_eq(&b, FieldOrder::Ignored));
+    }
+
+    #[test]
+    fn duplicates() {
+        let a = parse_query::<&str>("{ a a b }").unwrap();
+        let b = parse_query::<&str>("{ a b b }").unwrap();
+        assert!(!a.semantic_eq(&b, FieldOrder::Ignored));
+    }
+
+    #[test]
+    fn values() {
+        let a = parse_value::<&str>("{b: \"\"\"x\"\"\", a: -0.0}").unwrap();
+        let b = parse_value::<&str>("{a: 0.0, b: \"x\"}").unwrap();
+        assert!(a.semantic_eq(&b, FieldOrder::Significant));
+        assert_eq!(hash(Semantic::new(&a)), hash(Semantic::new(&b)));
+
+        let c = parse_value::<&str>("{a: 0, b: \"x\"}").unwrap();
+        assert!(!a.semantic_eq(&c, FieldOrder::Significant));
+    }
+
+    #[test]
+    fn order_is_part_of_key() {
+        let a = parse_query::<&str>("{ a }").unwrap();
+        assert!(Semantic::new(&a) != Semantic::ignore_order(&a));
+    }
+}
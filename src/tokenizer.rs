@@ -5,7 +5,7 @@ use combine::error::StreamError;
 use combine::stream::ResetStream;
 use combine::{Positioned, StreamOnce};
 
-use crate::position::Pos;
+use crate::position::{self, Pos};
 
 #[derive(Debug, PartialEq, Eq, Clone, Copy)]
 pub enum Kind {
@@ -129,14 +129,14 @@ fn check_float(value: &str, exponent: Option<usize>, real: Option<usize>) -> boo
 }
 
 impl<'a> TokenStream<'a> {
-    pub fn new(s: &str) -> TokenStream {
+    pub fn new(s: &str) -> TokenStream<'_> {
         Self::with_recursion_limit(s, 50)
     }
 
     /// Specify a limit to recursive parsing. Note that increasing the limit
     /// from the default may represent a security issue since a maliciously
     /// crafted input may cause a stack overflow, crashing the process.
-    pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream {
+    pub(crate) fn with_recursion_limit(s: &str, recursion_limit: usize) -> TokenStream<'_> {
         let mut me = TokenStream {
             buf: s,
             position: Pos { line: 1, column: 1 },
@@ -281,7 +281,7 @@ impl<'a> TokenStream<'a> {
                                 self.off += idx + 1;
                                 return Ok((StringValue, idx + 1));
                             }
-                            '\n' => {
+                            '\n' | '\r' => {
                                 return Err(Error::Unexpected(Info::Owned(
                                     "unterminated string value".to_string(),
                                 )));
@@ -305,35 +305,34 @@ impl<'a> TokenStream<'a> {
     }
 
     fn skip_whitespace(&mut self) {
-        let mut iter = self.buf[self.off..].char_indices();
+        let mut iter = self.buf[self.off..].char_indices().peekable();
         let idx = loop {
             let (idx, cur_char) = match iter.next() {
                 Some(pair) => pair,
                 None => break self.buf.len() - self.off,
             };
             match cur_char {
-                '\u{feff}' | '\r' => continue,
-                '\t' => self.position.column += 8,
-                '\n' => {
+                '\u{feff}' => continue,
+                // "\r\n" is a single line terminator
+                '\r' if matches!(iter.peek(), Some(&(_, '\n'))) => continue,
+                '\r' | '\n' => {
                     self.position.column = 1;
                     self.position.line += 1;
                 }
                 // comma is also entirely ignored in spec
-                ' ' | ',' => {
+                ' ' | '\t' | ',' => {
                     self.position.column += 1;
-                    continue;
                 }
                 //comment
                 '#' => {
-                    for (_, cur_char) in iter.by_ref() {
+                    // line terminator is counted on the next iteration
+                    while let Some(&(_, cur_char)) = iter.peek() {
                         // TODO(tailhook) ensure SourceCharacter
                         if cur_char == '\r' || cur_char == '\n' {
-                            self.position.column = 1;
-                            self.position.line += 1;
                             break;
                         }
+                        iter.next();
                     }
-                    continue;
                 }
                 _ => break idx,
             }
@@ -344,15 +343,14 @@ impl<'a> TokenStream<'a> {
     fn update_position(&mut self, len: usize) {
         let val = &self.buf[self.off..][..len];
         self.off += len;
-        let lines = val.as_bytes().iter().filter(|&&x| x == b'\n').count();
-        self.position.line += lines;
-        if lines > 0 {
-            let line_offset = val.rfind('\n').unwrap() + 1;
-            let num = val[line_offset..].chars().count();
-            self.position.column = num + 1;
-        } else {
-            let num = val.chars().count();
-            self.position.column += num;
+        let mut line_start = None;
+        for (start, _) in position::lines(val).skip(1) {
+            self.position.line += 1;
+            line_start = Some(start);
+        }
+        match line_start {
+            Some(start) => self.position.column = val[start..].chars().count() + 1,
+            None => self.position.column += val.chars().count(),
         }
     }
 }
@@ -604,6 +602,14 @@ mod test {
         assert_eq!(tok_typ(r#""hello""#), [StringValue]);
         assert_eq!(tok_str(r#""my\"quote""#), [r#""my\"quote""#]);
         assert_eq!(tok_typ(r#""my\"quote""#), [StringValue]);
+        assert_eq!(
+            tok_str(r#""\u{22}" "\uD83D\uDE00""#),
+            [r#""\u{22}""#, r#""\uD83D\uDE00""#]
+        );
+        assert_eq!(
+            tok_str(r#""\u{0000000041}" x"#),
+            [r#""\u{0000000041}""#, "x"]
+        );
     }
 
     #[test]
@@ -617,4 +623,40 @@ mod test {
         assert_eq!(tok_str(r#""""\"""quote" """"#), [r#""""\"""quote" """"#]);
         assert_eq!(tok_typ(r#""""\"""quote" """"#), [BlockString]);
     }
+
+    fn tok_pos(s: &str) -> Vec<(usize, usize)> {
+        let mut r = Vec::new();
+        let mut s = TokenStream::new(s);
+        loop {
+            let pos = s.position();
+            match s.uncons() {
+                Ok(_) => r.push((pos.line, pos.column)),
+                Err(ref e) if e == &Error::end_of_input() => break,
+                Err(e) => panic!("Parse error at {}: {}", s.position(), e),
+            }
+        }
+        r
+    }
+
+    #[test]
+    fn line_terminators() {
+        assert_eq!(tok_pos("a\nb\r\nc\rd"), [(1, 1), (2, 1), (3, 1), (4, 1)]);
+        assert_eq!(
+            tok_pos("a # x\r\nb # y\rc # z\nd"),
+            [(1, 1), (2, 1), (3, 1), (4, 1)]
+        );
+        assert_eq!(tok_pos("\"\"\"a\r\nb\rc\"\"\" d"), [(1, 1), (3, 6)]);
+    }
+
+    #[test]
+    fn tabs_and_unicode() {
+        assert_eq!(tok_pos("\ta\t\tb"), [(1, 2), (1, 5)]);
+        assert_eq!(tok_pos("\"\u{e9}\u{1F600}\" b"), [(1, 1), (1, 6)]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn carriage_return_in_string() {
+        tok_str("\"a\rb\"");
+    }
 }
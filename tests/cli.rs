@@ -0,0 +1,168 @@
+use std::fs;
+use std::path::PathBuf;
+use std::process::{Command, Output};
+
+fn graphql(args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_graphql"))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn temp_file(name: &str, content: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("graphql-cli-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join(name);
+    fs::write(&path, content).unwrap();
+    path
+}
+
+fn stdout(output: &Output) -> String {
+    String::from_utf8(output.stdout.clone()).unwrap()
+}
+
+fn stderr(output: &Output) -> String {
+    String::from_utf8(output.stderr.clone()).unwrap()
+}
+
+#[test]
+fn fmt_in_place() {
+    let query = temp_file("fmt.graphql", "query Q{a(x:1){b}}\n# comment\n{ c }\n");
+    let schema = temp_file("fmt_schema.graphql", "type A{a:Int}\n");
+    let output = graphql(&["fmt", query.to_str().unwrap(), schema.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(&query).unwrap(),
+        "query Q {\n  a(x: 1) {\n    b\n  }\n}\n# comment\n{\n  c\n}\n"
+    );
+    assert_eq!(
+        fs::read_to_string(&schema).unwrap(),
+        "type A {\n  a: Int\n}\n"
+    );
+}
+
+#[test]
+fn fmt_check() {
+    let formatted = temp_file("formatted.graphql", "{\n  a\n}\n");
+    let unformatted = temp_file("unformatted.graphql", "{ a }\n");
+    let output = graphql(&["fmt", "--check", formatted.to_str().unwrap()]);
+    assert!(output.status.success());
+    let output = graphql(&["fmt", "--check", unformatted.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!("{}: not formatted\n", unformatted.display())
+    );
+    assert_eq!(fs::read_to_string(&unformatted).unwrap(), "{ a }\n");
+}
+
+#[test]
+fn fmt_check_comments_and_blank_lines() {
+    let comments = temp_file(
+        "comments.graphql",
+        "type Query {   a:Int # c\n b:   String}\n",
+    );
+    let blank_lines = temp_file(
+        "blank_lines.graphql",
+        "type A {\n  a: Int\n}\n\n\n\n\ntype B {\n  b: Int\n}\n",
+    );
+    let output = graphql(&["fmt", "--check", comments.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "{}:1:1: warning: contains comments, not formatted\n",
+            comments.display()
+        )
+    );
+    let formatted = temp_file(
+        "formatted_comments.graphql",
+        "type Query {\n  # the id\n  id: ID\n}\n",
+    );
+    let output = graphql(&["fmt", "--check", formatted.to_str().unwrap()]);
+    assert!(output.status.success());
+    let output = graphql(&["fmt", "--check", blank_lines.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    let output = graphql(&["fmt", blank_lines.to_str().unwrap()]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(&blank_lines).unwrap(),
+        "type A {\n  a: Int\n}\n\ntype B {\n  b: Int\n}\n"
+    );
+}
+
+#[test]
+fn fmt_style() {
+    let schema = temp_file("style.graphql", "type A{b:Int a:Int}\n");
+    let output = graphql(&[
+        "fmt",
+        "--sort-fields",
+        "--indent",
+        "4",
+        schema.to_str().unwrap(),
+    ]);
+    assert!(output.status.success(), "{}", stderr(&output));
+    assert_eq!(
+        fs::read_to_string(&schema).unwrap(),
+        "type A {\n    a: Int\n    b: Int\n}\n"
+    );
+}
+
+#[test]
+fn check_errors() {
+    let file = temp_file("error.graphql", "query {\n  a(x: [)\n}\n");
+    let output = graphql(&["check", file.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stderr(&output),
+        format!(
+            "{}:2:9: Unexpected `)[Punctuator]`, Expected ]\n",
+            file.display()
+        )
+    );
+}
+
+#[test]
+fn forced_kind() {
+    let file = temp_file("kind.graphql", "type A { a: Int }\n");
+    assert!(graphql(&["check", file.to_str().unwrap()]).status.success());
+    let output = graphql(&["check", "--query", file.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+}
+
+#[test]
+fn minify() {
+    let file = temp_file("minify.graphql", "query Q {\n  a(x: 1) {\n    b\n  }\n}\n");
+    let output = graphql(&["minify", file.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "query Q{a(x:1){b}}\n");
+}
+
+#[test]
+fn usage() {
+    let output = graphql(&["frobnicate"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert!(stderr(&output).starts_with("graphql: unknown command `frobnicate`\nUsage:"));
+}
+
+#[test]
+fn diff() {
+    let old = temp_file("old.graphql", "type Query {\n  a: Int\n  b: Int\n}\n");
+    let new = temp_file("new.graphql", "type Query {\n  b: Int!\n  c: Int\n}\n");
+    let output = graphql(&["diff", old.to_str().unwrap(), new.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(1));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{old}:2:3: breaking: Field `Query.a` was removed\n\
+             {new}:2:3: safe: Field `Query.b` changed type from `Int` to `Int!`\n\
+             {new}:3:3: safe: Field `Query.c` was added\n",
+            old = old.display(),
+            new = new.display(),
+        )
+    );
+    let output = graphql(&["diff", new.to_str().unwrap(), new.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "");
+}
@@ -18,6 +18,7 @@ fn test_error(filename: &str) {
     let expected = iter.next().expect("file should contain error message");
     let err = parse_query::<String>(graphql).unwrap_err();
     assert_eq!(err.to_string(), expected);
+    assert!(expected.contains(&format!("Parse error at {}\n", err.position())));
 }
 
 #[test]
@@ -28,3 +29,11 @@ fn invalid_curly_brace() {
 fn bad_args() {
     test_error("bad_args");
 }
+#[test]
+fn lone_surrogate() {
+    test_error("lone_surrogate");
+}
+#[test]
+fn bare_dot() {
+    test_error("bare_dot");
+}
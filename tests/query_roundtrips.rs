@@ -6,7 +6,7 @@ extern crate pretty_assertions;
 use std::fs::File;
 use std::io::Read;
 
-use graphql_parser::{parse_query, Style};
+use graphql_parser::{parse_query, parse_value, Style};
 
 fn roundtrip_multiline_args(filename: &str) {
     roundtrip(filename, Style::default().multiline_arguments(true))
@@ -16,6 +16,14 @@ fn roundtrip_default(filename: &str) {
     roundtrip(filename, &Style::default())
 }
 
+fn roundtrip_max_width(filename: &str) {
+    roundtrip(filename, Style::default().max_width(80))
+}
+
+fn roundtrip_blank_lines(filename: &str) {
+    roundtrip(filename, Style::default().preserve_blank_lines(true))
+}
+
 fn roundtrip(filename: &str, style: &Style) {
     let mut buf = String::with_capacity(1024);
     let path = format!("tests/queries/{}.graphql", filename);
@@ -171,3 +179,82 @@ fn directive_args_multiline() {
 fn kitchen_sink() {
     roundtrip2("kitchen-sink");
 }
+#[test]
+fn subscription_nameless_vars() {
+    roundtrip_default("subscription_nameless_vars");
+}
+#[test]
+fn max_width() {
+    roundtrip_max_width("max_width");
+}
+#[test]
+fn minified() {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/queries/kitchen-sink.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_query::<String>(&buf).unwrap();
+    let minified = ast.format(&Style::minified());
+    assert_eq!(
+        minified,
+        r#"query queryName($foo:ComplexType$site:Site=MOBILE){whoever123is:node(id:[123 456]){id...on User@defer{field2{id alias:field1(first:10 after:$foo)@include(if:$foo){id...frag}}}...@skip(unless:$foo){id}...{id}}}mutation likeStory{like(story:123)@defer{story{id}}}subscription StoryLikeSubscription($input:StoryLikeSubscribeInput){storyLikeSubscribe(input:$input){story{likers{count}likeSentence{text}}}}fragment frag on Friend{foo(size:$size bar:$b obj:{block:"block string uses \"\"\""key:"value"})}{unnamed(truthy:true falsey:false nullish:null)query}"#
+    );
+    assert_eq!(
+        parse_query::<String>(&minified)
+            .unwrap()
+            .format(&Style::minified()),
+        minified
+    );
+}
+#[test]
+fn string_escapes() {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/queries/string_escapes.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    // Only texts borrowing from the source keep the original literals
+    let ast = parse_query::<&str>(&buf).unwrap();
+    assert_eq!(ast.to_string(), buf);
+    let ast = parse_query::<String>(&buf).unwrap();
+    assert_eq!(ast.to_string(), buf.replace(r#"\/"#, "/"));
+}
+#[test]
+fn sorted() {
+    let ast = parse_query::<String>(
+        "fragment b on T { x } query Q { b(z: 1, a: 2) @d(y: 1, x: 2) ...b } fragment a on T { x } { a }",
+    )
+    .unwrap();
+    assert_eq!(
+        ast.format(Style::default().sort_definitions(true).sort_arguments(true)),
+        "{\n  a\n}\n\nquery Q {\n  b(a: 2, z: 1) @d(x: 2, y: 1)\n  ...b\n}\n\n\
+         fragment a on T {\n  x\n}\n\nfragment b on T {\n  x\n}\n"
+    );
+}
+#[test]
+fn floats() {
+    for &(source, formatted) in &[
+        ("1e3", "1000.0"),
+        ("1.50", "1.5"),
+        ("2E-400", "0.0"),
+        ("1e300", "1e300"),
+    ] {
+        let value = parse_value::<String>(source).unwrap();
+        assert_eq!(value.to_string(), formatted);
+        assert_eq!(parse_value::<String>(formatted).unwrap(), value);
+    }
+}
+#[test]
+fn blank_lines() {
+    roundtrip_blank_lines("blank_lines");
+}
+#[test]
+fn blank_lines_removed() {
+    let ast = parse_query::<String>("{\n  a\n\n  b\n}\n").unwrap();
+    assert_eq!(ast.to_string(), "{\n  a\n  b\n}\n");
+}
+#[test]
+fn minified_token_separation() {
+    let ast = parse_query::<String>(r#"{ a(x: ["", "b"], y: -1, z: 1.5, e: E) }"#).unwrap();
+    assert_eq!(
+        ast.format(&Style::minified()),
+        r#"{a(x:["" "b"]y:-1 z:1.5 e:E)}"#
+    );
+}
@@ -0,0 +1,174 @@
+extern crate graphql_parser;
+#[cfg(test)]
+#[macro_use]
+extern crate pretty_assertions;
+
+use graphql_parser::{format_query_range, format_schema_range, line_range, Style, TextEdit};
+use graphql_parser::{skipped_query_parts, skipped_schema_definitions, Pos};
+
+fn apply(source: &str, edits: &[TextEdit]) -> String {
+    let mut result = source.to_string();
+    for edit in edits.iter().rev() {
+        result.replace_range(edit.range.clone(), &edit.new_text);
+    }
+    result
+}
+
+fn query(source: &str, range: std::ops::Range<usize>) -> String {
+    let edits = format_query_range(source, range, &Style::default()).unwrap();
+    apply(source, &edits)
+}
+
+fn schema(source: &str, range: std::ops::Range<usize>) -> String {
+    let edits = format_schema_range(source, range, &Style::default()).unwrap();
+    apply(source, &edits)
+}
+
+const QUERY: &str = "query A {a    b}\n\n# keep   this\nquery  B{ c  { d   e {f} } }\n";
+
+#[test]
+fn query_definition() {
+    let start = QUERY.find("query  B").unwrap();
+    assert_eq!(
+        query(QUERY, start..start + 5),
+        "query A {a    b}\n\n# keep   this\nquery B {\n  c {\n    d\n    e {\n      f\n    }\n  }\n}\n"
+    );
+    assert_eq!(
+        query(QUERY, 0..QUERY.len()),
+        "query A {\n  a\n  b\n}\n\n# keep   this\nquery B {\n  c {\n    d\n    e {\n      f\n    }\n  }\n}\n"
+    );
+}
+
+#[test]
+fn query_cursor() {
+    let cursor = QUERY.find('b').unwrap();
+    assert_eq!(
+        query(QUERY, cursor..cursor),
+        "query A {\n  a\n  b\n}\n\n# keep   this\nquery  B{ c  { d   e {f} } }\n"
+    );
+}
+
+#[test]
+fn query_nested_selection_set() {
+    let source = "query {\n  a {\n    b   c {d}\n  }\n  x  {y}\n}\n";
+    let cursor = source.find('d').unwrap();
+    assert_eq!(
+        query(source, cursor..cursor),
+        "query {\n  a {\n    b   c {\n      d\n    }\n  }\n  x  {y}\n}\n"
+    );
+    let start = source.find('b').unwrap();
+    assert_eq!(
+        query(source, start..cursor),
+        "query {\n  a {\n    b\n    c {\n      d\n    }\n  }\n  x  {y}\n}\n"
+    );
+}
+
+#[test]
+fn query_comments() {
+    let source = "{ a # comment\n  b }";
+    let edits = format_query_range(source, 0..source.len(), &Style::default()).unwrap();
+    assert_eq!(edits, vec![]);
+    let source = "{ a { b # comment\n  c } d { e  f } }";
+    let cursor = source.find('f').unwrap();
+    assert_eq!(
+        query(source, cursor..cursor),
+        "{ a { b # comment\n  c } d {\n    e\n    f\n  } }"
+    );
+}
+
+#[test]
+fn query_skipped() {
+    let source = "{ a }\n{ b # comment\n  c { d # comment\n e } }";
+    assert_eq!(
+        skipped_query_parts(source, 0..source.len()).unwrap(),
+        vec![Pos { line: 2, column: 1 }]
+    );
+    let cursor = source.find(" e ").unwrap() + 1;
+    assert_eq!(
+        skipped_query_parts(source, cursor..cursor).unwrap(),
+        vec![Pos { line: 3, column: 5 }]
+    );
+    assert_eq!(skipped_query_parts(source, 0..1).unwrap(), vec![]);
+}
+
+#[test]
+fn query_formatted() {
+    let source = "{\n  a\n}\n";
+    let edits = format_query_range(source, 0..source.len(), &Style::default()).unwrap();
+    assert_eq!(edits, vec![]);
+}
+
+#[test]
+fn schema_definitions() {
+    let source = "type  A {a:Int}\n\"descr\"   type B{b:Int}\nextend  type   B @x\n";
+    let lines = line_range(source, 2, 3);
+    assert_eq!(
+        &source[lines.clone()],
+        "\"descr\"   type B{b:Int}\nextend  type   B @x"
+    );
+    assert_eq!(
+        schema(source, lines),
+        "type  A {a:Int}\n\"descr\"\ntype B {\n  b: Int\n}\n\nextend type B @x\n"
+    );
+    let cursor = source.find("extend").unwrap() + 2;
+    assert_eq!(
+        schema(source, cursor..cursor),
+        "type  A {a:Int}\n\"descr\"   type B{b:Int}\nextend type B @x\n"
+    );
+}
+
+#[test]
+fn schema_blank_lines() {
+    let source = "type A {\n  a: Int\n}\n\n\n\nscalar B\nscalar C\n# comment\n\n\nscalar D\n";
+    assert_eq!(
+        schema(source, 0..source.len()),
+        "type A {\n  a: Int\n}\n\nscalar B\n\nscalar C\n# comment\n\n\nscalar D\n"
+    );
+    let cursor = source.find('C').unwrap();
+    assert_eq!(schema(source, cursor..cursor), source);
+}
+
+#[test]
+fn schema_skipped() {
+    let source = "type A {\n  a: Int # comment\n}\n\"descr\" # comment\nscalar B\nscalar C\n";
+    assert_eq!(
+        skipped_schema_definitions(source, 0..source.len()).unwrap(),
+        vec![Pos { line: 1, column: 1 }, Pos { line: 4, column: 1 }]
+    );
+}
+
+#[test]
+fn lines() {
+    let source = "a\nbc\n\nd";
+    assert_eq!(line_range(source, 1, 1), 0..1);
+    assert_eq!(line_range(source, 2, 3), 2..5);
+    assert_eq!(line_range(source, 4, 10), 6..7);
+    assert_eq!(line_range(source, 10, 12), 7..7);
+}
+
+#[test]
+fn blank_lines_after_multiline_arguments() {
+    let style = Style::default().preserve_blank_lines(true).clone();
+    let source = "{\n  a(x: {\n    y: 1\n  })\n  b\n\n  ...F @d(x: [\n    1\n  ])\n  c\n}\n";
+    let edits = format_query_range(source, 0..source.len(), &style).unwrap();
+    assert_eq!(
+        apply(source, &edits),
+        "{\n  a(x: {y: 1})\n  b\n\n  ...F @d(x: [1])\n  c\n}\n"
+    );
+}
+
+#[test]
+fn blank_lines_after_multiline_values() {
+    let style = Style::default().preserve_blank_lines(true).clone();
+    let source = "input Filter {\n  range: Range = {\n    min: 1\n  }\n  name: String\n\n  \
+                  limit: Int\n}\n\ntype Query {\n  users(filter: Filter = {\n    name: \"x\"\n  \
+                  }): [User]\n  count: Int @deprecated(reason: \"\"\"\n  Use\n  total\n  \"\"\")\n  \
+                  id: ID\n}\n";
+    let edits = format_schema_range(source, 0..source.len(), &style).unwrap();
+    assert_eq!(
+        apply(source, &edits),
+        "input Filter {\n  range: Range = {min: 1}\n  name: String\n\n  limit: Int\n}\n\n\
+         type Query {\n  users(filter: Filter = {name: \"x\"}): [User]\n  \
+         count: Int @deprecated(reason: \"\"\"\n    Use\n    total\n  \"\"\")\n  id: ID\n}\n"
+    );
+}
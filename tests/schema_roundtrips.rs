@@ -6,7 +6,7 @@ extern crate pretty_assertions;
 use std::fs::File;
 use std::io::Read;
 
-use graphql_parser::parse_schema;
+use graphql_parser::{parse_schema, Style};
 
 fn roundtrip(filename: &str) {
     let mut buf = String::with_capacity(1024);
@@ -17,6 +17,24 @@ fn roundtrip(filename: &str) {
     assert_eq!(ast.to_string(), buf);
 }
 
+fn roundtrip_max_width(filename: &str) {
+    let mut buf = String::with_capacity(1024);
+    let path = format!("tests/schemas/{}.graphql", filename);
+    let mut f = File::open(path).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_schema::<String>(&buf).unwrap();
+    assert_eq!(ast.format(Style::default().max_width(80)), buf);
+}
+
+fn roundtrip_blank_lines(filename: &str) {
+    let mut buf = String::with_capacity(1024);
+    let path = format!("tests/schemas/{}.graphql", filename);
+    let mut f = File::open(path).unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_schema::<String>(&buf).unwrap();
+    assert_eq!(ast.format(Style::default().preserve_blank_lines(true)), buf);
+}
+
 fn roundtrip2(filename: &str) {
     let mut buf = String::with_capacity(1024);
     let source = format!("tests/schemas/{}.graphql", filename);
@@ -123,3 +141,132 @@ fn directive_variable_definition() {
 fn repeatable() {
     roundtrip("repeatable")
 }
+#[test]
+fn max_width() {
+    roundtrip_max_width("max_width");
+}
+#[test]
+fn blank_lines() {
+    roundtrip_blank_lines("blank_lines");
+}
+#[test]
+fn blank_lines_in_kitchen_sink() {
+    roundtrip_blank_lines("kitchen-sink_canonical");
+}
+#[test]
+fn block_strings_reindented() {
+    let source =
+        "type User {\r\n  \"\"\"\r\n        Name of\r\n          the user\r\n        \"\"\"\r\n  \
+                  name(\"\"\"\r\n  First\r\n  only\r\n  \"\"\" first: Boolean): String\r\n}\r\n";
+    let ast = parse_schema::<&str>(source).unwrap();
+    assert_eq!(
+        ast.format(Style::default().indent(4)),
+        "type User {\n    \"\"\"\n        Name of\n          the user\n    \"\"\"\n    \
+         name(\"\"\"\n        First\n        only\n    \"\"\" first: Boolean): String\n}\n"
+    );
+}
+
+fn minify(style: &Style) -> String {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/schemas/kitchen-sink.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let minified = parse_schema::<String>(&buf).unwrap().format(style);
+    // must be valid
+    parse_schema::<String>(&minified).unwrap();
+    minified
+}
+
+#[test]
+fn minified() {
+    assert_eq!(
+        minify(&Style::minified()),
+        r#"schema{query:QueryType mutation:MutationType}"This is a description\nof the `Foo` type."type Foo implements Bar&Baz{one:Type two(argument:InputType!):Type three(argument:InputType other:String):Int four(argument:String="string"):String five(argument:[String]=["string" "string"]):String six(argument:InputType={key:"value"}):Type seven(argument:Int=null):Type}type AnnotatedObject@onObject(arg:"value"){annotatedField(arg:Type="default"@onArg):Type@onField}type UndefinedType extend type Foo{seven(argument:[String]):Type}extend type Foo@onType interface Bar{one:Type four(argument:String="string"):String}interface AnnotatedInterface@onInterface{annotatedField(arg:Type@onArg):Type@onField}interface UndefinedInterface extend interface Bar{two(argument:InputType!):Type}extend interface Bar@onInterface union Feed=Story|Article|Advert union AnnotatedUnion@onUnion=A|B union AnnotatedUnionTwo@onUnion=A|B union UndefinedUnion extend union Feed=Photo|Video extend union Feed@onUnion scalar CustomScalar scalar AnnotatedScalar@onScalar extend scalar CustomScalar@onScalar enum Site{DESKTOP MOBILE}enum AnnotatedEnum@onEnum{ANNOTATED_VALUE@onEnumValue OTHER_VALUE}enum UndefinedEnum extend enum Site{VR}extend enum Site@onEnum input InputType{key:String!answer:Int=42}input AnnotatedInput@onInputObject{annotatedField:Type@onField}input UndefinedInput extend input InputType{other:Float=12300.0}extend input InputType@onInputObject directive@skip(if:Boolean!)on FIELD|FRAGMENT_SPREAD|INLINE_FRAGMENT directive@include(if:Boolean!)on FIELD|FRAGMENT_SPREAD|INLINE_FRAGMENT directive@include2(if:Boolean!)on FIELD|FRAGMENT_SPREAD|INLINE_FRAGMENT"#
+    );
+}
+
+#[test]
+fn minified_without_descriptions_and_directives() {
+    assert_eq!(
+        minify(Style::minified().descriptions(false).directives(false)),
+        r#"schema{query:QueryType mutation:MutationType}type Foo implements Bar&Baz{one:Type two(argument:InputType!):Type three(argument:InputType other:String):Int four(argument:String="string"):String five(argument:[String]=["string" "string"]):String six(argument:InputType={key:"value"}):Type seven(argument:Int=null):Type}type AnnotatedObject{annotatedField(arg:Type="default"):Type}type UndefinedType extend type Foo{seven(argument:[String]):Type}interface Bar{one:Type four(argument:String="string"):String}interface AnnotatedInterface{annotatedField(arg:Type):Type}interface UndefinedInterface extend interface Bar{two(argument:InputType!):Type}union Feed=Story|Article|Advert union AnnotatedUnion=A|B union AnnotatedUnionTwo=A|B union UndefinedUnion extend union Feed=Photo|Video scalar CustomScalar scalar AnnotatedScalar enum Site{DESKTOP MOBILE}enum AnnotatedEnum{ANNOTATED_VALUE OTHER_VALUE}enum UndefinedEnum extend enum Site{VR}input InputType{key:String!answer:Int=42}input AnnotatedInput{annotatedField:Type}input UndefinedInput extend input InputType{other:Float=12300.0}directive@skip(if:Boolean!)on FIELD|FRAGMENT_SPREAD|INLINE_FRAGMENT directive@include(if:Boolean!)on FIELD|FRAGMENT_SPREAD|INLINE_FRAGMENT directive@include2(if:Boolean!)on FIELD|FRAGMENT_SPREAD|INLINE_FRAGMENT"#
+    );
+}
+
+#[test]
+fn sorted() {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/schemas/kitchen-sink.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let ast = parse_schema::<String>(&buf).unwrap();
+
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/schemas/kitchen-sink_sorted.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let mut style = Style::default();
+    style
+        .sort_definitions(true)
+        .group_extensions(true)
+        .sort_fields(true)
+        .sort_arguments(true);
+    assert_eq!(ast.format(&style), buf);
+}
+
+#[test]
+fn sorted_extensions() {
+    let ast = parse_schema::<String>(
+        "extend type B @b extend type X @x type B scalar A extend scalar A @a directive @d on FIELD",
+    )
+    .unwrap();
+    assert_eq!(
+        ast.format(Style::default().group_extensions(true)),
+        "extend type X @x\n\ntype B\n\nextend type B @b\n\nscalar A\n\nextend scalar A @a\n\n\
+         directive @d on FIELD\n"
+    );
+    assert_eq!(
+        ast.format(Style::default().sort_definitions(true)),
+        "scalar A\n\ntype B\n\ndirective @d on FIELD\n\nextend scalar A @a\n\n\
+         extend type B @b\n\nextend type X @x\n"
+    );
+}
+
+#[test]
+fn streaming() {
+    let mut buf = String::with_capacity(1024);
+    let mut f = File::open("tests/schemas/kitchen-sink.graphql").unwrap();
+    f.read_to_string(&mut buf).unwrap();
+    let single = parse_schema::<String>(&buf).unwrap();
+    // Larger than the output buffer
+    let buf = buf.repeat(20);
+    let ast = parse_schema::<String>(&buf).unwrap();
+    for (style, separator) in &[
+        (Style::default(), "\n"),
+        (Style::default().max_width(40).clone(), "\n"),
+        (Style::minified(), " "),
+    ] {
+        let expected = vec![single.format(style); 20].join(separator);
+        assert_eq!(ast.format(style), expected);
+        let mut string = String::new();
+        ast.format_to(style, &mut string).unwrap();
+        assert_eq!(string, expected);
+        let mut bytes = Vec::new();
+        ast.write_to(style, &mut bytes).unwrap();
+        assert_eq!(String::from_utf8(bytes).unwrap(), expected);
+    }
+    assert_eq!(format!("{}", ast), ast.format(&Style::default()));
+}
+
+#[test]
+fn streaming_error() {
+    struct Failing;
+    impl std::io::Write for Failing {
+        fn write(&mut self, _: &[u8]) -> std::io::Result<usize> {
+            Err(std::io::Error::new(std::io::ErrorKind::Other, "disk full"))
+        }
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+    let ast = parse_schema::<String>("type Query { a: Int }").unwrap();
+    let err = ast.write_to(&Style::default(), &mut Failing).unwrap_err();
+    assert_eq!(err.to_string(), "disk full");
+}